@@ -5,7 +5,7 @@
 
 use io_utils::open_for_read;
 use std::{assert, env, format, i32, io::BufRead, str};
-use string_utils::TextUtils;
+use string_utils::{parse_kv_map, TextUtils};
 use vector_utils::unique_sort;
 
 pub fn fetch_exons(species: &str, exons: &mut Vec<(String, i32, i32, bool, String, i32)>) {
@@ -50,21 +50,13 @@ pub fn fetch_exons(species: &str, exons: &mut Vec<(String, i32, i32, bool, Strin
         if fields.len() < 9 {
             continue;
         }
-        let fields8: Vec<&str> = fields[8].split_terminator(';').collect();
-        if fields8.len() < 6 {
-            continue;
-        }
-        if !fields8[4].contains("exon_number") {
-            continue;
-        }
-        if !fields8[5].contains("gene_name") {
-            continue;
-        }
-        let exon = fields8[4].between("\"", "\"").force_i32();
-        let gene = fields8[5].between("\"", "\"");
+        let attrs = parse_kv_map(fields[8], ';', ' ');
+        let (exon, gene) = match (attrs.get("exon_number"), attrs.get("gene_name")) {
+            (Some(e), Some(g)) => (e[0].force_i32(), &g[0]),
+            _ => continue,
+        };
         // println!( "" );
         // for j in 0..fields.len() { println!( "{}: {}", j, fields[j] ); }
-        // for j in 0..fields8.len() { println!( "8.{}: {}", j, fields8[j] ); }
         let chr = fields[0];
         let (start, stop) = (fields[3].force_i32(), fields[4].force_i32());
         let mut fw = false;
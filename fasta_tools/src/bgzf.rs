@@ -0,0 +1,371 @@
+// Copyright (c) 2018 10x Genomics, Inc. All rights reserved.
+
+// Reading and writing of BGZF files, as made by bgzip.
+//
+// A BGZF file is a series of gzip members, called blocks, each holding at most 64 KB of
+// data, and carrying its own compressed size in a BC extra subfield.  Ordinary gzip
+// readers such as MultiGzDecoder therefore read BGZF files transparently, but in addition a
+// BGZF file can be read from the start of any block, without decompressing what precedes it.
+//
+// A position in a BGZF file is described by a virtual offset, which is the offset in the file
+// of the start of a block, shifted left 16 bits, plus an offset within the uncompressed data
+// of the block.  A .gzi index, as made by bgzip -i, gives the compressed and uncompressed
+// offsets of the start of every block but the first, allowing seeking to an uncompressed
+// position.
+
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use flate2::{Compression, Crc};
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+// The largest amount of data that bgzip puts in a block, chosen so that the compressed block
+// fits in 64 KB even if the data are incompressible.
+
+pub const BGZF_BLOCK_SIZE: usize = 0xff00;
+
+// The empty block that marks the end of a BGZF file.
+
+const BGZF_EOF: [u8; 28] = [
+    0x1f, 0x8b, 0x08, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0x06, 0x00, 0x42, 0x43, 0x02, 0x00,
+    0x1b, 0x00, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+];
+
+fn invalid(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+// Given the first 12 bytes of a gzip member, and its extra field, return the total size of
+// the block if it is a BGZF block.
+
+fn bgzf_block_size(head: &[u8], extra: &[u8]) -> Option<usize> {
+    if head[0..4] != [0x1f, 0x8b, 0x08, 0x04] {
+        return None;
+    }
+    let mut i = 0;
+    while i + 4 <= extra.len() {
+        let slen = u16::from_le_bytes([extra[i + 2], extra[i + 3]]) as usize;
+        if extra[i] == b'B' && extra[i + 1] == b'C' && slen == 2 && i + 6 <= extra.len() {
+            return Some(u16::from_le_bytes([extra[i + 4], extra[i + 5]]) as usize + 1);
+        }
+        i += 4 + slen;
+    }
+    None
+}
+
+// Determine if a file is BGZF, by looking at its first block header.
+
+pub fn is_bgzf(f: impl AsRef<Path>) -> io::Result<bool> {
+    let mut head = Vec::new();
+    File::open(f)?.take(512).read_to_end(&mut head)?;
+    if head.len() < 12 {
+        return Ok(false);
+    }
+    let xlen = u16::from_le_bytes([head[10], head[11]]) as usize;
+    let extra = &head[12..head.len().min(12 + xlen)];
+    Ok(bgzf_block_size(&head, extra).is_some())
+}
+
+// ▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓
+
+// A reader for BGZF data, which can seek to virtual offsets.
+
+pub struct BgzfReader<R: Read + Seek> {
+    inner: R,
+    block: Vec<u8>,
+    pos: usize,
+    block_start: u64,
+    next_block: u64,
+    eof: bool,
+}
+
+impl<R: Read + Seek> BgzfReader<R> {
+    pub fn new(inner: R) -> BgzfReader<R> {
+        BgzfReader {
+            inner,
+            block: Vec::new(),
+            pos: 0,
+            block_start: 0,
+            next_block: 0,
+            eof: false,
+        }
+    }
+
+    // Load the block that starts at the given offset in the underlying data.  At the end of
+    // the data, there is no block, and eof is set.
+
+    fn load_block(&mut self, start: u64) -> io::Result<()> {
+        self.inner.seek(SeekFrom::Start(start))?;
+        self.block.clear();
+        self.pos = 0;
+        self.block_start = start;
+        let mut head = [0u8; 12];
+        let n = (&mut self.inner).take(12).read(&mut head)?;
+        if n == 0 {
+            self.eof = true;
+            self.next_block = start;
+            return Ok(());
+        }
+        self.inner.read_exact(&mut head[n..])?;
+        let xlen = u16::from_le_bytes([head[10], head[11]]) as usize;
+        let mut extra = vec![0u8; xlen];
+        self.inner.read_exact(&mut extra)?;
+        let bsize = bgzf_block_size(&head, &extra)
+            .ok_or_else(|| invalid(format!("no BGZF block at offset {}", start)))?;
+        if bsize < 12 + xlen + 8 {
+            return Err(invalid(format!("bad BGZF block size at offset {}", start)));
+        }
+        let mut rest = vec![0u8; bsize - 12 - xlen];
+        self.inner.read_exact(&mut rest)?;
+        let (cdata, tail) = rest.split_at(rest.len() - 8);
+        DeflateDecoder::new(cdata).read_to_end(&mut self.block)?;
+        let crc = u32::from_le_bytes([tail[0], tail[1], tail[2], tail[3]]);
+        let isize = u32::from_le_bytes([tail[4], tail[5], tail[6], tail[7]]);
+        let mut c = Crc::new();
+        c.update(&self.block);
+        if c.sum() != crc || self.block.len() as u32 != isize {
+            return Err(invalid(format!("corrupt BGZF block at offset {}", start)));
+        }
+        self.eof = false;
+        self.next_block = start + bsize as u64;
+        Ok(())
+    }
+
+    // Return the virtual offset of the next byte to be read.
+
+    pub fn virtual_offset(&self) -> u64 {
+        if self.pos == self.block.len() && !self.block.is_empty() {
+            self.next_block << 16
+        } else {
+            (self.block_start << 16) | self.pos as u64
+        }
+    }
+
+    // Seek to a virtual offset.
+
+    pub fn seek_virtual(&mut self, voffset: u64) -> io::Result<()> {
+        self.load_block(voffset >> 16)?;
+        let pos = (voffset & 0xffff) as usize;
+        if pos > self.block.len() {
+            return Err(invalid(format!(
+                "virtual offset {} is past its block",
+                voffset
+            )));
+        }
+        self.pos = pos;
+        Ok(())
+    }
+
+    // Seek to a position in the uncompressed data, using a .gzi index, which lists
+    // (compressed offset, uncompressed offset) for each block but the first.
+
+    pub fn seek_uncompressed(&mut self, gzi: &[(u64, u64)], upos: u64) -> io::Result<()> {
+        let i = gzi.partition_point(|&(_, u)| u <= upos);
+        let (c, u) = if i == 0 { (0, 0) } else { gzi[i - 1] };
+        self.load_block(c)?;
+        let mut skip = upos - u;
+        while skip > 0 {
+            let n = (self.block.len() as u64).min(skip);
+            skip -= n;
+            self.pos = n as usize;
+            if skip > 0 {
+                if self.eof {
+                    return Err(invalid(format!("position {} is past the end", upos)));
+                }
+                self.load_block(self.next_block)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read + Seek> Read for BgzfReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        // Skip over exhausted and empty blocks, such as the end of file marker.
+
+        while self.pos == self.block.len() {
+            if self.eof {
+                return Ok(0);
+            }
+            self.load_block(self.next_block)?;
+        }
+        let n = buf.len().min(self.block.len() - self.pos);
+        buf[..n].copy_from_slice(&self.block[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+// ▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓
+
+// A writer of BGZF data, which records the .gzi index of the blocks it writes.  Data are
+// written in blocks of BGZF_BLOCK_SIZE bytes, or the given block size, and also a block is
+// ended whenever flush is called.  Call finish to write the end of file marker.
+
+pub struct BgzfWriter<W: Write> {
+    inner: W,
+    buf: Vec<u8>,
+    block_size: usize,
+    coffset: u64,
+    uoffset: u64,
+    index: Vec<(u64, u64)>,
+}
+
+impl<W: Write> BgzfWriter<W> {
+    pub fn new(inner: W) -> BgzfWriter<W> {
+        BgzfWriter::with_block_size(inner, BGZF_BLOCK_SIZE)
+    }
+
+    pub fn with_block_size(inner: W, block_size: usize) -> BgzfWriter<W> {
+        assert!(block_size > 0 && block_size <= BGZF_BLOCK_SIZE);
+        BgzfWriter {
+            inner,
+            buf: Vec::new(),
+            block_size,
+            coffset: 0,
+            uoffset: 0,
+            index: Vec::new(),
+        }
+    }
+
+    fn write_block(&mut self) -> io::Result<()> {
+        if self.buf.is_empty() {
+            return Ok(());
+        }
+        let mut e = DeflateEncoder::new(Vec::new(), Compression::default());
+        e.write_all(&self.buf)?;
+        let cdata = e.finish()?;
+        let bsize = 18 + cdata.len() + 8;
+        let mut head = BGZF_EOF[..16].to_vec();
+        head.extend(((bsize - 1) as u16).to_le_bytes());
+        let mut c = Crc::new();
+        c.update(&self.buf);
+        if self.coffset > 0 {
+            self.index.push((self.coffset, self.uoffset));
+        }
+        self.inner.write_all(&head)?;
+        self.inner.write_all(&cdata)?;
+        self.inner.write_all(&c.sum().to_le_bytes())?;
+        self.inner
+            .write_all(&(self.buf.len() as u32).to_le_bytes())?;
+        self.coffset += bsize as u64;
+        self.uoffset += self.buf.len() as u64;
+        self.buf.clear();
+        Ok(())
+    }
+
+    // Finish writing, returning the underlying writer and the .gzi index.
+
+    pub fn finish(mut self) -> io::Result<(W, Vec<(u64, u64)>)> {
+        self.write_block()?;
+        self.inner.write_all(&BGZF_EOF)?;
+        self.inner.flush()?;
+        Ok((self.inner, self.index))
+    }
+}
+
+impl<W: Write> Write for BgzfWriter<W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        let n = data.len().min(self.block_size - self.buf.len());
+        self.buf.extend_from_slice(&data[..n]);
+        if self.buf.len() == self.block_size {
+            self.write_block()?;
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.write_block()?;
+        self.inner.flush()
+    }
+}
+
+// ▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓
+
+// Read and write .gzi files, which consist of the number of entries, followed by the
+// entries, each a compressed and uncompressed offset, all as little-endian u64s.
+
+pub fn read_gzi(f: impl AsRef<Path>) -> io::Result<Vec<(u64, u64)>> {
+    let mut r = BufReader::new(File::open(f)?);
+    let mut next = || -> io::Result<u64> {
+        let mut b = [0u8; 8];
+        r.read_exact(&mut b)?;
+        Ok(u64::from_le_bytes(b))
+    };
+    let n = next()?;
+    let mut gzi = Vec::new();
+    for _ in 0..n {
+        gzi.push((next()?, next()?));
+    }
+    Ok(gzi)
+}
+
+pub fn write_gzi(f: impl AsRef<Path>, gzi: &[(u64, u64)]) -> io::Result<()> {
+    let mut w = BufWriter::new(File::create(f)?);
+    w.write_all(&(gzi.len() as u64).to_le_bytes())?;
+    for &(c, u) in gzi.iter() {
+        w.write_all(&c.to_le_bytes())?;
+        w.write_all(&u.to_le_bytes())?;
+    }
+    w.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_bgzf_round_trip() {
+        let data = (0..10_000u32)
+            .map(|i| (i * 7 % 251) as u8)
+            .collect::<Vec<u8>>();
+        let mut w = BgzfWriter::with_block_size(Vec::new(), 1000);
+        w.write_all(&data).unwrap();
+        let (bytes, gzi) = w.finish().unwrap();
+        assert_eq!(gzi.len(), 9);
+        assert!(gzi
+            .iter()
+            .enumerate()
+            .all(|(i, &(_, u))| u == 1000 * (i as u64 + 1)));
+        assert!(bytes.ends_with(&BGZF_EOF));
+
+        // Read everything, with both a BgzfReader and an ordinary gzip reader.
+
+        let mut x = Vec::new();
+        BgzfReader::new(Cursor::new(&bytes))
+            .read_to_end(&mut x)
+            .unwrap();
+        assert_eq!(x, data);
+        let mut y = Vec::new();
+        flate2::read::MultiGzDecoder::new(&bytes[..])
+            .read_to_end(&mut y)
+            .unwrap();
+        assert_eq!(y, data);
+
+        // Seek to uncompressed positions, including block boundaries and the end.
+
+        let mut r = BgzfReader::new(Cursor::new(&bytes));
+        for upos in [0, 1, 999, 1000, 1001, 5500, 9999, 10_000] {
+            r.seek_uncompressed(&gzi, upos).unwrap();
+            let n = 5.min(10_000 - upos as usize);
+            let mut z = vec![0u8; n];
+            r.read_exact(&mut z).unwrap();
+            assert_eq!(z, &data[upos as usize..upos as usize + n]);
+        }
+        assert!(r.seek_uncompressed(&gzi, 10_001).is_err());
+
+        // Seek to virtual offsets.
+
+        r.seek_uncompressed(&gzi, 2500).unwrap();
+        let v = r.virtual_offset();
+        assert_eq!(v, (gzi[1].0 << 16) | 500);
+        let mut z = vec![0u8; 700];
+        r.read_exact(&mut z).unwrap();
+        assert_eq!(z, &data[2500..3200]);
+        r.seek_virtual(v).unwrap();
+        r.read_exact(&mut z).unwrap();
+        assert_eq!(z, &data[2500..3200]);
+    }
+}
@@ -1,15 +1,209 @@
-// Fetch a given GenBank accession.
+// Fetch GenBank accessions.
+//
+// usage: fetch_gb [-o|--output FILE] [--rettype fasta|gb] [--retry N] ACCESSION...
+//
+// The records are written in the order given, to FILE, or by default to stdout.  If any
+// accession cannot be fetched, the others are still written, the failures are listed, and
+// the exit status is nonzero.
 
-use fasta_tools::load_genbank_accession_as_fasta_bytes;
+use fasta_tools::genbank::{fetch_genbank_records, GbFetchOptions, GbFormat};
 use std::env;
 use std::fs::File;
-use std::io::Write;
+use std::io::{BufWriter, Write};
+use std::process::exit;
+
+const USAGE: &str = "usage: fetch_gb [-o|--output FILE] [--rettype fasta|gb] [--retry N] \
+                     ACCESSION...";
+
+#[derive(Debug, PartialEq)]
+struct Args {
+    accs: Vec<String>,
+    output: Option<String>,
+    format: GbFormat,
+    retries: Option<usize>,
+}
+
+fn parse_args(args: &[String]) -> Result<Args, String> {
+    let mut x = Args {
+        accs: Vec::new(),
+        output: None,
+        format: GbFormat::Fasta,
+        retries: None,
+    };
+    let mut i = 0;
+    while i < args.len() {
+        let arg = &args[i];
+        if arg.starts_with('-') {
+            let value = args
+                .get(i + 1)
+                .ok_or_else(|| format!("{} requires a value", arg))?;
+            match arg.as_str() {
+                "-o" | "--output" => x.output = Some(value.clone()),
+                "--rettype" => x.format = value.parse::<GbFormat>()?,
+                "--retry" => {
+                    let n = value
+                        .parse::<usize>()
+                        .map_err(|_| format!("--retry requires a number, not {}", value))?;
+                    x.retries = Some(n);
+                }
+                _ => return Err(format!("unknown option {}", arg)),
+            }
+            i += 2;
+        } else {
+            x.accs.push(arg.clone());
+            i += 1;
+        }
+    }
+    if x.accs.is_empty() {
+        return Err("no accessions were given".to_string());
+    }
+    Ok(x)
+}
+
+// Fetch the records and write those that were found, returning the accessions that could not
+// be fetched, each with the reason.  If a batched request fails, the accessions are fetched
+// one at a time, so that the failures can be attributed.
+
+fn run<W: Write>(
+    args: &Args,
+    opts: &GbFetchOptions,
+    out: &mut W,
+) -> std::io::Result<Vec<(String, String)>> {
+    let accs = args.accs.iter().map(|a| a.as_str()).collect::<Vec<_>>();
+    let results = match fetch_genbank_records(&accs, args.format, opts) {
+        Ok(records) => records.into_iter().map(Ok).collect::<Vec<_>>(),
+        Err(_) if accs.len() > 1 => accs
+            .iter()
+            .map(|acc| {
+                fetch_genbank_records(&[acc], args.format, opts)
+                    .map(|mut r| r.pop().unwrap())
+                    .map_err(|e| e.to_string())
+            })
+            .collect(),
+        Err(e) => vec![Err(e.to_string())],
+    };
+    let mut failed = Vec::new();
+    for (acc, r) in accs.iter().zip(results) {
+        match r {
+            Ok(Some(record)) => out.write_all(record.as_bytes())?,
+            Ok(None) => failed.push((acc.to_string(), "no record was returned".to_string())),
+            Err(e) => failed.push((acc.to_string(), e)),
+        }
+    }
+    out.flush()?;
+    Ok(failed)
+}
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    let acc = &args[1];
-    let mut bytes = Vec::<u8>::new();
-    load_genbank_accession_as_fasta_bytes(&acc, &mut bytes);
-    let mut file = File::create(&acc).unwrap();
-    file.write_all(&bytes).unwrap();
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    let args = parse_args(&args).unwrap_or_else(|e| {
+        eprintln!("fetch_gb: {}\n{}", e, USAGE);
+        exit(2);
+    });
+    let mut opts = GbFetchOptions::default();
+    if let Some(n) = args.retries {
+        opts.retries = n;
+    }
+    let failed = match &args.output {
+        Some(f) => {
+            let file = File::create(f).unwrap_or_else(|e| {
+                eprintln!("fetch_gb: could not create {}: {}", f, e);
+                exit(1);
+            });
+            run(&args, &opts, &mut BufWriter::new(file))
+        }
+        None => run(&args, &opts, &mut std::io::stdout().lock()),
+    };
+    let failed = failed.unwrap_or_else(|e| {
+        eprintln!("fetch_gb: write failed: {}", e);
+        exit(1);
+    });
+    if !failed.is_empty() {
+        for (acc, e) in failed.iter() {
+            eprintln!("fetch_gb: failed to fetch {}: {}", acc, e);
+        }
+        exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(x: &[&str]) -> Vec<String> {
+        x.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let x = parse_args(&strings(&[
+            "AB1",
+            "-o",
+            "out.gb",
+            "X2.1",
+            "--rettype",
+            "gb",
+        ]))
+        .unwrap();
+        assert_eq!(
+            x,
+            Args {
+                accs: strings(&["AB1", "X2.1"]),
+                output: Some("out.gb".to_string()),
+                format: GbFormat::GenBank,
+                retries: None,
+            }
+        );
+        let x = parse_args(&strings(&["--retry", "5", "--output", "a.fa", "AB1"])).unwrap();
+        assert_eq!((x.retries, x.format), (Some(5), GbFormat::Fasta));
+        assert_eq!(x.output.as_deref(), Some("a.fa"));
+        assert!(parse_args(&[]).is_err());
+        assert!(parse_args(&strings(&["-o", "a.fa"])).is_err());
+        assert!(parse_args(&strings(&["AB1", "-o"])).is_err());
+        assert!(parse_args(&strings(&["AB1", "--rettype", "genbank"])).is_err());
+        assert!(parse_args(&strings(&["AB1", "--retry", "x"])).is_err());
+        assert!(parse_args(&strings(&["AB1", "--verbose", "1"])).is_err());
+    }
+
+    // This test needs the mock_http feature, as in
+    // cargo test -p fasta_tools --features mock_http
+
+    #[cfg(feature = "mock_http")]
+    #[test]
+    fn test_run() {
+        use fasta_tools::mock_http::{mock_server, response};
+        let mock =
+            |r: Vec<(&str, &str)>| mock_server(r.iter().map(|(s, b)| response(s, "", b)).collect());
+        let args = parse_args(&strings(&["KX1", "AB1", "Z9"])).unwrap();
+
+        // One batched request, which lacks Z9.
+
+        let opts = GbFetchOptions {
+            base_url: mock(vec![("200 OK", ">AB1.1 b\nAC\n>KX1.2 k\nTT\n")]),
+            ..Default::default()
+        };
+        let mut out = Vec::<u8>::new();
+        let failed = run(&args, &opts, &mut out).unwrap();
+        assert_eq!(out, b">KX1.2 k\nTT\n>AB1.1 b\nAC\n");
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].0, "Z9");
+
+        // The batched request fails, so the accessions are fetched one at a time.
+
+        let opts = GbFetchOptions {
+            base_url: mock(vec![
+                ("400 Bad Request", ""),
+                ("200 OK", ">KX1.2 k\nTT\n"),
+                ("400 Bad Request", ""),
+                ("200 OK", ">Z9.1 z\nGG\n"),
+            ]),
+            ..Default::default()
+        };
+        let mut out = Vec::<u8>::new();
+        let failed = run(&args, &opts, &mut out).unwrap();
+        assert_eq!(out, b">KX1.2 k\nTT\n>Z9.1 z\nGG\n");
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].0, "AB1");
+        assert!(failed[0].1.contains("HTTP status 400"));
+    }
 }
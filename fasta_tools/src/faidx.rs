@@ -0,0 +1,276 @@
+// Copyright (c) 2018 10x Genomics, Inc. All rights reserved.
+
+// Fetching regions of indexed fasta files, using .fai indices as made by samtools faidx.
+//
+// A .fai file has one tab-separated line per record, giving its name (the part of its header
+// before the first whitespace), its length, the offset in the file of its first base, the
+// number of bases per line, and the number of bytes per line, including the line ending.
+// For a BGZF-compressed fasta file, offsets are in the uncompressed data, and a .gzi index
+// is also needed.
+
+use crate::bgzf::{is_bgzf, read_gzi, BgzfReader};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FaiRecord {
+    pub name: String,
+    pub length: u64,
+    pub offset: u64,
+    pub line_bases: u64,
+    pub line_width: u64,
+}
+
+fn invalid(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+pub fn read_fai(f: impl AsRef<Path>) -> io::Result<Vec<FaiRecord>> {
+    let f = f.as_ref();
+    let mut fai = Vec::new();
+    for (i, line) in BufReader::new(File::open(f)?).lines().enumerate() {
+        let line = line?;
+        let fields = line.split('\t').collect::<Vec<&str>>();
+        let num = |j: usize| fields.get(j).and_then(|x| x.parse::<u64>().ok());
+        match (num(1), num(2), num(3), num(4)) {
+            (Some(length), Some(offset), Some(line_bases), Some(line_width)) => {
+                fai.push(FaiRecord {
+                    name: fields[0].to_string(),
+                    length,
+                    offset,
+                    line_bases,
+                    line_width,
+                });
+            }
+            _ => {
+                return Err(invalid(format!(
+                    "bad line {} in fai file {}",
+                    i + 1,
+                    f.display()
+                )))
+            }
+        }
+    }
+    Ok(fai)
+}
+
+pub fn write_fai<W: Write>(w: &mut W, fai: &[FaiRecord]) -> io::Result<()> {
+    for r in fai.iter() {
+        writeln!(
+            w,
+            "{}\t{}\t{}\t{}\t{}",
+            r.name, r.length, r.offset, r.line_bases, r.line_width
+        )?;
+    }
+    Ok(())
+}
+
+// Build the .fai index for uncompressed fasta data.  Every sequence line of a record except
+// the last must have the same length.
+
+pub fn build_fai<R: BufRead>(mut r: R) -> io::Result<Vec<FaiRecord>> {
+    let mut fai = Vec::<FaiRecord>::new();
+    let mut offset = 0;
+    let mut line = Vec::<u8>::new();
+    let mut short_line = false;
+    loop {
+        line.clear();
+        let n = r.read_until(b'\n', &mut line)? as u64;
+        if n == 0 {
+            break;
+        }
+        offset += n;
+        if line[0] == b'>' {
+            let h = String::from_utf8_lossy(&line[1..]);
+            let name = h.split_whitespace().next().unwrap_or("").to_string();
+            fai.push(FaiRecord {
+                name,
+                length: 0,
+                offset,
+                line_bases: 0,
+                line_width: 0,
+            });
+            short_line = false;
+            continue;
+        }
+        let r = fai
+            .last_mut()
+            .ok_or_else(|| invalid("fasta data do not start with >".to_string()))?;
+        let bases = line.iter().filter(|&&c| c != b'\n' && c != b'\r').count() as u64;
+        if r.line_bases == 0 {
+            r.line_bases = bases;
+            r.line_width = n;
+        } else if short_line || bases > r.line_bases {
+            return Err(invalid(format!(
+                "record {} has uneven line lengths",
+                r.name
+            )));
+        }
+        short_line = bases < r.line_bases;
+        r.length += bases;
+    }
+    Ok(fai)
+}
+
+// ▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓
+
+// A BGZF-compressed fasta file, with its .fai and .gzi indices, which are the file name
+// with .fai and .gzi appended.  Regions can be fetched without decompressing the whole file.
+// A fasta file that is compressed with ordinary gzip cannot be used this way, and must be
+// recompressed with bgzip, or read sequentially.
+
+pub struct IndexedFastaGz {
+    path: PathBuf,
+    reader: BgzfReader<BufReader<File>>,
+    fai: Vec<FaiRecord>,
+    gzi: Vec<(u64, u64)>,
+}
+
+impl IndexedFastaGz {
+    pub fn open(f: impl AsRef<Path>) -> io::Result<IndexedFastaGz> {
+        let path = f.as_ref().to_path_buf();
+        if !is_bgzf(&path)? {
+            return Err(invalid(format!(
+                "{} is not BGZF-compressed, so cannot be read by region",
+                path.display()
+            )));
+        }
+        let with_ext = |ext: &str| {
+            let mut p = path.clone().into_os_string();
+            p.push(ext);
+            PathBuf::from(p)
+        };
+        let fai = read_fai(with_ext(".fai"))?;
+        let gzi = read_gzi(with_ext(".gzi"))?;
+        let reader = BgzfReader::new(BufReader::new(File::open(&path)?));
+        Ok(IndexedFastaGz {
+            path,
+            reader,
+            fai,
+            gzi,
+        })
+    }
+
+    pub fn records(&self) -> &[FaiRecord] {
+        &self.fai
+    }
+
+    // Fetch the bases in the zero-based half-open interval [start, stop) of the named record.
+    // The interval is truncated at the end of the record.
+
+    pub fn fetch(&mut self, name: &str, start: u64, stop: u64) -> io::Result<Vec<u8>> {
+        let r = self
+            .fai
+            .iter()
+            .find(|r| r.name == name)
+            .ok_or_else(|| invalid(format!("no record {} in {}", name, self.path.display())))?;
+        let stop = stop.min(r.length);
+        if start >= stop {
+            return Ok(Vec::new());
+        }
+        let upos = r.offset + (start / r.line_bases) * r.line_width + start % r.line_bases;
+        self.reader.seek_uncompressed(&self.gzi, upos)?;
+        let n = (stop - start) as usize;
+        let mut x = Vec::with_capacity(n);
+        let mut buf = [0u8; 4096];
+        while x.len() < n {
+            let m = self.reader.read(&mut buf)?;
+            if m == 0 {
+                return Err(invalid(format!("{} is truncated", self.path.display())));
+            }
+            for &c in buf[..m].iter() {
+                if c != b'\n' && c != b'\r' && x.len() < n {
+                    x.push(c);
+                }
+            }
+        }
+        Ok(x)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bgzf::{write_gzi, BgzfWriter};
+
+    #[test]
+    fn test_indexed_fasta_gz() {
+        use crate::read_fasta_to_vec_vec_u8;
+
+        // Make a fasta file with lines of 60 bases, compress it with small blocks so that
+        // regions span blocks, and index it.
+
+        let bases = |n: usize, k: usize| {
+            (0..n)
+                .map(|i| b"ACGT"[(i * k + i / 7) % 4])
+                .collect::<Vec<u8>>()
+        };
+        let seqs = [bases(1000, 1), bases(61, 3), bases(2500, 5)];
+        let names = ["chr1 first", "chr2", "chr3 third"];
+        let mut fa = Vec::new();
+        for (name, seq) in names.iter().zip(seqs.iter()) {
+            crate::write_fasta_record(&mut fa, name, seq, 60).unwrap();
+        }
+        let dir = std::env::temp_dir();
+        let f = dir.join(format!("fasta_tools_{}_indexed.fa.gz", std::process::id()));
+        let mut w = BgzfWriter::with_block_size(File::create(&f).unwrap(), 500);
+        w.write_all(&fa).unwrap();
+        let (_, gzi) = w.finish().unwrap();
+        let mut fai_path = f.clone().into_os_string();
+        fai_path.push(".fai");
+        let mut gzi_path = f.clone().into_os_string();
+        gzi_path.push(".gzi");
+        let fai = build_fai(&fa[..]).unwrap();
+        assert_eq!(
+            fai[1],
+            FaiRecord {
+                name: "chr2".to_string(),
+                length: 61,
+                offset: 1035,
+                line_bases: 60,
+                line_width: 61,
+            }
+        );
+        write_fai(&mut File::create(&fai_path).unwrap(), &fai).unwrap();
+        write_gzi(&gzi_path, &gzi).unwrap();
+        assert_eq!(read_fai(&fai_path).unwrap(), fai);
+
+        // Fetch regions.
+
+        let mut x = IndexedFastaGz::open(&f).unwrap();
+        assert_eq!(x.records().len(), 3);
+        for (i, name) in ["chr1", "chr2", "chr3"].iter().enumerate() {
+            let n = seqs[i].len() as u64;
+            for (start, stop) in [
+                (0, 10),
+                (55, 65),
+                (59, 60),
+                (60, 61),
+                (0, n),
+                (n - 1, n + 5),
+            ] {
+                let y = x.fetch(name, start, stop).unwrap();
+                assert_eq!(y, &seqs[i][start as usize..stop.min(n) as usize]);
+            }
+        }
+        assert_eq!(x.fetch("chr3", 1234, 2345).unwrap(), &seqs[2][1234..2345]);
+        assert!(x.fetch("chr3", 10, 10).unwrap().is_empty());
+        assert!(x.fetch("chr4", 0, 10).is_err());
+
+        // The sequential readers still work on the file.
+
+        let v = read_fasta_to_vec_vec_u8(&f);
+        assert!(v[5] == seqs[2]);
+
+        // A file compressed with ordinary gzip is not BGZF.
+
+        let g = dir.join(format!("fasta_tools_{}_plain_gz.fa.gz", std::process::id()));
+        let mut e = flate2::write::GzEncoder::new(File::create(&g).unwrap(), Default::default());
+        e.write_all(&fa).unwrap();
+        e.finish().unwrap();
+        assert!(!is_bgzf(&g).unwrap());
+        assert!(is_bgzf(&f).unwrap());
+        assert!(IndexedFastaGz::open(&g).is_err());
+    }
+}
@@ -0,0 +1,642 @@
+// Copyright (c) 2018 10x Genomics, Inc. All rights reserved.
+
+// Tools for fetching GenBank records from NCBI, using the efetch service.
+//
+// Requests are made in-process using ureq, with a timeout, and retrying with exponential
+// backoff if the server reports that it is overloaded or rate limiting us.
+
+use crate::{to_dna_string_checked, BasePolicy, DnaPolicy, FastaErrorKind};
+use debruijn::dna_string::DnaString;
+use std::io::{self, Read};
+use std::thread::sleep;
+use std::time::Duration;
+
+pub const EFETCH_URL: &str = "https://eutils.ncbi.nlm.nih.gov/entrez/eutils/efetch.fcgi";
+
+// The longest we will wait between retries, whatever the server asks for.
+
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+#[derive(Debug)]
+pub enum GbError {
+    // The server could not be reached.
+    Connect(String),
+    // The request timed out.
+    Timeout(String),
+    // Some other failure to make the request or read the response.
+    Transport(String),
+    // The server returned a failure status, after the given number of attempts.
+    Http { status: u32, attempts: usize },
+    // The response could not be interpreted.
+    BadResponse(String),
+    // The response did not include records for these accessions.
+    Missing(Vec<String>),
+}
+
+impl std::fmt::Display for GbError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            GbError::Connect(e) => write!(f, "could not connect to GenBank: {}", e),
+            GbError::Timeout(e) => write!(f, "GenBank request timed out: {}", e),
+            GbError::Transport(e) => write!(f, "GenBank request failed: {}", e),
+            GbError::Http { status, attempts } => write!(
+                f,
+                "GenBank request failed with HTTP status {} after {} attempt{}",
+                status,
+                attempts,
+                if *attempts == 1 { "" } else { "s" }
+            ),
+            GbError::BadResponse(e) => write!(f, "bad GenBank response: {}", e),
+            GbError::Missing(accs) => {
+                write!(f, "GenBank response has no record for {}", accs.join(", "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for GbError {}
+
+// Options for fetching.  After a failure with status 429 or 5xx, a failure to connect, or a
+// timeout, a request is retried up to retries times, waiting first for backoff, and then
+// doubling the wait each time, unless the server sends a Retry-After header giving the number
+// of seconds to wait.
+
+#[derive(Clone, Debug)]
+pub struct GbFetchOptions {
+    pub base_url: String,
+    pub timeout: Duration,
+    pub retries: usize,
+    pub backoff: Duration,
+}
+
+impl Default for GbFetchOptions {
+    fn default() -> Self {
+        GbFetchOptions {
+            base_url: EFETCH_URL.to_string(),
+            timeout: Duration::from_secs(60),
+            retries: 3,
+            backoff: Duration::from_secs(1),
+        }
+    }
+}
+
+// Percent-encode a query parameter value.
+
+fn percent_encode(s: &str) -> String {
+    let mut x = String::new();
+    for b in s.bytes() {
+        if b.is_ascii_alphanumeric() || b"-_.~".contains(&b) {
+            x.push(b as char);
+        } else {
+            x += &format!("%{:02X}", b);
+        }
+    }
+    x
+}
+
+// Form the efetch URL for the given nucleotide accessions, which are comma-separated, and
+// rettype, for example fasta or gb.
+
+pub fn efetch_url(base_url: &str, ids: &[&str], rettype: &str) -> String {
+    let ids = ids.iter().map(|id| percent_encode(id)).collect::<Vec<_>>();
+    format!(
+        "{}?db=nucleotide&id={}&rettype={}&retmode=text",
+        base_url,
+        ids.join(","),
+        percent_encode(rettype)
+    )
+}
+
+fn is_timeout(e: &io::Error) -> bool {
+    matches!(
+        e.kind(),
+        io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock
+    )
+}
+
+fn read_error(e: io::Error) -> GbError {
+    if is_timeout(&e) {
+        GbError::Timeout(e.to_string())
+    } else {
+        GbError::Transport(e.to_string())
+    }
+}
+
+fn transport_error(t: ureq::Transport) -> GbError {
+    let timed_out = std::error::Error::source(&t)
+        .and_then(|e| e.downcast_ref::<io::Error>())
+        .is_some_and(is_timeout);
+    match t.kind() {
+        _ if timed_out => GbError::Timeout(t.to_string()),
+        ureq::ErrorKind::Dns
+        | ureq::ErrorKind::ConnectionFailed
+        | ureq::ErrorKind::ProxyConnect => GbError::Connect(t.to_string()),
+        _ => GbError::Transport(t.to_string()),
+    }
+}
+
+// Make one GET request, following redirects, and return the final HTTP status, the value of
+// any Retry-After header that gives a number of seconds, and the body.
+
+fn http_get(url: &str, timeout: Duration) -> Result<(u32, Option<Duration>, Vec<u8>), GbError> {
+    let agent = ureq::AgentBuilder::new().timeout(timeout).build();
+    let r = match agent.get(url).call() {
+        Ok(r) | Err(ureq::Error::Status(_, r)) => r,
+        Err(ureq::Error::Transport(t)) => return Err(transport_error(t)),
+    };
+    let status = r.status() as u32;
+    let retry_after = r
+        .header("Retry-After")
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .map(Duration::from_secs);
+    let mut body = Vec::<u8>::new();
+    r.into_reader().read_to_end(&mut body).map_err(read_error)?;
+    Ok((status, retry_after, body))
+}
+
+// Fetch records for the given accessions from efetch, retrying as described for
+// GbFetchOptions, and return the response text.
+
+pub fn fetch_genbank(
+    ids: &[&str],
+    rettype: &str,
+    opts: &GbFetchOptions,
+) -> Result<String, GbError> {
+    let url = efetch_url(&opts.base_url, ids, rettype);
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        let (retry, e, retry_after) = match http_get(&url, opts.timeout) {
+            Ok((status, _, body)) if (200..300).contains(&status) => {
+                return String::from_utf8(body)
+                    .map_err(|_| GbError::BadResponse("response is not valid UTF-8".to_string()));
+            }
+            Ok((status, retry_after, _)) => {
+                let e = GbError::Http { status, attempts };
+                (status == 429 || status >= 500, e, retry_after)
+            }
+            Err(e @ (GbError::Connect(_) | GbError::Timeout(_))) => (true, e, None),
+            Err(e) => (false, e, None),
+        };
+        if !retry || attempts > opts.retries {
+            return Err(e);
+        }
+        let backoff = opts.backoff * 2u32.saturating_pow(attempts as u32 - 1);
+        sleep(retry_after.unwrap_or(backoff).min(MAX_BACKOFF));
+    }
+}
+
+// Fetch the fasta record for a single accession, as text.
+
+pub fn load_genbank_accession_fasta_checked(
+    accession: &str,
+    opts: &GbFetchOptions,
+) -> Result<String, GbError> {
+    let fasta = fetch_genbank(&[accession], "fasta", opts)?;
+    parse_fasta_response(&fasta)?;
+    Ok(fasta)
+}
+
+// Parse a fasta response into headers and bases.  This fails unless the response is
+// nonempty fasta, starting with '>', whose bases are A, C, G, T or IUPAC ambiguity codes,
+// so that for example an HTML page saying that the service has moved is rejected.
+
+fn parse_fasta_response(fasta: &str) -> Result<Vec<(String, String)>, GbError> {
+    if !fasta.starts_with('>') {
+        let first = fasta.lines().next().unwrap_or_default();
+        return Err(GbError::BadResponse(format!(
+            "response is not fasta, beginning {:?}",
+            first.chars().take(60).collect::<String>()
+        )));
+    }
+    let mut records = Vec::<(String, String)>::new();
+    crate::parse_fasta_records(
+        fasta.as_bytes(),
+        std::path::Path::new("<efetch response>"),
+        false,
+        true,
+        |h, _, b| {
+            if let Some(p) = b.bytes().position(|c| !BasePolicy::AllowIupac.allows(c)) {
+                return Err(FastaErrorKind::DisallowedBase {
+                    position: p,
+                    byte: b.as_bytes()[p],
+                });
+            }
+            records.push((h, b));
+            Ok(())
+        },
+    )
+    .map_err(|e| GbError::BadResponse(e.to_string()))?;
+    Ok(records)
+}
+
+fn to_dna_string(bases: &str) -> DnaString {
+    to_dna_string_checked(bases.as_bytes(), DnaPolicy::default()).unwrap()
+}
+
+// Fetch the bases for a single accession.  Ambiguous bases become A.
+
+pub fn load_genbank_accession_checked(accession: &str) -> Result<DnaString, GbError> {
+    let fasta = fetch_genbank(&[accession], "fasta", &GbFetchOptions::default())?;
+    let records = parse_fasta_response(&fasta)?;
+    Ok(to_dna_string(&records[0].1))
+}
+
+// Fetch the bases for several accessions, in as few requests as possible, returning them in
+// the same order as accs, each with its accession.  A record matches an accession if its id,
+// which is the part of its header before the first space, is the accession, or is the
+// accession followed by a version, as in AC244625.2 for AC244625.
+
+const MAX_IDS_PER_REQUEST: usize = 200;
+
+pub fn load_genbank_accessions(accs: &[&str]) -> Result<Vec<(String, DnaString)>, GbError> {
+    load_genbank_accessions_with(accs, &GbFetchOptions::default())
+}
+
+pub fn load_genbank_accessions_with(
+    accs: &[&str],
+    opts: &GbFetchOptions,
+) -> Result<Vec<(String, DnaString)>, GbError> {
+    let mut uniq = accs.to_vec();
+    uniq.sort_unstable();
+    uniq.dedup();
+    let mut fasta = String::new();
+    for ids in uniq.chunks(MAX_IDS_PER_REQUEST) {
+        fasta += &fetch_genbank(ids, "fasta", opts)?;
+        if !fasta.ends_with('\n') {
+            fasta.push('\n');
+        }
+    }
+    match_genbank_records(&fasta, accs)
+}
+
+// Test if a record id is an accession, or the accession followed by a version.
+
+fn id_matches(id: &str, acc: &str) -> bool {
+    id == acc || id.strip_prefix(acc).is_some_and(|v| v.starts_with('.'))
+}
+
+// ▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓
+
+// The formats in which records can be fetched as text.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GbFormat {
+    Fasta,
+    GenBank,
+}
+
+impl GbFormat {
+    pub fn rettype(self) -> &'static str {
+        match self {
+            GbFormat::Fasta => "fasta",
+            GbFormat::GenBank => "gb",
+        }
+    }
+}
+
+impl std::str::FromStr for GbFormat {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "fasta" => Ok(GbFormat::Fasta),
+            "gb" => Ok(GbFormat::GenBank),
+            _ => Err(format!(
+                "unknown rettype {}, which should be fasta or gb",
+                s
+            )),
+        }
+    }
+}
+
+// Split a response into records, each with its id.  For fasta, the id is the part of the
+// header before the first space, and for GenBank, it is the first word of the VERSION line,
+// or if there is none, of the ACCESSION line.  GenBank records end with a line //.  Blank
+// lines between records are dropped.
+
+fn split_genbank_records(text: &str, format: GbFormat) -> Vec<(String, String)> {
+    let mut records = Vec::<(String, String)>::new();
+    let mut current = String::new();
+    let mut id: Option<String> = None;
+    let word = |line: &str, key: &str| {
+        let rest = line.strip_prefix(key)?;
+        rest.split_whitespace().next().map(|w| w.to_string())
+    };
+    for line in text.lines() {
+        match format {
+            GbFormat::Fasta => {
+                if let Some(h) = line.strip_prefix('>') {
+                    if !current.is_empty() {
+                        records.push((id.take().unwrap(), std::mem::take(&mut current)));
+                    }
+                    id = Some(h.split(' ').next().unwrap().to_string());
+                } else if id.is_none() || line.is_empty() {
+                    continue;
+                }
+            }
+            GbFormat::GenBank => {
+                if current.is_empty() && line.trim().is_empty() {
+                    continue;
+                }
+                if let Some(v) = word(line, "VERSION ") {
+                    id = Some(v);
+                } else if id.is_none() {
+                    id = word(line, "ACCESSION ");
+                }
+            }
+        }
+        current += line;
+        current.push('\n');
+        if format == GbFormat::GenBank && line == "//" {
+            records.push((id.take().unwrap_or_default(), std::mem::take(&mut current)));
+        }
+    }
+    if !current.is_empty() {
+        records.push((id.unwrap_or_default(), current));
+    }
+    records
+}
+
+// Fetch the records for several accessions as text, in as few requests as possible,
+// returning for each accession, in the same order as accs, its record, or None if the
+// response did not include it.  Records are matched to accessions as for
+// load_genbank_accessions.
+
+pub fn fetch_genbank_records(
+    accs: &[&str],
+    format: GbFormat,
+    opts: &GbFetchOptions,
+) -> Result<Vec<Option<String>>, GbError> {
+    let mut uniq = accs.to_vec();
+    uniq.sort_unstable();
+    uniq.dedup();
+    let mut records = Vec::<(String, String)>::new();
+    for ids in uniq.chunks(MAX_IDS_PER_REQUEST) {
+        let text = fetch_genbank(ids, format.rettype(), opts)?;
+        records.append(&mut split_genbank_records(&text, format));
+    }
+    Ok(accs
+        .iter()
+        .map(|acc| {
+            records
+                .iter()
+                .find(|(id, _)| id_matches(id, acc))
+                .map(|(_, r)| r.clone())
+        })
+        .collect())
+}
+
+// Parse a multi-record fasta response and match its records to accessions.
+
+fn match_genbank_records(fasta: &str, accs: &[&str]) -> Result<Vec<(String, DnaString)>, GbError> {
+    let records = parse_fasta_response(fasta)?;
+    let mut found = Vec::new();
+    let mut missing = Vec::new();
+    for &acc in accs {
+        let r = records
+            .iter()
+            .find(|(h, _)| id_matches(h.split(' ').next().unwrap(), acc));
+        match r {
+            Some((_, b)) => found.push((acc.to_string(), to_dna_string(b))),
+            None => missing.push(acc.to_string()),
+        }
+    }
+    if !missing.is_empty() {
+        return Err(GbError::Missing(missing));
+    }
+    Ok(found)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_http::{mock_server, response};
+
+    #[test]
+    fn test_efetch_url() {
+        assert_eq!(
+            efetch_url(EFETCH_URL, &["AB1.2", "X 3"], "fasta"),
+            "https://eutils.ncbi.nlm.nih.gov/entrez/eutils/efetch.fcgi\
+             ?db=nucleotide&id=AB1.2,X%203&rettype=fasta&retmode=text"
+        );
+    }
+
+    #[test]
+    fn test_fetch_retry() {
+        let fasta = ">AB1.2 test\nACGT\nAC\n";
+        let base_url = mock_server(vec![
+            response("429 Too Many Requests", "Retry-After: 0\r\n", ""),
+            response("503 Service Unavailable", "", ""),
+            response("200 OK", "", fasta),
+        ]);
+        let opts = GbFetchOptions {
+            base_url,
+            backoff: Duration::from_millis(1),
+            ..Default::default()
+        };
+        assert_eq!(fetch_genbank(&["AB1.2"], "fasta", &opts).unwrap(), fasta);
+    }
+
+    #[test]
+    fn test_load_genbank_accessions_chunks() {
+        // Two requests, the first of whose responses does not end with a newline.
+
+        let accs = (0..=MAX_IDS_PER_REQUEST)
+            .map(|i| format!("A{:03}", i))
+            .collect::<Vec<_>>();
+        let first = accs[..MAX_IDS_PER_REQUEST]
+            .iter()
+            .map(|a| format!(">{}.1\nACGT", a))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let base_url = mock_server(vec![
+            response("200 OK", "", &first),
+            response(
+                "200 OK",
+                "",
+                &format!(">{}.1\nGG\n", accs[MAX_IDS_PER_REQUEST]),
+            ),
+        ]);
+        let opts = GbFetchOptions {
+            base_url,
+            ..Default::default()
+        };
+        let accs = accs.iter().map(|a| a.as_str()).collect::<Vec<_>>();
+        let x = load_genbank_accessions_with(&accs, &opts).unwrap();
+        assert_eq!(x.len(), accs.len());
+        assert_eq!(x[MAX_IDS_PER_REQUEST - 1].1.to_string(), "ACGT");
+        assert_eq!(x[MAX_IDS_PER_REQUEST].1.to_string(), "GG");
+    }
+
+    #[test]
+    fn test_fetch_gives_up() {
+        let base_url = mock_server(vec![
+            response("500 Internal Server Error", "", ""),
+            response("500 Internal Server Error", "", ""),
+        ]);
+        let opts = GbFetchOptions {
+            base_url,
+            retries: 1,
+            backoff: Duration::from_millis(1),
+            ..Default::default()
+        };
+        let e = fetch_genbank(&["AB1.2"], "fasta", &opts).unwrap_err();
+        assert!(matches!(
+            e,
+            GbError::Http {
+                status: 500,
+                attempts: 2
+            }
+        ));
+        let base_url = mock_server(vec![response("400 Bad Request", "", "")]);
+        let opts = GbFetchOptions {
+            base_url,
+            ..Default::default()
+        };
+        let e = fetch_genbank(&["AB1.2"], "fasta", &opts).unwrap_err();
+        assert!(matches!(
+            e,
+            GbError::Http {
+                status: 400,
+                attempts: 1
+            }
+        ));
+    }
+
+    #[test]
+    fn test_fetch_transport_errors() {
+        // Nothing is listening on the port.
+
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let opts = GbFetchOptions {
+            base_url: format!("http://127.0.0.1:{}/efetch.fcgi", port),
+            retries: 0,
+            ..Default::default()
+        };
+        let e = fetch_genbank(&["AB1.2"], "fasta", &opts).unwrap_err();
+        assert!(matches!(e, GbError::Connect(_)), "{}", e);
+
+        // The server accepts the connection but never responds.
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let opts = GbFetchOptions {
+            base_url: format!("http://{}/efetch.fcgi", listener.local_addr().unwrap()),
+            timeout: Duration::from_millis(200),
+            retries: 1,
+            backoff: Duration::from_millis(1),
+        };
+        let e = fetch_genbank(&["AB1.2"], "fasta", &opts).unwrap_err();
+        assert!(matches!(e, GbError::Timeout(_)), "{}", e);
+        assert!(e.to_string().starts_with("GenBank request timed out"));
+        drop(listener);
+    }
+
+    #[test]
+    fn test_match_genbank_records() {
+        let fasta = ">AC244625.2 Homo sapiens chromosome 7 clone, complete sequence\n\
+                     ACGTACGT\nAC\n\n>KX1.1 Mus musculus\nTTTT\n\n";
+        let x = match_genbank_records(fasta, &["KX1.1", "AC244625"]).unwrap();
+        assert_eq!(x.len(), 2);
+        assert_eq!(x[0].0, "KX1.1");
+        assert_eq!(x[0].1.to_string(), "TTTT");
+        assert_eq!(x[1].0, "AC244625");
+        assert_eq!(x[1].1.to_string(), "ACGTACGTAC");
+        let e = match_genbank_records(fasta, &["KX1.1", "AC24462", "Z9"]).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "GenBank response has no record for AC24462, Z9"
+        );
+        assert!(matches!(
+            match_genbank_records("<html>moved</html>", &["KX1.1"]),
+            Err(GbError::BadResponse(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_fasta_response() {
+        let x = parse_fasta_response(">KX1.1 Mus musculus\nACGTNRY\nac\n>Z9.1 z\nGG\n").unwrap();
+        assert_eq!(x.len(), 2);
+        assert_eq!(x[1], ("Z9.1 z".to_string(), "GG".to_string()));
+        assert_eq!(to_dna_string(&x[0].1).to_string(), "ACGTAAAAC");
+
+        // A page saying that the service has moved, which does not say "moved" in the first
+        // line, and other things that are not fasta.
+
+        let html = "<!DOCTYPE HTML PUBLIC \"-//IETF//DTD HTML 2.0//EN\">\n<html><head>\n\
+                    <title>301 Moved Permanently</title>\n</head><body>\n<h1>Moved \
+                    Permanently</h1>\n<p>The document has moved <a href=\"https://x\">here</a>\
+                    .</p>\n</body></html>\n";
+        let e = parse_fasta_response(html).unwrap_err().to_string();
+        assert!(
+            e.starts_with("bad GenBank response: response is not fasta, beginning \"<!DOCTYPE"),
+            "{}",
+            e
+        );
+        assert!(parse_fasta_response("").is_err());
+        assert!(parse_fasta_response("Error: ID list is empty!\n>x\nAC\n").is_err());
+        let e = parse_fasta_response(">KX1.1\nACGT\n<p>moved</p>\n").unwrap_err();
+        assert!(
+            e.to_string().contains("disallowed base '<' at position 4"),
+            "{}",
+            e
+        );
+        assert!(parse_fasta_response(">KX1.1\n").is_err());
+    }
+
+    #[test]
+    fn test_fetch_moved() {
+        let html = "<html><body>The document has moved.</body></html>";
+        let base_url = mock_server(vec![
+            response("200 OK", "", html),
+            response("200 OK", "", ">AB1.2 test\nACGT\n"),
+        ]);
+        let opts = GbFetchOptions {
+            base_url,
+            ..Default::default()
+        };
+        assert!(matches!(
+            load_genbank_accession_fasta_checked("AB1.2", &opts),
+            Err(GbError::BadResponse(_))
+        ));
+        assert_eq!(
+            load_genbank_accession_fasta_checked("AB1.2", &opts).unwrap(),
+            ">AB1.2 test\nACGT\n"
+        );
+    }
+
+    #[test]
+    fn test_split_genbank_records() {
+        let fasta = ">AC244625.2 Homo sapiens\nACGT\nAC\n\n>KX1.1 Mus musculus\nTTTT\n\n";
+        assert_eq!(
+            split_genbank_records(fasta, GbFormat::Fasta),
+            vec![
+                (
+                    "AC244625.2".to_string(),
+                    ">AC244625.2 Homo sapiens\nACGT\nAC\n".to_string()
+                ),
+                (
+                    "KX1.1".to_string(),
+                    ">KX1.1 Mus musculus\nTTTT\n".to_string()
+                ),
+            ]
+        );
+        let gb =
+            "LOCUS       KX1  4 bp\nACCESSION   KX1\nVERSION     KX1.1\nORIGIN\n        1 tttt\n\
+                  //\n\nLOCUS       Z9  2 bp\nACCESSION   Z9 Z10\nORIGIN\n        1 ac\n//\n\n";
+        let x = split_genbank_records(gb, GbFormat::GenBank);
+        assert_eq!(x.len(), 2);
+        assert_eq!(x[0].0, "KX1.1");
+        assert!(x[0].1.starts_with("LOCUS       KX1 "));
+        assert!(x[0].1.ends_with("1 tttt\n//\n"));
+        assert_eq!(x[1].0, "Z9");
+        assert!(x[1].1.starts_with("LOCUS       Z9 "));
+        assert!(id_matches("KX1.1", "KX1"));
+        assert!(!id_matches("KX12", "KX1"));
+        assert_eq!("gb".parse::<GbFormat>(), Ok(GbFormat::GenBank));
+        assert!("genbank".parse::<GbFormat>().is_err());
+    }
+}
@@ -0,0 +1,228 @@
+// Copyright (c) 2018 10x Genomics, Inc. All rights reserved.
+
+// Parsing fasta headers.  A header, without the leading >, consists of an id, which is
+// everything before the first space, and optionally a description, which is everything after
+// it.
+//
+// Headers in the VDJ reference files (regions.fa) have the form
+// record|gene source|gene|region type|receptor|chain|isotype|allele
+// for example
+// 1|IGHA1 ENST00000641837|IGHA1|C-REGION|IG|IGH|A1|00
+// in which the isotype is None except for heavy chain constant region genes.
+
+use std::fmt;
+
+// Split a header into its id and description.
+
+pub fn split_fasta_header(h: &str) -> (&str, Option<&str>) {
+    match h.split_once(' ') {
+        Some((id, description)) => (id, Some(description)),
+        None => (h, None),
+    }
+}
+
+// ▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓
+
+// The fields of a VDJ reference header.  Formatting gives the header.
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VdjHeaderFields {
+    pub record: usize,
+    pub gene: String,
+    pub source: String,
+    pub region_type: String,
+    pub receptor: String,
+    pub chain: String,
+    pub isotype: Option<String>,
+    pub allele: String,
+}
+
+fn is_heavy_constant(gene: &str) -> bool {
+    gene == "IGHD"
+        || gene == "IGHE"
+        || gene == "IGHM"
+        || gene.starts_with("IGHG")
+        || gene.starts_with("IGHA")
+}
+
+impl VdjHeaderFields {
+    // The fields for a gene, as build_vdj_ref makes them.  A trailing space is removed from
+    // the gene name.  The region type is 5'UTR or 3'UTR if the corresponding flag is set,
+    // and is otherwise determined by the gene name, which must have at least four characters.
+
+    pub fn from_gene(
+        gene: &str,
+        is_5utr: bool,
+        is_3utr: bool,
+        record: usize,
+        source: &str,
+    ) -> VdjHeaderFields {
+        let gene = gene.strip_suffix(' ').unwrap_or(gene);
+        let genev = gene.as_bytes();
+        let constant = is_heavy_constant(gene);
+        let region_type = if is_5utr {
+            "5'UTR".to_string()
+        } else if is_3utr {
+            "3'UTR".to_string()
+        } else if constant {
+            "C-REGION".to_string()
+        } else if genev[3] == b'V' {
+            "L-REGION+V-REGION".to_string()
+        } else {
+            format!("{}-REGION", genev[3] as char)
+        };
+        VdjHeaderFields {
+            record,
+            gene: gene.to_string(),
+            source: source.to_string(),
+            region_type,
+            receptor: gene[..2].to_string(),
+            chain: gene[..3].to_string(),
+            isotype: constant.then(|| gene["IGH".len()..].to_string()),
+            allele: "00".to_string(),
+        }
+    }
+
+    // Parse a header, returning None unless it has the VDJ form and formatting the fields
+    // would give it back exactly.
+
+    pub fn parse(h: &str) -> Option<VdjHeaderFields> {
+        let f = h.split('|').collect::<Vec<_>>();
+        if f.len() != 8 {
+            return None;
+        }
+        let (_, source) = f[1].split_once(' ')?;
+        let x = VdjHeaderFields {
+            record: f[0].parse().ok()?,
+            gene: f[2].to_string(),
+            source: source.to_string(),
+            region_type: f[3].to_string(),
+            receptor: f[4].to_string(),
+            chain: f[5].to_string(),
+            isotype: (f[6] != "None").then(|| f[6].to_string()),
+            allele: f[7].to_string(),
+        };
+        (x.to_string() == h).then_some(x)
+    }
+}
+
+impl fmt::Display for VdjHeaderFields {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}|{} {}|{}|{}|{}|{}|{}|{}",
+            self.record,
+            self.gene,
+            self.source,
+            self.gene,
+            self.region_type,
+            self.receptor,
+            self.chain,
+            self.isotype.as_deref().unwrap_or("None"),
+            self.allele
+        )
+    }
+}
+
+// ▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓
+
+// A parsed header, with the VDJ fields if it is a VDJ reference header.  Formatting gives
+// back the header, from the id and description.
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FastaHeader {
+    pub id: String,
+    pub description: Option<String>,
+    pub vdj: Option<VdjHeaderFields>,
+}
+
+impl FastaHeader {
+    pub fn parse(h: &str) -> FastaHeader {
+        let (id, description) = split_fasta_header(h);
+        FastaHeader {
+            id: id.to_string(),
+            description: description.map(|d| d.to_string()),
+            vdj: VdjHeaderFields::parse(h),
+        }
+    }
+}
+
+impl fmt::Display for FastaHeader {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.description {
+            Some(d) => write!(f, "{} {}", self.id, d),
+            None => write!(f, "{}", self.id),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fasta_header() {
+        assert_eq!(split_fasta_header("chr1"), ("chr1", None));
+        assert_eq!(
+            split_fasta_header("chr1 AC:CM000663.2 gi|568336023"),
+            ("chr1", Some("AC:CM000663.2 gi|568336023"))
+        );
+        assert_eq!(split_fasta_header("chr1 "), ("chr1", Some("")));
+        let h = FastaHeader::parse("segment before IGSF21-AS1 exon 4");
+        assert_eq!(h.id, "segment");
+        assert_eq!(h.description.as_deref(), Some("before IGSF21-AS1 exon 4"));
+        assert!(h.vdj.is_none());
+        assert_eq!(h.to_string(), "segment before IGSF21-AS1 exon 4");
+        assert_eq!(FastaHeader::parse("chr1").to_string(), "chr1");
+
+        // An excerpt of headers from vdj_refs, human and mouse, which are regenerated from the
+        // gene exactly as build_vdj_ref makes them.
+
+        let regions = "\
+            >1|IGHA1 ENST00000641837|IGHA1|C-REGION|IG|IGH|A1|00\n\
+            >1|IGHA1 ENST00000641837|IGHA1|3'UTR|IG|IGH|A1|00\n\
+            >6|IGHD ENST00000633698|IGHD|C-REGION|IG|IGH|D|00\n\
+            >7|IGHD1-1 ENST00000633210|IGHD1-1|D-REGION|IG|IGH|None|00\n\
+            >37|IGHE ENST00000390541|IGHE|C-REGION|IG|IGH|E|00\n\
+            >45|IGHG3 ENST00000641136|IGHG3|C-REGION|IG|IGH|G3|00\n\
+            >60|IGHM ENST00000637539|IGHM|C-REGION|IG|IGH|M|00\n\
+            >62|IGHV1-18 ENST00000633147|IGHV1-18|5'UTR|IG|IGH|None|00\n\
+            >63|IGHV1-18 ENST00000633147|IGHV1-18|L-REGION+V-REGION|IG|IGH|None|00\n\
+            >256|IGLC1 ENST00000390321|IGLC1|C-REGION|IG|IGL|None|00\n\
+            >485|TRBJ1-1 ENST00000632951|TRBJ1-1|J-REGION|TR|TRB|None|00\n\
+            >679|TRBD2 GRCh38-release94|TRBD2|D-REGION|TR|TRB|None|00\n\
+            >1|IGHA ENSMUST00000178282|IGHA|C-REGION|IG|IGH|A|00\n\
+            >350|TRAJ11 ENSMUST00000103730|TRAJ11|J-REGION|TR|TRA|None|00\n";
+        for line in regions.lines() {
+            let line = line.strip_prefix('>').unwrap();
+            let h = FastaHeader::parse(line);
+            assert_eq!(h.to_string(), line);
+            let v = h.vdj.unwrap();
+            assert_eq!(v.to_string(), line);
+            assert_eq!(h.id, format!("{}|{}", v.record, v.gene));
+            let is_5utr = v.region_type == "5'UTR";
+            let is_3utr = v.region_type == "3'UTR";
+            let gene = format!("{} ", v.gene);
+            let x = VdjHeaderFields::from_gene(&gene, is_5utr, is_3utr, v.record, &v.source);
+            assert_eq!(x.to_string(), line);
+        }
+        let v =
+            VdjHeaderFields::parse("485|TRBJ1-1 ENST00000632951|TRBJ1-1|J-REGION|TR|TRB|None|00")
+                .unwrap();
+        assert_eq!((v.record, v.isotype), (485, None));
+        assert_eq!(v.chain, "TRB");
+
+        // Headers that are not in the VDJ form, or would not be regenerated exactly.
+
+        for h in [
+            "1|IGHA1|IGHA1|C-REGION|IG|IGH|A1|00",
+            "1|IGHA1 ENST00000641837|IGHA2|C-REGION|IG|IGH|A1|00",
+            "01|IGHA1 ENST00000641837|IGHA1|C-REGION|IG|IGH|A1|00",
+            "x|IGHA1 ENST00000641837|IGHA1|C-REGION|IG|IGH|A1|00",
+            "1|IGHA1 ENST00000641837|IGHA1|C-REGION|IG|IGH|A1",
+            "1|IGHA1 ENST00000641837|IGHA1|C-REGION|IG|IGH|A1|00|",
+        ] {
+            assert!(VdjHeaderFields::parse(h).is_none(), "{}", h);
+        }
+    }
+}
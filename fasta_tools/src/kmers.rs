@@ -0,0 +1,150 @@
+// Copyright (c) 2018 10x Genomics, Inc. All rights reserved.
+
+// Counting kmers.  A kmer of length k <= 32 is encoded in a u64 with two bits per base, as for
+// pack_2bit, with the first base in the highest-order bits used.
+
+use std::collections::HashMap;
+use tables::print_tabular_vbox;
+
+fn base_bits(b: u8) -> Option<u64> {
+    match b {
+        b'A' | b'a' => Some(0),
+        b'C' | b'c' => Some(1),
+        b'G' | b'g' => Some(2),
+        b'T' | b't' => Some(3),
+        _ => None,
+    }
+}
+
+// Decode a kmer, as uppercase.
+
+pub fn decode_kmer(x: u64, k: usize) -> Vec<u8> {
+    (0..k)
+        .map(|i| b"ACGT"[((x >> (2 * (k - 1 - i))) & 3) as usize])
+        .collect()
+}
+
+// Count the kmers in sequences, ignoring case, and skipping windows that contain anything
+// other than A, C, G or T.
+
+pub fn count_kmers(seqs: &[&[u8]], k: usize) -> HashMap<u64, u32> {
+    count_kmers_with(seqs, k, false)
+}
+
+// As count_kmers, but if canonical is true, replace each kmer by the minimum of it and its
+// reverse complement.
+
+pub fn count_kmers_with(seqs: &[&[u8]], k: usize, canonical: bool) -> HashMap<u64, u32> {
+    assert!(
+        (1..=32).contains(&k),
+        "k must be between 1 and 32, not {}",
+        k
+    );
+    let mask = if k == 32 {
+        u64::MAX
+    } else {
+        (1 << (2 * k)) - 1
+    };
+    let mut counts = HashMap::<u64, u32>::new();
+    for seq in seqs.iter() {
+        // The kmer ending at the current base, its reverse complement, and the number of bases
+        // since the last one that is not A, C, G or T.
+
+        let (mut fw, mut rc, mut n) = (0u64, 0u64, 0);
+        for &b in seq.iter() {
+            match base_bits(b) {
+                Some(x) => {
+                    fw = ((fw << 2) | x) & mask;
+                    rc = (rc >> 2) | ((3 - x) << (2 * (k - 1)));
+                    n += 1;
+                }
+                None => {
+                    n = 0;
+                    continue;
+                }
+            }
+            if n >= k {
+                let x = if canonical { fw.min(rc) } else { fw };
+                *counts.entry(x).or_insert(0) += 1;
+            }
+        }
+    }
+    counts
+}
+
+// Make a table of the n most frequent kmers, ties being broken by kmer.
+
+pub fn top_kmers_table(counts: &HashMap<u64, u32>, k: usize, n: usize) -> String {
+    let mut top = counts.iter().map(|(&x, &c)| (c, x)).collect::<Vec<_>>();
+    top.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+    let mut rows = vec![vec!["kmer".to_string(), "count".to_string()]];
+    for &(c, x) in top.iter().take(n) {
+        rows.push(vec![
+            String::from_utf8(decode_kmer(x, k)).unwrap(),
+            c.to_string(),
+        ]);
+    }
+    let mut log = String::new();
+    print_tabular_vbox(&mut log, &rows, 2, b"l|r", false, false);
+    log
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reverse_complement;
+
+    // Count kmers by brute force, as strings.
+
+    fn brute_force(seqs: &[&[u8]], k: usize, canonical: bool) -> HashMap<Vec<u8>, u32> {
+        let mut counts = HashMap::<Vec<u8>, u32>::new();
+        for seq in seqs.iter() {
+            for w in seq.windows(k) {
+                let w = w.to_ascii_uppercase();
+                if w.iter().all(|b| b"ACGT".contains(b)) {
+                    let rc = reverse_complement(&w);
+                    let x = if canonical && rc < w { rc } else { w };
+                    *counts.entry(x).or_insert(0) += 1;
+                }
+            }
+        }
+        counts
+    }
+
+    #[test]
+    fn test_count_kmers() {
+        let seqs: [&[u8]; 4] = [
+            b"ACGTACGTTTGACNACGTAcgtAANNGGGCCCAGT",
+            b"NNNNACGTTGCAACGTTTTTTTTTTTTTTTTTTTTTTTTTTTTTTTTTTTTTTTTA",
+            b"AC",
+            b"",
+        ];
+        for k in [1, 2, 3, 5, 31, 32] {
+            for canonical in [false, true] {
+                let counts = count_kmers_with(&seqs, k, canonical)
+                    .iter()
+                    .map(|(&x, &c)| (decode_kmer(x, k), c))
+                    .collect::<HashMap<_, _>>();
+                assert!(counts == brute_force(&seqs, k, canonical), "k = {}", k);
+            }
+        }
+
+        // Canonical counts of a sequence and its reverse complement are the same.
+
+        let s = b"GATTACAGGGTTTACCCAAATTTG";
+        let rc = reverse_complement(s);
+        assert_eq!(
+            count_kmers_with(&[s], 4, true),
+            count_kmers_with(&[&rc], 4, true)
+        );
+        assert_ne!(count_kmers(&[s], 4), count_kmers(&[&rc], 4));
+
+        // The table.
+
+        let counts = count_kmers(&[b"AAAACAAC"], 2);
+        let table = top_kmers_table(&counts, 2, 2);
+        assert!(table.contains("│AA    │      4│"), "{}", table);
+        assert!(table.contains("│AC    │      2│"), "{}", table);
+        assert!(!table.contains("CA"));
+    }
+}
@@ -2,253 +2,1566 @@
 
 // Tools for working with fasta files.
 
+pub mod bgzf;
+pub mod faidx;
+pub mod genbank;
+pub mod header;
+pub mod kmers;
+pub mod merge;
+#[cfg(any(test, feature = "mock_http"))]
+pub mod mock_http;
+pub mod stats;
+pub mod subset;
+pub mod translate;
+pub mod twobit;
+
 use debruijn::dna_string::DnaString;
 use flate2::read::MultiGzDecoder;
-use io_utils::open_for_read;
-use std::process::Command;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use genbank::{
+    load_genbank_accession_checked, load_genbank_accession_fasta_checked, GbFetchOptions,
+};
+use std::collections::HashMap;
+use std::io::{BufReader, BufWriter};
+use std::path::PathBuf;
 use std::{io::prelude::*, path::Path};
 use string_utils::TextUtils;
 
 // ▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓
 
+// Complement a base, allowing IUPAC ambiguity codes and preserving case, so that for example
+// R (A or G) becomes Y (C or T) and r becomes y.  Return None for anything else.
+
+fn complement_base(b: u8) -> Option<u8> {
+    let c = match b.to_ascii_uppercase() {
+        b'A' => b'T',
+        b'C' => b'G',
+        b'G' => b'C',
+        b'T' => b'A',
+        b'R' => b'Y',
+        b'Y' => b'R',
+        b'S' => b'S',
+        b'W' => b'W',
+        b'K' => b'M',
+        b'M' => b'K',
+        b'B' => b'V',
+        b'V' => b'B',
+        b'D' => b'H',
+        b'H' => b'D',
+        b'N' => b'N',
+        _ => return None,
+    };
+    Some(if b.is_ascii_lowercase() {
+        c.to_ascii_lowercase()
+    } else {
+        c
+    })
+}
+
+// Reverse complement a sequence, as for complement_base, leaving other bytes unchanged.
+// The variants reverse complement in place, and into a buffer supplied by the caller, which
+// is cleared first, so as to avoid allocation.
+
 pub fn reverse_complement(x: &[u8]) -> Vec<u8> {
-    let mut x = x.to_vec();
-    let n = x.len();
-    for j in 0..n / 2 {
-        x.swap(j, n - j - 1);
+    let mut y = Vec::with_capacity(x.len());
+    rc_copy_into(x, &mut y);
+    y
+}
+
+pub fn reverse_complement_in_place(x: &mut [u8]) {
+    x.reverse();
+    for b in x.iter_mut() {
+        *b = complement_base(*b).unwrap_or(*b);
     }
-    for j in 0..n {
-        if x[j] == b'A' {
-            x[j] = b'T';
-        } else if x[j] == b'C' {
-            x[j] = b'G';
-        } else if x[j] == b'G' {
-            x[j] = b'C';
-        } else if x[j] == b'T' {
-            x[j] = b'A';
-        }
+}
+
+pub fn rc_copy_into(src: &[u8], dst: &mut Vec<u8>) {
+    dst.clear();
+    dst.extend(src.iter().rev().map(|&b| complement_base(b).unwrap_or(b)));
+}
+
+// Reverse complement a sequence, as for complement_base, returning the position in x and
+// value of the first byte that is not an IUPAC base, if there is one.
+
+pub fn reverse_complement_strict(x: &[u8]) -> Result<Vec<u8>, (usize, u8)> {
+    if let Some(i) = x.iter().position(|&b| complement_base(b).is_none()) {
+        return Err((i, x[i]));
     }
-    x
+    Ok(reverse_complement(x))
 }
 
 // ▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓
 
+// Error from reading a fasta file, giving the file, the line number at which the error
+// was detected (or 0 if it was not on a particular line), and what went wrong.
+
+#[derive(Debug)]
+pub struct FastaError {
+    pub path: PathBuf,
+    pub line: usize,
+    pub kind: FastaErrorKind,
+}
+
+#[derive(Debug)]
+pub enum FastaErrorKind {
+    // The file does not start with a header line.
+    MissingHeader,
+    // A header line is not followed by any bases.
+    EmptyRecord,
+    // A line is not valid UTF-8.
+    BadUtf8,
+    // The file could not be decompressed.
+    BadGzip(String),
+    // The file could not be opened or read.
+    IoError(std::io::Error),
+    // A header is the same as the header at the given earlier line.
+    DuplicateHeader {
+        first_line: usize,
+    },
+    // The record has a base that is not allowed, at the given zero-based position in it.
+    DisallowedBase {
+        position: usize,
+        byte: u8,
+    },
+    // The record has the same id as the record at the given line of the given file.
+    DuplicateId {
+        id: String,
+        first_path: PathBuf,
+        first_line: usize,
+    },
+}
+
+impl std::fmt::Display for FastaError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "error reading fasta file {}", self.path.display())?;
+        if self.line > 0 {
+            write!(f, " at line {}", self.line)?;
+        }
+        match &self.kind {
+            FastaErrorKind::MissingHeader => write!(f, ": file does not start with >"),
+            FastaErrorKind::EmptyRecord => write!(f, ": record has no bases"),
+            FastaErrorKind::BadUtf8 => write!(f, ": line is not valid UTF-8"),
+            FastaErrorKind::BadGzip(e) => write!(f, ": gzip decompression failed: {}", e),
+            FastaErrorKind::IoError(e) => write!(f, ": {}", e),
+            FastaErrorKind::DuplicateHeader { first_line } => {
+                write!(f, ": header duplicates that at line {}", first_line)
+            }
+            FastaErrorKind::DisallowedBase { position, byte } => write!(
+                f,
+                ": record has disallowed base {} at position {}",
+                show_byte(*byte),
+                position
+            ),
+            FastaErrorKind::DuplicateId {
+                id,
+                first_path,
+                first_line,
+            } => write!(
+                f,
+                ": id {} was already used at line {} of {}",
+                id,
+                first_line,
+                first_path.display()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for FastaError {}
+
+// The path given in errors for fasta data that are read from stdin or another reader.
+
+const STREAM_PATH: &str = "-";
+
+// Wrap a reader so as to decompress it if it is gzipped, as recognized by the first two bytes
+// being the gzip magic number, whatever the file is called.  The returned flag is true if the
+// data are to be decompressed.
+
+fn decompress_if_gzipped<'a, R: BufRead + 'a>(
+    mut r: R,
+    path: &Path,
+) -> Result<(Box<dyn BufRead + 'a>, bool), FastaError> {
+    let head = r.fill_buf().map_err(|e| FastaError {
+        path: path.to_path_buf(),
+        line: 0,
+        kind: FastaErrorKind::IoError(e),
+    })?;
+    Ok(if head.starts_with(&[0x1f, 0x8b]) {
+        (Box::new(BufReader::new(MultiGzDecoder::new(r))), true)
+    } else {
+        (Box::new(r), false)
+    })
+}
+
+// Open a fasta file for reading, or stdin if the path is -, decompressing it if it is gzipped.
+// The returned flag is true if the file is to be decompressed.
+
+fn open_fasta(f: &Path) -> Result<(Box<dyn BufRead>, bool), FastaError> {
+    if f == Path::new(STREAM_PATH) {
+        return decompress_if_gzipped(BufReader::new(std::io::stdin()), f);
+    }
+    let file = std::fs::File::open(f).map_err(|e| FastaError {
+        path: f.to_path_buf(),
+        line: 0,
+        kind: FastaErrorKind::IoError(e),
+    })?;
+    decompress_if_gzipped(BufReader::new(file), f)
+}
+
+// Read the next line into buf, without its line ending, and advance the line number, returning
+// false at the end of the input.  Read errors are reported as gzip errors if gz is true.
+
+fn next_fasta_line<R: BufRead>(
+    fin: &mut R,
+    buf: &mut Vec<u8>,
+    path: &Path,
+    gz: bool,
+    line: &mut usize,
+) -> Result<bool, FastaError> {
+    buf.clear();
+    let n = fin.read_until(b'\n', buf).map_err(|e| FastaError {
+        path: path.to_path_buf(),
+        line: *line + 1,
+        kind: if gz {
+            FastaErrorKind::BadGzip(e.to_string())
+        } else {
+            FastaErrorKind::IoError(e)
+        },
+    })?;
+    if n == 0 {
+        return Ok(false);
+    }
+    *line += 1;
+    if buf.last() == Some(&b'\n') {
+        buf.pop();
+        if buf.last() == Some(&b'\r') {
+            buf.pop();
+        }
+    }
+    Ok(true)
+}
+
+// Parse fasta records from a reader, calling add(header, line, bases) for each record, where
+// the header excludes the >, line is the line number of the header, and the bases are the
+// concatenation of the lines that follow it.  An empty input has no records.  A record with
+// no bases is an EmptyRecord error if fail_on_empty is true, and otherwise is passed to add
+// with empty bases.  Read errors are reported as gzip errors if gz is true.  If add returns an
+// error, parsing stops, and the error is returned, at the line of the header.
+
+fn parse_fasta_records<R: BufRead>(
+    mut fin: R,
+    path: &Path,
+    gz: bool,
+    fail_on_empty: bool,
+    mut add: impl FnMut(String, usize, String) -> Result<(), FastaErrorKind>,
+) -> Result<(), FastaError> {
+    let err = |line, kind| FastaError {
+        path: path.to_path_buf(),
+        line,
+        kind,
+    };
+    let mut header: Option<(String, usize)> = None;
+    let mut bases = String::new();
+    let mut buf = Vec::<u8>::new();
+    let mut line = 0;
+    while next_fasta_line(&mut fin, &mut buf, path, gz, &mut line)? {
+        let s = std::str::from_utf8(&buf).map_err(|_| err(line, FastaErrorKind::BadUtf8))?;
+        if let Some(h) = s.strip_prefix('>') {
+            if let Some((prev, hline)) = header.replace((h.to_string(), line)) {
+                if fail_on_empty && bases.is_empty() {
+                    return Err(err(hline, FastaErrorKind::EmptyRecord));
+                }
+                add(prev, hline, std::mem::take(&mut bases)).map_err(|k| err(hline, k))?;
+            }
+        } else if header.is_none() {
+            return Err(err(line, FastaErrorKind::MissingHeader));
+        } else {
+            bases += s;
+        }
+    }
+    if let Some((h, hline)) = header {
+        if fail_on_empty && bases.is_empty() {
+            return Err(err(hline, FastaErrorKind::EmptyRecord));
+        }
+        add(h, hline, bases).map_err(|k| err(hline, k))?;
+    }
+    Ok(())
+}
+
 // Read a fasta file or gzipped fasta file and convert to a Vec<Vec<u8>>, in which
-// outer vec entries alternate between header lines and base lines.
+// outer vec entries alternate between header lines and base lines.  An empty file yields an
+// empty vector, and a record with no bases an empty base line.  The path may be - for stdin.  Case
+// is preserved, so soft-masked regions may be found using masked_regions.
 
-pub fn read_fasta_to_vec_vec_u8(f: impl AsRef<Path>) -> Vec<Vec<u8>> {
+pub fn try_read_fasta_to_vec_vec_u8(f: impl AsRef<Path>) -> Result<Vec<Vec<u8>>, FastaError> {
+    let f = f.as_ref();
+    let (fin, gz) = open_fasta(f)?;
+    vec_vec_u8_from(fin, f, gz)
+}
+
+// As try_read_fasta_to_vec_vec_u8, but reading from a reader, for example a pipe.  The data
+// may be gzipped.
+
+pub fn read_fasta_to_vec_vec_u8_from_reader<R: BufRead>(r: R) -> Result<Vec<Vec<u8>>, FastaError> {
+    let path = Path::new(STREAM_PATH);
+    let (fin, gz) = decompress_if_gzipped(r, path)?;
+    vec_vec_u8_from(fin, path, gz)
+}
+
+fn vec_vec_u8_from<R: BufRead>(fin: R, f: &Path, gz: bool) -> Result<Vec<Vec<u8>>, FastaError> {
     let mut x = Vec::<Vec<u8>>::new();
+    parse_fasta_records(fin, f, gz, false, |h, _, b| {
+        x.push(h.into_bytes());
+        x.push(b.into_bytes());
+        Ok(())
+    })?;
+    Ok(x)
+}
+
+pub fn read_fasta_to_vec_vec_u8(f: impl AsRef<Path>) -> Vec<Vec<u8>> {
+    try_read_fasta_to_vec_vec_u8(f).unwrap_or_else(|e| panic!("{}", e))
+}
+
+// ▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓
+
+// Which bases are allowed in a sequence: only A, C, G and T, also N, or also the IUPAC
+// ambiguity codes.  Case is ignored, so that soft-masked (lowercase) bases are allowed.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BasePolicy {
+    Strict,
+    AllowN,
+    AllowIupac,
+}
+
+impl BasePolicy {
+    pub fn allows(self, b: u8) -> bool {
+        match (self, b.to_ascii_uppercase()) {
+            (_, b'A' | b'C' | b'G' | b'T') => true,
+            (BasePolicy::AllowN, b'N') => true,
+            (BasePolicy::AllowIupac, _) => complement_base(b).is_some(),
+            _ => false,
+        }
+    }
+}
+
+// Show a byte for an error message, quoted if it is printable ASCII, and in hex otherwise.
+
+fn show_byte(b: u8) -> String {
+    if b.is_ascii_graphic() || b == b' ' {
+        format!("'{}'", b as char)
+    } else {
+        format!("0x{:02x}", b)
+    }
+}
+
+// A base that is not allowed by a policy, giving the record header, the line number, the
+// one-based column in the line, and the byte.
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BaseViolation {
+    pub record: String,
+    pub line: usize,
+    pub column: usize,
+    pub byte: u8,
+}
+
+impl std::fmt::Display for BaseViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: disallowed base {} in record {}",
+            self.line,
+            self.column,
+            show_byte(self.byte),
+            self.record
+        )
+    }
+}
+
+// Find all the bases in a fasta file or gzipped fasta file that are not allowed by the policy,
+// reading it one line at a time.  Text before the first header is an error.
+
+pub fn validate_fasta(
+    f: impl AsRef<Path>,
+    policy: BasePolicy,
+) -> Result<Vec<BaseViolation>, FastaError> {
     let f = f.as_ref();
-    match f.extension() {
-        Some(ex) if ex != "gz" => {
-            let fin = open_for_read![f];
-            let mut last: String = String::new();
-            let mut first = true;
-            for line in fin.lines() {
-                let s = line.unwrap();
-                if first {
-                    if !s.starts_with('>') {
-                        panic!("fasta format failure reading {}", f.to_string_lossy());
-                    }
-                    first = false;
-                    x.push(s.get(1..).unwrap().as_bytes().to_vec());
-                } else if s.starts_with('>') {
-                    x.push(last.as_bytes().to_vec());
-                    last.clear();
-                    x.push(s.get(1..).unwrap().as_bytes().to_vec());
-                } else {
-                    last += &s;
-                }
+    let (mut fin, gz) = open_fasta(f)?;
+    let mut violations = Vec::<BaseViolation>::new();
+    let mut record: Option<String> = None;
+    let mut buf = Vec::<u8>::new();
+    let mut line = 0;
+    while next_fasta_line(&mut fin, &mut buf, f, gz, &mut line)? {
+        if let Some(h) = buf.strip_prefix(b">") {
+            record = Some(String::from_utf8_lossy(h).to_string());
+            continue;
+        }
+        let r = record.as_ref().ok_or_else(|| FastaError {
+            path: f.to_path_buf(),
+            line,
+            kind: FastaErrorKind::MissingHeader,
+        })?;
+        for (i, &b) in buf.iter().enumerate() {
+            if !policy.allows(b) {
+                violations.push(BaseViolation {
+                    record: r.clone(),
+                    line,
+                    column: i + 1,
+                    byte: b,
+                });
             }
-            x.push(last.as_bytes().to_vec());
-        }
-        _ => {
-            let gz = MultiGzDecoder::new(std::fs::File::open(&f).unwrap());
-            let fin = std::io::BufReader::new(gz);
-            let mut last: String = String::new();
-            let mut first = true;
-            for line in fin.lines() {
-                let s = line.unwrap();
-                if first {
-                    if !s.starts_with('>') {
-                        panic!("fasta format failure");
-                    }
-                    first = false;
-                    x.push(s.get(1..).unwrap().as_bytes().to_vec());
-                } else if s.starts_with('>') {
-                    x.push(last.as_bytes().to_vec());
-                    last.clear();
-                    x.push(s.get(1..).unwrap().as_bytes().to_vec());
-                } else {
-                    last += &s;
-                }
+        }
+    }
+    Ok(violations)
+}
+
+// Replace the bases that are not allowed by the policy by the given byte, returning the
+// number replaced.
+
+pub fn sanitize_bases(x: &mut [u8], policy: BasePolicy, replacement: u8) -> usize {
+    let mut n = 0;
+    for b in x.iter_mut() {
+        if !policy.allows(*b) {
+            *b = replacement;
+            n += 1;
+        }
+    }
+    n
+}
+
+// Find the soft-masked regions of a sequence, which are the maximal runs of lowercase bytes,
+// as zero-based half-open intervals.
+
+pub fn masked_regions(seq: &[u8]) -> Vec<(usize, usize)> {
+    let mut regions = Vec::<(usize, usize)>::new();
+    let mut start: Option<usize> = None;
+    for (i, b) in seq.iter().enumerate() {
+        match (b.is_ascii_lowercase(), start) {
+            (true, None) => start = Some(i),
+            (false, Some(s)) => {
+                regions.push((s, i));
+                start = None;
             }
-            x.push(last.as_bytes().to_vec());
+            _ => {}
         }
+    }
+    if let Some(s) = start {
+        regions.push((s, seq.len()));
+    }
+    regions
+}
+
+// ▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓
+
+// What to do with N (in either case) in conversion to a DnaString: fail, replace it by a
+// fixed base, or replace it by a pseudorandom base that depends only on the seed and the
+// position of the N, so that conversion is reproducible.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NPolicy {
+    Error,
+    Fixed(u8),
+    Random(u64),
+}
+
+// A policy for conversion to a DnaString.  Lowercase bases are uppercased if uppercase is
+// set, and are otherwise an error.  N is handled according to n.  Any other byte is an
+// error, unless other is set, in which case it is replaced by that base.  Replacement bases
+// must be A, C, G or T.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DnaPolicy {
+    pub uppercase: bool,
+    pub n: NPolicy,
+    pub other: Option<u8>,
+}
+
+impl DnaPolicy {
+    // Allow only A, C, G and T, in uppercase.
+
+    pub const STRICT: DnaPolicy = DnaPolicy {
+        uppercase: false,
+        n: NPolicy::Error,
+        other: None,
+    };
+
+    // What DnaString::from_acgt_bytes does: everything other than A, C, G and T, in either
+    // case, becomes A.  This is the default.
+
+    pub const PERMISSIVE: DnaPolicy = DnaPolicy {
+        uppercase: true,
+        n: NPolicy::Fixed(b'A'),
+        other: Some(b'A'),
     };
-    x
+}
+
+impl Default for DnaPolicy {
+    fn default() -> Self {
+        DnaPolicy::PERMISSIVE
+    }
+}
+
+// A byte rejected in conversion to a DnaString, and its zero-based position.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SeqError {
+    pub position: usize,
+    pub byte: u8,
+}
+
+impl std::fmt::Display for SeqError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "disallowed base {} at position {}",
+            show_byte(self.byte),
+            self.position
+        )
+    }
+}
+
+impl std::error::Error for SeqError {}
+
+fn acgt_bits(b: u8) -> Option<u8> {
+    match b {
+        b'A' => Some(0),
+        b'C' => Some(1),
+        b'G' => Some(2),
+        b'T' => Some(3),
+        _ => None,
+    }
+}
+
+fn replacement_bits(b: u8) -> u8 {
+    acgt_bits(b).unwrap_or_else(|| panic!("replacement base {} is not A, C, G or T", show_byte(b)))
+}
+
+// A pseudorandom base for position i, using the splitmix64 mixing function.
+
+fn random_bits(seed: u64, i: usize) -> u8 {
+    let mut z = seed.wrapping_add((i as u64 + 1).wrapping_mul(0x9e3779b97f4a7c15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    ((z ^ (z >> 31)) & 3) as u8
+}
+
+// Convert bases to a DnaString according to the policy, failing at the first byte that the
+// policy rejects.
+
+pub fn to_dna_string_checked(seq: &[u8], policy: DnaPolicy) -> Result<DnaString, SeqError> {
+    let mut x = DnaString::with_capacity(seq.len());
+    for (i, &byte) in seq.iter().enumerate() {
+        let b = byte.to_ascii_uppercase();
+        let bits = match (acgt_bits(b), b, policy.n, policy.other) {
+            _ if b != byte && !policy.uppercase => return Err(SeqError { position: i, byte }),
+            (Some(bits), _, _, _) => bits,
+            (None, b'N', NPolicy::Fixed(r), _) => replacement_bits(r),
+            (None, b'N', NPolicy::Random(seed), _) => random_bits(seed, i),
+            (None, b'N', NPolicy::Error, _) => return Err(SeqError { position: i, byte }),
+            (None, _, _, Some(r)) => replacement_bits(r),
+            (None, _, _, None) => return Err(SeqError { position: i, byte }),
+        };
+        x.push(bits);
+    }
+    Ok(x)
 }
 
 // ▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓
 
-// This allows either a fasta file or a gzipped one.  This APPENDS to the
-// dv and headers vectors.
-// ◼ Kill the code duplication below.
+// Options for reading fasta files.  Bases are converted to a DnaString according to
+// dna_policy, which by default makes any base other than A, C, G or T (in either case) into
+// A.  Before that, bases may be checked against base_policy, and replaced if sanitize_with is
+// set.
 
-pub fn read_fasta_into_vec_dna_string_plus_headers(
+#[derive(Clone, Debug, Default)]
+pub struct FastaReadOptions {
+    // Fail if a record has the same header as an earlier record.
+    pub fail_on_duplicate_headers: bool,
+    // Fail if a record has no bases, rather than reading it as an empty sequence.
+    pub fail_on_empty_records: bool,
+    // Fail if a record has a base not allowed by this policy.
+    pub base_policy: Option<BasePolicy>,
+    // If base_policy is set, replace disallowed bases by this, rather than failing.
+    pub sanitize_with: Option<u8>,
+    // How to convert bases to a DnaString, failing on bases that it rejects.
+    pub dna_policy: DnaPolicy,
+}
+
+// This allows either a fasta file or a gzipped one, or - for stdin.  This APPENDS to the
+// dv and headers vectors, but only if the whole file is read successfully.  An empty file
+// adds nothing, and a record with no bases adds an empty DnaString, unless
+// fail_on_empty_records is set.
+
+pub fn try_read_fasta_into_vec_dna_string_plus_headers(
     f: impl AsRef<Path>,
     dv: &mut Vec<DnaString>,
     headers: &mut Vec<String>,
-) {
+) -> Result<(), FastaError> {
+    try_read_fasta_into_vec_dna_string_plus_headers_with(
+        f,
+        dv,
+        headers,
+        &FastaReadOptions::default(),
+    )
+}
+
+pub fn try_read_fasta_into_vec_dna_string_plus_headers_with(
+    f: impl AsRef<Path>,
+    dv: &mut Vec<DnaString>,
+    headers: &mut Vec<String>,
+    opts: &FastaReadOptions,
+) -> Result<(), FastaError> {
     let f = f.as_ref();
-    match f.extension() {
-        Some(ex) if ex != "gz" => {
-            let fin = open_for_read![f];
-            let mut last: String = String::new();
-            let mut first = true;
-            for line in fin.lines() {
-                let s = line.unwrap();
-                if first {
-                    if !s.starts_with('>') {
-                        panic!("fasta format failure reading {}", f.to_string_lossy());
-                    }
-                    first = false;
-                    headers.push(s.get(1..).unwrap().to_string());
-                } else if s.starts_with('>') {
-                    dv.push(DnaString::from_dna_string(&last));
-                    last.clear();
-                    headers.push(s.get(1..).unwrap().to_string());
-                } else {
-                    last += &s;
-                }
+    let (fin, gz) = open_fasta(f)?;
+    dna_strings_from(fin, f, gz, dv, headers, None, opts)
+}
+
+// As try_read_fasta_into_vec_dna_string_plus_headers_with, but also APPEND the soft-masked
+// regions of each record, as found by masked_regions, since case is lost in conversion to a
+// DnaString.
+
+pub fn try_read_fasta_into_vec_dna_string_plus_headers_and_masks(
+    f: impl AsRef<Path>,
+    dv: &mut Vec<DnaString>,
+    headers: &mut Vec<String>,
+    masks: &mut Vec<Vec<(usize, usize)>>,
+    opts: &FastaReadOptions,
+) -> Result<(), FastaError> {
+    let f = f.as_ref();
+    let (fin, gz) = open_fasta(f)?;
+    dna_strings_from(fin, f, gz, dv, headers, Some(masks), opts)
+}
+
+// As try_read_fasta_into_vec_dna_string_plus_headers_with, but reading from a reader, for
+// example a pipe.  The data may be gzipped.
+
+pub fn read_fasta_from_reader<R: BufRead>(
+    r: R,
+    dv: &mut Vec<DnaString>,
+    headers: &mut Vec<String>,
+    opts: &FastaReadOptions,
+) -> Result<(), FastaError> {
+    let path = Path::new(STREAM_PATH);
+    let (fin, gz) = decompress_if_gzipped(r, path)?;
+    dna_strings_from(fin, path, gz, dv, headers, None, opts)
+}
+
+fn dna_strings_from<R: BufRead>(
+    fin: R,
+    f: &Path,
+    gz: bool,
+    dv: &mut Vec<DnaString>,
+    headers: &mut Vec<String>,
+    masks: Option<&mut Vec<Vec<(usize, usize)>>>,
+    opts: &FastaReadOptions,
+) -> Result<(), FastaError> {
+    let (mut d, mut h) = (Vec::<DnaString>::new(), Vec::<String>::new());
+    let mut m = Vec::<Vec<(usize, usize)>>::new();
+    let mut seen = HashMap::<String, usize>::new();
+    let fail_on_empty = opts.fail_on_empty_records;
+    parse_fasta_records(fin, f, gz, fail_on_empty, |header, line, bases| {
+        if opts.fail_on_duplicate_headers {
+            if let Some(&first_line) = seen.get(&header) {
+                return Err(FastaErrorKind::DuplicateHeader { first_line });
             }
-            dv.push(DnaString::from_dna_string(&last));
-        }
-        _ => {
-            let gz = MultiGzDecoder::new(std::fs::File::open(&f).unwrap());
-            let fin = std::io::BufReader::new(gz);
-            let mut last: String = String::new();
-            let mut first = true;
-            for line in fin.lines() {
-                let s = line;
-                if s.is_err() {
-                    eprintln!(
-                        "\nError in read_fasta_into_vec_dna_string_plus_headers reading \
-                        file\n{}\nerr = {}",
-                        f.display(),
-                        s.err().unwrap(),
-                    );
-                    std::process::exit(1);
+            seen.insert(header.clone(), line);
+        }
+        let mut bases = bases.into_bytes();
+        if masks.is_some() {
+            m.push(masked_regions(&bases));
+        }
+        if let Some(policy) = opts.base_policy {
+            match opts.sanitize_with {
+                Some(r) => {
+                    sanitize_bases(&mut bases, policy, r);
                 }
-                let s = s.unwrap();
-                if first {
-                    if !s.starts_with('>') {
-                        panic!("fasta format failure");
+                None => {
+                    if let Some(position) = bases.iter().position(|&b| !policy.allows(b)) {
+                        return Err(FastaErrorKind::DisallowedBase {
+                            position,
+                            byte: bases[position],
+                        });
                     }
-                    first = false;
-                    headers.push(s.get(1..).unwrap().to_string());
-                } else if s.starts_with('>') {
-                    dv.push(DnaString::from_dna_string(&last));
-                    last.clear();
-                    headers.push(s.get(1..).unwrap().to_string());
-                } else {
-                    last += &s;
                 }
             }
-            dv.push(DnaString::from_dna_string(&last));
         }
-    };
+        let x = to_dna_string_checked(&bases, opts.dna_policy).map_err(|e| {
+            FastaErrorKind::DisallowedBase {
+                position: e.position,
+                byte: e.byte,
+            }
+        })?;
+        h.push(header);
+        d.push(x);
+        Ok(())
+    })?;
+    dv.append(&mut d);
+    headers.append(&mut h);
+    if let Some(masks) = masks {
+        masks.append(&mut m);
+    }
+    Ok(())
+}
+
+pub fn read_fasta_into_vec_dna_string_plus_headers(
+    f: impl AsRef<Path>,
+    dv: &mut Vec<DnaString>,
+    headers: &mut Vec<String>,
+) {
+    try_read_fasta_into_vec_dna_string_plus_headers(f, dv, headers)
+        .unwrap_or_else(|e| panic!("{}", e))
 }
 
 // ▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓
 
+// Read fasta records from a string, as for try_read_fasta_into_vec_dna_string_plus_headers.
+
+pub fn try_read_fasta_contents_into_vec_dna_string_plus_headers(
+    f: &str,
+    dv: &mut Vec<DnaString>,
+    headers: &mut Vec<String>,
+) -> Result<(), FastaError> {
+    let opts = FastaReadOptions::default();
+    let path = Path::new(STREAM_PATH);
+    dna_strings_from(f.as_bytes(), path, false, dv, headers, None, &opts)
+}
+
 pub fn read_fasta_contents_into_vec_dna_string_plus_headers(
     f: &str,
     dv: &mut Vec<DnaString>,
     headers: &mut Vec<String>,
 ) {
-    let mut last: String = String::new();
-    let mut first = true;
-    let lines = f.split('\n').collect::<Vec<&str>>();
-    for i in 0..lines.len() {
-        let s = &lines[i];
-        if first {
-            if !s.starts_with('>') {
-                panic!("fasta format failure reading {}", f);
+    try_read_fasta_contents_into_vec_dna_string_plus_headers(f, dv, headers)
+        .unwrap_or_else(|e| panic!("{}", e))
+}
+
+// A problem with a header in a fasta file, as found by check_fasta_headers.  Line numbers
+// start at one.
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FastaHeaderIssue {
+    // The header is the same as an earlier one.
+    Duplicate {
+        header: String,
+        first_line: usize,
+        line: usize,
+    },
+    // The header is just >.
+    Empty {
+        line: usize,
+    },
+    // The header contains a tab.
+    Tab {
+        header: String,
+        line: usize,
+    },
+}
+
+impl std::fmt::Display for FastaHeaderIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            FastaHeaderIssue::Duplicate {
+                header,
+                first_line,
+                line,
+            } => write!(
+                f,
+                "header {} at line {} duplicates that at line {}",
+                header, line, first_line
+            ),
+            FastaHeaderIssue::Empty { line } => write!(f, "empty header at line {}", line),
+            FastaHeaderIssue::Tab { header, line } => {
+                write!(f, "header {:?} at line {} contains a tab", header, line)
             }
-            first = false;
-            headers.push(s.get(1..).unwrap().to_string());
-        } else if s.starts_with('>') {
-            dv.push(DnaString::from_dna_string(&last));
-            last.clear();
-            headers.push(s.get(1..).unwrap().to_string());
-        } else {
-            last += s;
         }
     }
-    dv.push(DnaString::from_dna_string(&last));
 }
 
-// This APPENDS.
+// Check the headers in a fasta file or gzipped fasta file, returning the problems found, in
+// the order of the lines on which they occur.  Only headers are examined.
 
-pub fn read_fasta_headers(f: impl AsRef<Path>, headers: &mut Vec<String>) {
+pub fn check_fasta_headers(f: impl AsRef<Path>) -> Result<Vec<FastaHeaderIssue>, FastaError> {
     let f = f.as_ref();
-    let fin = open_for_read![f];
-    let mut last: String = String::new();
-    let mut first = true;
-    for line in fin.lines() {
-        let s = line.unwrap();
-        if first {
-            if !s.starts_with('>') {
-                panic!("fasta format failure reading {}", f.to_string_lossy());
+    let (mut fin, gz) = open_fasta(f)?;
+    let mut issues = Vec::new();
+    let mut seen = HashMap::<Vec<u8>, usize>::new();
+    let mut buf = Vec::<u8>::new();
+    let mut line = 0;
+    while next_fasta_line(&mut fin, &mut buf, f, gz, &mut line)? {
+        let h = match buf.strip_prefix(b">") {
+            Some(h) => h,
+            None => continue,
+        };
+        let header = String::from_utf8_lossy(h).to_string();
+        if h.is_empty() {
+            issues.push(FastaHeaderIssue::Empty { line });
+        } else if h.contains(&b'\t') {
+            issues.push(FastaHeaderIssue::Tab {
+                header: header.clone(),
+                line,
+            });
+        }
+        if !h.is_empty() {
+            match seen.get(h) {
+                Some(&first_line) => issues.push(FastaHeaderIssue::Duplicate {
+                    header,
+                    first_line,
+                    line,
+                }),
+                None => {
+                    seen.insert(h.to_vec(), line);
+                }
             }
-            first = false;
-            headers.push(s.get(1..).unwrap().to_string());
-        } else if s.starts_with('>') {
-            last.clear();
-            headers.push(s.get(1..).unwrap().to_string());
-        } else {
-            last += &s;
         }
     }
+    Ok(issues)
+}
+
+// Read the headers of a fasta file or gzipped fasta file, or - for stdin.  This APPENDS, but
+// only if the whole file is read successfully.  The file is checked as for the other readers.
+
+pub fn try_read_fasta_headers(
+    f: impl AsRef<Path>,
+    headers: &mut Vec<String>,
+) -> Result<(), FastaError> {
+    let f = f.as_ref();
+    let (fin, gz) = open_fasta(f)?;
+    let mut h = Vec::<String>::new();
+    parse_fasta_records(fin, f, gz, false, |header, _, _| {
+        h.push(header);
+        Ok(())
+    })?;
+    headers.append(&mut h);
+    Ok(())
+}
+
+pub fn read_fasta_headers(f: impl AsRef<Path>, headers: &mut Vec<String>) {
+    try_read_fasta_headers(f, headers).unwrap_or_else(|e| panic!("{}", e))
 }
 // ▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓
 
-// Load a GenBank accession.
+// Write records in fasta format, with the sequence lines wrapped at the given width, or not
+// wrapped if wrap is zero.  The headers should not include the >.  An empty sequence is an
+// InvalidInput error, and nothing is written for it, as such a record is rejected by readers
+// having fail_on_empty_records set, and by other tools.
+
+pub fn write_fasta_record<W: Write>(
+    w: &mut W,
+    header: &str,
+    seq: &[u8],
+    wrap: usize,
+) -> std::io::Result<()> {
+    if seq.is_empty() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("fasta record {} has no bases", header),
+        ));
+    }
+    writeln!(w, ">{}", header)?;
+    if wrap == 0 {
+        w.write_all(seq)?;
+        writeln!(w)?;
+    } else {
+        for line in seq.chunks(wrap) {
+            w.write_all(line)?;
+            writeln!(w)?;
+        }
+    }
+    Ok(())
+}
+
+pub fn write_fasta<W: Write>(
+    w: &mut W,
+    headers: &[String],
+    seqs: &[impl AsRef<[u8]>],
+    wrap: usize,
+) -> std::io::Result<()> {
+    assert_eq!(headers.len(), seqs.len());
+    for (h, s) in headers.iter().zip(seqs.iter()) {
+        write_fasta_record(w, h, s.as_ref(), wrap)?;
+    }
+    Ok(())
+}
+
+pub fn write_fasta_dna_strings<W: Write>(
+    w: &mut W,
+    headers: &[String],
+    dv: &[DnaString],
+    wrap: usize,
+) -> std::io::Result<()> {
+    assert_eq!(headers.len(), dv.len());
+    for (h, d) in headers.iter().zip(dv.iter()) {
+        write_fasta_record(w, h, d.to_string().as_bytes(), wrap)?;
+    }
+    Ok(())
+}
+
+// Write records as for write_fasta, to a gzipped file.
+
+pub fn write_fasta_gz(
+    f: impl AsRef<Path>,
+    headers: &[String],
+    seqs: &[impl AsRef<[u8]>],
+    wrap: usize,
+) -> std::io::Result<()> {
+    let file = std::fs::File::create(f)?;
+    let mut gz = GzEncoder::new(BufWriter::new(file), Compression::default());
+    write_fasta(&mut gz, headers, seqs, wrap)?;
+    gz.finish()?.flush()
+}
+
+// ▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓
+
+// Load a GenBank accession.  See the genbank module for versions that return errors.
 
 pub fn load_genbank_accession(accession: &str, bases: &mut DnaString) {
-    let link = format!(
-        "https://eutils.ncbi.nlm.nih.gov/entrez/eutils/\
-         efetch.fcgi?db=nucleotide&amp;id={}&amp;rettype=fasta",
-        accession
-    );
-    let o = Command::new("csh")
-        .arg("-c")
-        .arg(format!("curl \"{}\"", link))
-        .output()
-        .expect("failed to execute curl command");
-    let fasta = String::from_utf8(o.stdout).expect("Response was not valid utf-8.");
-    let mut fasta = fasta.after("\n").to_string();
-    // ◼ The following assert should not be necessary: the DnaString constructor
-    // ◼ should gag if it gets nonsense input.
-    assert!(!fasta.contains("moved"));
-    fasta = fasta.replace('\n', "");
-    *bases = DnaString::from_dna_string(&fasta);
+    *bases = load_genbank_accession_checked(accession).unwrap_or_else(|e| panic!("{}", e));
 }
 
 pub fn load_genbank_accession_as_fasta_bytes(accession: &str, bytes: &mut Vec<u8>) {
-    let link = format!(
-        "https://eutils.ncbi.nlm.nih.gov/entrez/eutils/\
-         efetch.fcgi?db=nucleotide&amp;id={}&amp;rettype=fasta",
-        accession
-    );
-    let o = Command::new("csh")
-        .arg("-c")
-        .arg(format!("curl \"{}\"", link))
-        .output()
-        .expect("failed to execute curl command");
-    let fasta = String::from_utf8(o.stdout).expect("Response was not valid utf-8.");
-    assert!(!fasta.contains("moved"));
+    let fasta = load_genbank_accession_fasta_checked(accession, &GbFetchOptions::default())
+        .unwrap_or_else(|e| panic!("{}", e));
     let fasta = fasta.rev_before("\n");
     *bytes = fasta.as_bytes().to_vec();
 }
+
+#[cfg(test)]
+mod tests {
+
+    // Write a file in the temp directory, with a name unique to this process, returning its
+    // path.  The file is gzipped if its name ends in .gz.
+
+    fn temp_file(name: &str, contents: &[u8]) -> std::path::PathBuf {
+        use flate2::{write::GzEncoder, Compression};
+        use std::io::Write;
+        let path =
+            std::env::temp_dir().join(format!("fasta_tools_{}_{}", std::process::id(), name));
+        let mut f = std::fs::File::create(&path).unwrap();
+        if name.ends_with(".gz") {
+            let mut gz = GzEncoder::new(f, Compression::default());
+            gz.write_all(contents).unwrap();
+            gz.finish().unwrap();
+        } else {
+            f.write_all(contents).unwrap();
+        }
+        path
+    }
+
+    #[test]
+    fn test_try_read_fasta() {
+        use crate::{
+            read_fasta_headers, read_fasta_into_vec_dna_string_plus_headers,
+            read_fasta_to_vec_vec_u8, try_read_fasta_into_vec_dna_string_plus_headers,
+            try_read_fasta_into_vec_dna_string_plus_headers_with, try_read_fasta_to_vec_vec_u8,
+            FastaErrorKind, FastaReadOptions,
+        };
+        let fa = b">a\nACGT\nAC\n>b desc\nTTT\n";
+        for name in ["ok.fa", "ok.fa.gz"] {
+            let f = temp_file(name, fa);
+            let x = try_read_fasta_to_vec_vec_u8(&f).unwrap();
+            let y: Vec<Vec<u8>> = [&b"a"[..], b"ACGTAC", b"b desc", b"TTT"]
+                .iter()
+                .map(|s| s.to_vec())
+                .collect();
+            assert_eq!(x, y);
+            let (mut dv, mut headers) = (Vec::new(), Vec::new());
+            try_read_fasta_into_vec_dna_string_plus_headers(&f, &mut dv, &mut headers).unwrap();
+            assert_eq!(headers, ["a", "b desc"]);
+            assert_eq!(dv[0].to_string(), "ACGTAC");
+            assert_eq!(dv[1].to_string(), "TTT");
+        }
+
+        // An empty file has no records.
+
+        let f = temp_file("empty.fa", b"");
+        assert!(try_read_fasta_to_vec_vec_u8(&f).unwrap().is_empty());
+
+        // Records with no bases, at the end or in the middle, are read as empty, as they
+        // always were by the panicking readers.
+
+        let f = temp_file("header_only.fa", b">a\n");
+        assert_eq!(
+            read_fasta_to_vec_vec_u8(&f),
+            vec![b"a".to_vec(), Vec::new()]
+        );
+        let f = temp_file("empty_record.fa", b">a\nAC\n>b\n>c\nGT\n");
+        assert_eq!(
+            read_fasta_to_vec_vec_u8(&f),
+            [&b"a"[..], b"AC", b"b", b"", b"c", b"GT"]
+                .iter()
+                .map(|s| s.to_vec())
+                .collect::<Vec<_>>()
+        );
+        let (mut dv, mut headers) = (Vec::new(), Vec::new());
+        read_fasta_into_vec_dna_string_plus_headers(&f, &mut dv, &mut headers);
+        assert_eq!(headers, ["a", "b", "c"]);
+        assert_eq!(dv.iter().map(|d| d.len()).collect::<Vec<_>>(), [2, 0, 2]);
+        let mut headers = Vec::new();
+        read_fasta_headers(&f, &mut headers);
+        assert_eq!(headers, ["a", "b", "c"]);
+
+        // Errors, with their line numbers.
+
+        let strict = FastaReadOptions {
+            fail_on_empty_records: true,
+            ..Default::default()
+        };
+        let err = |name: &str, contents: &[u8]| {
+            let f = temp_file(name, contents);
+            let (mut dv, mut headers) = (Vec::new(), Vec::new());
+            let e = try_read_fasta_into_vec_dna_string_plus_headers_with(
+                &f,
+                &mut dv,
+                &mut headers,
+                &strict,
+            )
+            .unwrap_err();
+            assert!(dv.is_empty() && headers.is_empty());
+            e
+        };
+        let e = err("header_only.fa", b">a\n");
+        assert!(matches!(e.kind, FastaErrorKind::EmptyRecord) && e.line == 1);
+        let e = err("empty_record.fa", b">a\nAC\n>b\n>c\nGT\n");
+        assert!(matches!(e.kind, FastaErrorKind::EmptyRecord) && e.line == 3);
+        let e = err("no_header.fa", b"ACGT\n>a\nAC\n");
+        assert!(matches!(e.kind, FastaErrorKind::MissingHeader) && e.line == 1);
+        let e = err("utf8.fa", b">a\nAC\n>\xff\nAC\n");
+        assert!(matches!(e.kind, FastaErrorKind::BadUtf8) && e.line == 3);
+        let f = temp_file("truncated.fa.gz", fa);
+        let mut gz = std::fs::read(&f).unwrap();
+        gz.truncate(gz.len() - 10);
+        std::fs::write(&f, &gz).unwrap();
+        let e = try_read_fasta_to_vec_vec_u8(&f).unwrap_err();
+        assert!(matches!(e.kind, FastaErrorKind::BadGzip(_)));
+        let e = try_read_fasta_to_vec_vec_u8("/nonexistent/x.fa").unwrap_err();
+        assert!(matches!(e.kind, FastaErrorKind::IoError(_)) && e.line == 0);
+        assert_eq!(
+            e.to_string(),
+            "error reading fasta file /nonexistent/x.fa: No such file or directory (os error 2)"
+        );
+    }
+
+    #[test]
+    fn test_write_fasta() {
+        use crate::{
+            read_fasta_to_vec_vec_u8, try_read_fasta_into_vec_dna_string_plus_headers_with,
+            write_fasta, write_fasta_dna_strings, write_fasta_gz, FastaErrorKind, FastaReadOptions,
+        };
+        use debruijn::dna_string::DnaString;
+        let headers = vec!["a".to_string(), "b two".to_string(), "c".to_string()];
+        let seqs = [&b"ACGTACGTA"[..], b"ACGTAC", b"T"];
+        let mut x = Vec::<u8>::new();
+        write_fasta(&mut x, &headers, &seqs, 3).unwrap();
+        assert_eq!(
+            std::str::from_utf8(&x).unwrap(),
+            ">a\nACG\nTAC\nGTA\n>b two\nACG\nTAC\n>c\nT\n"
+        );
+        let mut y = Vec::<u8>::new();
+        write_fasta(&mut y, &headers, &seqs, 0).unwrap();
+        assert_eq!(
+            std::str::from_utf8(&y).unwrap(),
+            ">a\nACGTACGTA\n>b two\nACGTAC\n>c\nT\n"
+        );
+        let dv = seqs
+            .iter()
+            .map(|s| DnaString::from_acgt_bytes(s))
+            .collect::<Vec<_>>();
+        let mut z = Vec::<u8>::new();
+        write_fasta_dna_strings(&mut z, &headers, &dv, 3).unwrap();
+        assert_eq!(x, z);
+
+        // Round trip, plain and gzipped.
+
+        let expected = headers
+            .iter()
+            .zip(seqs.iter())
+            .flat_map(|(h, s)| [h.as_bytes().to_vec(), s.to_vec()])
+            .collect::<Vec<_>>();
+        let f = temp_file("written.fa", &x);
+        assert_eq!(read_fasta_to_vec_vec_u8(&f), expected);
+        let g = f.with_extension("fa.gz");
+        write_fasta_gz(&g, &headers, &seqs, 3).unwrap();
+        assert_eq!(read_fasta_to_vec_vec_u8(&g), expected);
+
+        // An empty sequence, which could not be read back, is not written.
+
+        let headers = vec!["a".to_string(), "b".to_string()];
+        let seqs = [&b"ACG"[..], b""];
+        for &wrap in [0, 3].iter() {
+            let mut x = Vec::<u8>::new();
+            let e = write_fasta(&mut x, &headers, &seqs, wrap).unwrap_err();
+            assert_eq!(e.kind(), std::io::ErrorKind::InvalidInput);
+            assert_eq!(x, b">a\nACG\n");
+            let f = temp_file("written_empty.fa", &x);
+            assert_eq!(
+                read_fasta_to_vec_vec_u8(&f),
+                vec![b"a".to_vec(), b"ACG".to_vec()]
+            );
+        }
+        let f = temp_file("written_empty.fa", b">a\nACG\n>b\n");
+        let opts = FastaReadOptions {
+            fail_on_empty_records: true,
+            ..Default::default()
+        };
+        let (mut dv, mut headers) = (Vec::new(), Vec::new());
+        let e =
+            try_read_fasta_into_vec_dna_string_plus_headers_with(&f, &mut dv, &mut headers, &opts)
+                .unwrap_err();
+        assert!(matches!(e.kind, FastaErrorKind::EmptyRecord));
+    }
+
+    #[test]
+    fn test_reverse_complement() {
+        use crate::{reverse_complement, reverse_complement_strict};
+        assert_eq!(reverse_complement(b"AACGTTG"), b"CAACGTT");
+        assert_eq!(reverse_complement(b""), b"");
+        assert_eq!(reverse_complement(b"acgTN"), b"NAcgt");
+        assert_eq!(reverse_complement(b"RYSWKMBDHVN"), b"NBDHVKMWSRY");
+        assert_eq!(reverse_complement(b"ryswkmbdhvn"), b"nbdhvkmwsry");
+        assert_eq!(reverse_complement(b"AC-G."), b".C-GT");
+        assert_eq!(reverse_complement_strict(b"AcGR").unwrap(), b"YCgT");
+        assert_eq!(reverse_complement_strict(b"AC-G.T"), Err((2, b'-')));
+        assert_eq!(reverse_complement_strict(b"ACXG"), Err((2, b'X')));
+    }
+
+    #[test]
+    fn test_reverse_complement_variants() {
+        use crate::{rc_copy_into, reverse_complement, reverse_complement_in_place};
+
+        // The implementation before IUPAC codes were handled, for comparison on ACGTN.
+
+        fn old_rc(x: &[u8]) -> Vec<u8> {
+            x.iter()
+                .rev()
+                .map(|&b| match b {
+                    b'A' => b'T',
+                    b'C' => b'G',
+                    b'G' => b'C',
+                    b'T' => b'A',
+                    _ => b,
+                })
+                .collect()
+        }
+        let mut buf = Vec::<u8>::new();
+        for x in [&b""[..], b"A", b"AC", b"ACGTN", b"GATTACA", b"NNNACGT"] {
+            assert_eq!(reverse_complement(x), old_rc(x));
+            let mut y = x.to_vec();
+            reverse_complement_in_place(&mut y);
+            assert_eq!(y, old_rc(x));
+            rc_copy_into(x, &mut buf);
+            assert_eq!(buf, old_rc(x));
+        }
+
+        // Ten million bases.
+
+        let x = b"ACGTTGCANA".repeat(1_000_000);
+        let t = std::time::Instant::now();
+        let mut y = x.clone();
+        reverse_complement_in_place(&mut y);
+        rc_copy_into(&x, &mut buf);
+        let elapsed = t.elapsed();
+        assert_eq!(y, old_rc(&x));
+        assert_eq!(buf, y);
+        reverse_complement_in_place(&mut y);
+        assert_eq!(y, x);
+        assert!(elapsed.as_secs() < 10);
+    }
+
+    #[test]
+    fn test_fasta_headers() {
+        use crate::{
+            check_fasta_headers, try_read_fasta_into_vec_dna_string_plus_headers,
+            try_read_fasta_into_vec_dna_string_plus_headers_with, FastaErrorKind, FastaHeaderIssue,
+            FastaReadOptions,
+        };
+        let fa = b">a\nAC\n>\nGT\n>b\tx\nA\n>a\nC\n>c\nG\n>b\tx\nT\n";
+        let f = temp_file("bad_headers.fa.gz", fa);
+        let issues = check_fasta_headers(&f).unwrap();
+        assert_eq!(
+            issues,
+            vec![
+                FastaHeaderIssue::Empty { line: 3 },
+                FastaHeaderIssue::Tab {
+                    header: "b\tx".to_string(),
+                    line: 5
+                },
+                FastaHeaderIssue::Duplicate {
+                    header: "a".to_string(),
+                    first_line: 1,
+                    line: 7
+                },
+                FastaHeaderIssue::Tab {
+                    header: "b\tx".to_string(),
+                    line: 11
+                },
+                FastaHeaderIssue::Duplicate {
+                    header: "b\tx".to_string(),
+                    first_line: 5,
+                    line: 11
+                },
+            ]
+        );
+        assert_eq!(
+            issues[2].to_string(),
+            "header a at line 7 duplicates that at line 1"
+        );
+        let g = temp_file("good_headers.fa", b">a\nAC\n>b\nGT\n");
+        assert!(check_fasta_headers(&g).unwrap().is_empty());
+
+        // Reading, with and without failing on duplicates.
+
+        let (mut dv, mut headers) = (Vec::new(), Vec::new());
+        try_read_fasta_into_vec_dna_string_plus_headers(&f, &mut dv, &mut headers).unwrap();
+        assert_eq!(headers.len(), 6);
+        let opts = FastaReadOptions {
+            fail_on_duplicate_headers: true,
+            ..Default::default()
+        };
+        let (mut dv, mut headers) = (Vec::new(), Vec::new());
+        let e =
+            try_read_fasta_into_vec_dna_string_plus_headers_with(&f, &mut dv, &mut headers, &opts)
+                .unwrap_err();
+        assert!(matches!(
+            e.kind,
+            FastaErrorKind::DuplicateHeader { first_line: 1 }
+        ));
+        assert_eq!(e.line, 7);
+        assert!(headers.is_empty());
+    }
+
+    #[test]
+    fn test_validate_fasta() {
+        use crate::{
+            sanitize_bases, try_read_fasta_into_vec_dna_string_plus_headers_with, validate_fasta,
+            BasePolicy, BaseViolation, FastaErrorKind, FastaReadOptions,
+        };
+        use debruijn::dna_string::DnaString;
+        let fa = b">a\nACGTacgt\nACnNA\n>b\nRYacgt\n";
+        let f = temp_file("alphabet.fa", fa);
+        let v = |record: &str, line, column, byte| BaseViolation {
+            record: record.to_string(),
+            line,
+            column,
+            byte,
+        };
+        assert_eq!(
+            validate_fasta(&f, BasePolicy::Strict).unwrap(),
+            vec![
+                v("a", 3, 3, b'n'),
+                v("a", 3, 4, b'N'),
+                v("b", 5, 1, b'R'),
+                v("b", 5, 2, b'Y')
+            ]
+        );
+        assert_eq!(
+            validate_fasta(&f, BasePolicy::AllowN).unwrap(),
+            vec![v("b", 5, 1, b'R'), v("b", 5, 2, b'Y')]
+        );
+        assert!(validate_fasta(&f, BasePolicy::AllowIupac)
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            v("b", 5, 1, b'R').to_string(),
+            "line 5, column 1: disallowed base 'R' in record b"
+        );
+        let g = temp_file("alphabet_bad.fa", b">a\nAC-T\x01\n");
+        let x = validate_fasta(&g, BasePolicy::AllowIupac).unwrap();
+        assert_eq!(x, vec![v("a", 2, 3, b'-'), v("a", 2, 5, 1)]);
+        assert!(x[1].to_string().contains("base 0x01"));
+        assert!(matches!(
+            validate_fasta(
+                temp_file("alphabet_nohead.fa", b"ACGT\n"),
+                BasePolicy::Strict
+            )
+            .unwrap_err()
+            .kind,
+            FastaErrorKind::MissingHeader
+        ));
+
+        // Sanitizing.
+
+        let mut x = b"ACnNRt".to_vec();
+        assert_eq!(sanitize_bases(&mut x, BasePolicy::AllowN, b'C'), 1);
+        assert_eq!(x, b"ACnNCt");
+        assert_eq!(sanitize_bases(&mut x, BasePolicy::Strict, b'G'), 2);
+        assert_eq!(x, b"ACGGCt");
+
+        // The DnaString readers.
+
+        let (mut dv, mut headers) = (Vec::new(), Vec::new());
+        let mut opts = FastaReadOptions {
+            base_policy: Some(BasePolicy::AllowN),
+            ..Default::default()
+        };
+        let e =
+            try_read_fasta_into_vec_dna_string_plus_headers_with(&f, &mut dv, &mut headers, &opts)
+                .unwrap_err();
+        assert_eq!(e.line, 4);
+        assert!(matches!(
+            e.kind,
+            FastaErrorKind::DisallowedBase {
+                position: 0,
+                byte: b'R'
+            }
+        ));
+        assert!(e.to_string().ends_with("disallowed base 'R' at position 0"));
+        assert!(dv.is_empty());
+        opts.base_policy = Some(BasePolicy::Strict);
+        opts.sanitize_with = Some(b'C');
+        try_read_fasta_into_vec_dna_string_plus_headers_with(&f, &mut dv, &mut headers, &opts)
+            .unwrap();
+        assert_eq!(headers, vec!["a", "b"]);
+        assert_eq!(dv[0], DnaString::from_dna_string("ACGTACGTACCCA"));
+        assert_eq!(dv[1], DnaString::from_dna_string("CCACGT"));
+
+        // Without a policy, lowercase bases are converted, and others become A.
+
+        let opts = FastaReadOptions::default();
+        try_read_fasta_into_vec_dna_string_plus_headers_with(&f, &mut dv, &mut headers, &opts)
+            .unwrap();
+        assert_eq!(dv[2], DnaString::from_dna_string("ACGTACGTACAAA"));
+    }
+
+    #[test]
+    fn test_read_fasta_from_reader() {
+        use crate::{
+            read_fasta_from_reader, read_fasta_to_vec_vec_u8_from_reader,
+            try_read_fasta_to_vec_vec_u8, FastaErrorKind, FastaReadOptions,
+        };
+        use flate2::{write::GzEncoder, Compression};
+        use std::io::{Cursor, Write};
+        let fa = b">a\nACGT\nAC\n>b desc\nTTT\n";
+        let mut e = GzEncoder::new(Vec::new(), Compression::default());
+        e.write_all(fa).unwrap();
+        let gz = e.finish().unwrap();
+        let expected = read_fasta_to_vec_vec_u8_from_reader(Cursor::new(&fa[..])).unwrap();
+        assert_eq!(expected.len(), 4);
+        assert_eq!(
+            read_fasta_to_vec_vec_u8_from_reader(Cursor::new(&gz)).unwrap(),
+            expected
+        );
+        for x in [&fa[..], &gz] {
+            let (mut dv, mut headers) = (Vec::new(), Vec::new());
+            let opts = FastaReadOptions::default();
+            read_fasta_from_reader(Cursor::new(x), &mut dv, &mut headers, &opts).unwrap();
+            assert_eq!(headers, ["a", "b desc"]);
+            assert_eq!(dv[0].to_string(), "ACGTAC");
+        }
+        let e = read_fasta_to_vec_vec_u8_from_reader(Cursor::new(b"AC\n>a\nAC\n")).unwrap_err();
+        assert!(matches!(e.kind, FastaErrorKind::MissingHeader));
+        assert_eq!(
+            e.to_string(),
+            "error reading fasta file - at line 1: file does not start with >"
+        );
+
+        // Gzipped data are recognized by content rather than by name.
+
+        let f = temp_file("really_gzipped.fa", b"");
+        std::fs::write(&f, &gz).unwrap();
+        assert_eq!(try_read_fasta_to_vec_vec_u8(&f).unwrap(), expected);
+        let g = temp_file("not_gzipped.fa.gz", b"");
+        std::fs::write(&g, fa).unwrap();
+        assert_eq!(try_read_fasta_to_vec_vec_u8(&g).unwrap(), expected);
+    }
+
+    // All the readers give the same results for plain and gzipped files having the same
+    // contents, including errors, which are compared by line and kind.
+
+    #[test]
+    fn test_plain_and_gz_agree() {
+        use crate::{
+            read_fasta_from_reader, try_read_fasta_contents_into_vec_dna_string_plus_headers,
+            try_read_fasta_headers, try_read_fasta_into_vec_dna_string_plus_headers,
+            try_read_fasta_to_vec_vec_u8, FastaError, FastaReadOptions,
+        };
+        use std::path::Path;
+        type Outcome = Result<String, (usize, String)>;
+        let outcome = |r: Result<String, FastaError>| -> Outcome {
+            r.map_err(|e| (e.line, format!("{:?}", e.kind)))
+        };
+        let readers = |f: &Path| -> Vec<Outcome> {
+            let (mut dv, mut headers) = (Vec::new(), Vec::new());
+            let dna = try_read_fasta_into_vec_dna_string_plus_headers(f, &mut dv, &mut headers)
+                .map(|_| format!("{:?} {:?}", headers, dv));
+            let mut headers = Vec::new();
+            let h = try_read_fasta_headers(f, &mut headers).map(|_| format!("{:?}", headers));
+            let (mut dv, mut headers) = (Vec::new(), Vec::new());
+            let opts = FastaReadOptions::default();
+            let bytes = std::fs::read(f).unwrap();
+            let r = read_fasta_from_reader(&bytes[..], &mut dv, &mut headers, &opts)
+                .map(|_| format!("{:?} {:?}", headers, dv));
+            vec![
+                outcome(try_read_fasta_to_vec_vec_u8(f).map(|x| format!("{:?}", x))),
+                outcome(dna),
+                outcome(h),
+                outcome(r),
+            ]
+        };
+        let cases: [&[u8]; 9] = [
+            b">a\nACGT\nAC\n>b desc\nTTT\n",
+            b">a\r\nACGT\r\n>b\r\nGG",
+            b"",
+            b">a\n",
+            b">a\nAC\n>b\n>c\nGT\n",
+            b"ACGT\n>a\nAC\n",
+            b">a\nAC\n>\xff\nAC\n",
+            b">a\nAC\n>b\nA\xffC\n",
+            b"\n>a\nAC\n",
+        ];
+        for (i, fa) in cases.iter().enumerate() {
+            let plain = readers(&temp_file(&format!("agree{}.fa", i), fa));
+            let gz = readers(&temp_file(&format!("agree{}.fa.gz", i), fa));
+            assert_eq!(plain, gz, "case {}", i);
+            for x in plain.iter() {
+                assert_eq!(x.is_ok(), plain[0].is_ok(), "case {}", i);
+            }
+
+            // Reading from a string agrees too, when the contents are valid UTF-8.
+
+            if let Ok(s) = std::str::from_utf8(fa) {
+                let (mut dv, mut headers) = (Vec::new(), Vec::new());
+                let c = try_read_fasta_contents_into_vec_dna_string_plus_headers(
+                    s,
+                    &mut dv,
+                    &mut headers,
+                )
+                .map(|_| format!("{:?} {:?}", headers, dv));
+                assert_eq!(outcome(c), plain[1], "case {}", i);
+            }
+        }
+    }
+
+    #[test]
+    fn test_masked_regions() {
+        use crate::{
+            masked_regions, try_read_fasta_into_vec_dna_string_plus_headers_and_masks,
+            try_read_fasta_to_vec_vec_u8, FastaReadOptions,
+        };
+        assert!(masked_regions(b"").is_empty());
+        assert!(masked_regions(b"ACGTN").is_empty());
+        assert_eq!(masked_regions(b"acgt"), vec![(0, 4)]);
+        let x = b"acGTTaaCCCgNnnTna";
+        assert_eq!(
+            masked_regions(x),
+            vec![(0, 2), (5, 7), (10, 11), (12, 14), (15, 17)]
+        );
+
+        // The Vec<u8> reader preserves case, and the DnaString reader can return the masks.
+
+        let f = temp_file("masked.fa.gz", b">a\nacGTTaa\nCCCg\n>b\nACGT\n>c\nACgt\n");
+        let v = try_read_fasta_to_vec_vec_u8(&f).unwrap();
+        assert_eq!(v[1], b"acGTTaaCCCg");
+        let (mut dv, mut headers, mut masks) = (Vec::new(), Vec::new(), vec![vec![(0, 1)]]);
+        let opts = FastaReadOptions::default();
+        try_read_fasta_into_vec_dna_string_plus_headers_and_masks(
+            &f,
+            &mut dv,
+            &mut headers,
+            &mut masks,
+            &opts,
+        )
+        .unwrap();
+        assert_eq!(dv.len(), 3);
+        assert_eq!(dv[0].to_string(), "ACGTTAACCCG");
+        assert_eq!(
+            masks,
+            vec![
+                vec![(0, 1)],
+                vec![(0, 2), (5, 7), (10, 11)],
+                vec![],
+                vec![(2, 4)]
+            ]
+        );
+    }
+
+    #[test]
+    fn test_to_dna_string_checked() {
+        use crate::{
+            to_dna_string_checked, try_read_fasta_into_vec_dna_string_plus_headers_with, DnaPolicy,
+            FastaErrorKind, FastaReadOptions, NPolicy, SeqError,
+        };
+        use debruijn::dna_string::DnaString;
+        let conv = |seq: &[u8], policy| to_dna_string_checked(seq, policy).map(|x| x.to_string());
+        let err = |position, byte| Err(SeqError { position, byte });
+
+        // The default is what from_acgt_bytes does.
+
+        let x = b"ACgtNnRy-A";
+        assert_eq!(
+            to_dna_string_checked(x, DnaPolicy::default()).unwrap(),
+            DnaString::from_acgt_bytes(x)
+        );
+        assert_eq!(conv(x, DnaPolicy::PERMISSIVE).unwrap(), "ACGTAAAAAA");
+
+        // Strict, and each relaxation of it.
+
+        assert_eq!(conv(b"ACGT", DnaPolicy::STRICT).unwrap(), "ACGT");
+        assert_eq!(conv(b"", DnaPolicy::STRICT).unwrap(), "");
+        assert_eq!(conv(b"ACgT", DnaPolicy::STRICT), err(2, b'g'));
+        assert_eq!(conv(b"ACNT", DnaPolicy::STRICT), err(2, b'N'));
+        let upper = DnaPolicy {
+            uppercase: true,
+            ..DnaPolicy::STRICT
+        };
+        assert_eq!(conv(b"acgT", upper).unwrap(), "ACGT");
+        assert_eq!(conv(b"acnT", upper), err(2, b'n'));
+        let fixed = DnaPolicy {
+            n: NPolicy::Fixed(b'C'),
+            ..DnaPolicy::STRICT
+        };
+        assert_eq!(conv(b"ANNT", fixed).unwrap(), "ACCT");
+        assert_eq!(conv(b"AnT", fixed), err(1, b'n'));
+        assert_eq!(conv(b"ANRT", fixed), err(2, b'R'));
+        let other = DnaPolicy {
+            other: Some(b'G'),
+            ..fixed
+        };
+        assert_eq!(conv(b"ANRT-", other).unwrap(), "ACGTG");
+        assert_eq!(conv(b"Ar", other), err(1, b'r'));
+
+        // Random replacement of N is reproducible, depends on the seed, and leaves other bases
+        // alone.
+
+        let random = |seed| DnaPolicy {
+            n: NPolicy::Random(seed),
+            ..DnaPolicy::STRICT
+        };
+        let n = vec![b'N'; 200];
+        let (r1, r2) = (conv(&n, random(1)).unwrap(), conv(&n, random(2)).unwrap());
+        assert_eq!(r1, conv(&n, random(1)).unwrap());
+        assert_ne!(r1, r2);
+        for b in "ACGT".chars() {
+            assert!(r1.chars().filter(|&c| c == b).count() > 20, "{}", r1);
+        }
+        let y = conv(b"ACNNGT", random(7)).unwrap();
+        assert!(y.starts_with("AC") && y.ends_with("GT"));
+        assert_eq!(conv(b"ACNR", random(7)), err(3, b'R'));
+
+        // In the readers.
+
+        let f = temp_file("dna_policy.fa", b">a\nACGT\nACnN\n>b\nGG\n");
+        let (mut dv, mut headers) = (Vec::new(), Vec::new());
+        let mut opts = FastaReadOptions {
+            dna_policy: upper,
+            ..Default::default()
+        };
+        let e =
+            try_read_fasta_into_vec_dna_string_plus_headers_with(&f, &mut dv, &mut headers, &opts)
+                .unwrap_err();
+        assert_eq!(e.line, 1);
+        assert!(matches!(
+            e.kind,
+            FastaErrorKind::DisallowedBase {
+                position: 6,
+                byte: b'n'
+            }
+        ));
+        assert!(dv.is_empty());
+        opts.dna_policy.n = NPolicy::Fixed(b'T');
+        try_read_fasta_into_vec_dna_string_plus_headers_with(&f, &mut dv, &mut headers, &opts)
+            .unwrap();
+        assert_eq!(dv[0].to_string(), "ACGTACTT");
+    }
+}
@@ -0,0 +1,272 @@
+// Copyright (c) 2018 10x Genomics, Inc. All rights reserved.
+
+// Merging fasta files, handling records in different files, or the same file, that have the
+// same id, which is the part of the header before the first space.
+
+use crate::{next_fasta_line, open_fasta, FastaError, FastaErrorKind};
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+// What to do with a record whose id is the same as that of another record:
+// Error: fail, so that nothing after the preceding record is written;
+// KeepFirst: write only the first record having the id;
+// KeepLast: write only the last record having the id;
+// RenameWithSuffix: write all the records, changing the id of the second to id_2, and so on,
+// skipping suffixes that would give the id of any record in the inputs, or an id already given.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DupPolicy {
+    Error,
+    KeepFirst,
+    KeepLast,
+    RenameWithSuffix,
+}
+
+// Counts for one input: the number of records read, the number written (including those
+// renamed), the number dropped because of a duplicate id, and the number renamed.
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MergeCounts {
+    pub path: PathBuf,
+    pub records: usize,
+    pub written: usize,
+    pub dropped: usize,
+    pub renamed: usize,
+}
+
+// The counts for each input, in order, and the ids that appeared more than once, sorted.
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MergeReport {
+    pub inputs: Vec<MergeCounts>,
+    pub duplicate_ids: Vec<String>,
+}
+
+fn id_of(header: &[u8]) -> String {
+    String::from_utf8_lossy(header.split(|&c| c == b' ').next().unwrap()).to_string()
+}
+
+// For each id, the input and record number of its last occurrence, for KeepLast, and so that
+// RenameWithSuffix knows every id in the inputs.
+
+fn last_occurrences(inputs: &[&Path]) -> Result<HashMap<String, (usize, usize)>, FastaError> {
+    let mut last = HashMap::<String, (usize, usize)>::new();
+    let mut buf = Vec::<u8>::new();
+    for (i, f) in inputs.iter().enumerate() {
+        let (mut fin, gz) = open_fasta(f)?;
+        let (mut line, mut k) = (0, 0);
+        while next_fasta_line(&mut fin, &mut buf, f, gz, &mut line)? {
+            if let Some(h) = buf.strip_prefix(b">") {
+                last.insert(id_of(h), (i, k));
+                k += 1;
+            }
+        }
+    }
+    Ok(last)
+}
+
+// Write the records of fasta files or gzipped fasta files, in order, to out, handling
+// duplicate ids according to the policy.  Lines are written as they are, except for the
+// headers of renamed records, and line endings are written as \n.  Each input is read once,
+// except that for KeepLast and RenameWithSuffix, the inputs are read twice, so they cannot
+// include stdin.
+
+pub fn merge_fastas(
+    inputs: &[&Path],
+    mut out: impl Write,
+    dup_policy: DupPolicy,
+) -> Result<MergeReport, FastaError> {
+    let last = match dup_policy {
+        DupPolicy::KeepLast | DupPolicy::RenameWithSuffix => last_occurrences(inputs)?,
+        _ => HashMap::new(),
+    };
+    let mut report = MergeReport::default();
+    let mut first = HashMap::<String, (usize, usize)>::new();
+    let mut used = HashSet::<String>::new();
+    let mut renamed = HashSet::<String>::new();
+    let mut dups = HashSet::<String>::new();
+    let mut buf = Vec::<u8>::new();
+    for (i, &f) in inputs.iter().enumerate() {
+        let (mut fin, gz) = open_fasta(f)?;
+        let err = |line, kind| FastaError {
+            path: f.to_path_buf(),
+            line,
+            kind,
+        };
+        let mut counts = MergeCounts {
+            path: f.to_path_buf(),
+            ..Default::default()
+        };
+        let (mut line, mut started, mut writing) = (0, false, None);
+        while next_fasta_line(&mut fin, &mut buf, f, gz, &mut line)? {
+            if let Some(h) = buf.strip_prefix(b">") {
+                started = true;
+                let id = id_of(h);
+                let k = counts.records;
+                counts.records += 1;
+                let dup = used.contains(&id);
+                if dup {
+                    dups.insert(id.clone());
+                }
+                let mut header = buf.clone();
+                let keep = match dup_policy {
+                    DupPolicy::Error if dup => {
+                        let (fi, first_line) = first[&id];
+                        return Err(err(
+                            line,
+                            FastaErrorKind::DuplicateId {
+                                id,
+                                first_path: inputs[fi].to_path_buf(),
+                                first_line,
+                            },
+                        ));
+                    }
+                    DupPolicy::KeepFirst => !dup,
+                    DupPolicy::KeepLast => last[&id] == (i, k),
+                    DupPolicy::RenameWithSuffix if dup => {
+                        let mut n = 2;
+                        let mut new_id = format!("{}_{}", id, n);
+                        while last.contains_key(&new_id) || renamed.contains(&new_id) {
+                            n += 1;
+                            new_id = format!("{}_{}", id, n);
+                        }
+                        header = format!(">{}", new_id).into_bytes();
+                        let id_len = h.iter().position(|&c| c == b' ').unwrap_or(h.len());
+                        header.extend_from_slice(&h[id_len..]);
+                        renamed.insert(new_id);
+                        counts.renamed += 1;
+                        true
+                    }
+                    _ => true,
+                };
+                first.entry(id.clone()).or_insert((i, line));
+                used.insert(id);
+                if keep {
+                    counts.written += 1;
+                } else {
+                    counts.dropped += 1;
+                }
+                writing = keep.then_some(header);
+                if let Some(header) = &writing {
+                    out.write_all(header)
+                        .and_then(|_| out.write_all(b"\n"))
+                        .map_err(|e| err(line, FastaErrorKind::IoError(e)))?;
+                }
+            } else if writing.is_some() {
+                out.write_all(&buf)
+                    .and_then(|_| out.write_all(b"\n"))
+                    .map_err(|e| err(line, FastaErrorKind::IoError(e)))?;
+            } else if !started {
+                return Err(err(line, FastaErrorKind::MissingHeader));
+            }
+        }
+        report.inputs.push(counts);
+    }
+    report.duplicate_ids = dups.into_iter().collect();
+    report.duplicate_ids.sort();
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_fastas() {
+        use flate2::{write::GzEncoder, Compression};
+        let dir = std::env::temp_dir();
+        let path = |name: &str| dir.join(format!("fasta_tools_{}_{}", std::process::id(), name));
+        let (f1, f2, f3) = (path("merge1.fa"), path("merge2.fa.gz"), path("merge3.fa"));
+        std::fs::write(&f1, b">chr1 host\nACGT\nAC\n>spike1\nGGGG\n").unwrap();
+        let mut e = GzEncoder::new(std::fs::File::create(&f2).unwrap(), Compression::default());
+        e.write_all(b">spike1 again\nTTTT\n>chr1_2\nCC\n>IGHV1\nAAA\n")
+            .unwrap();
+        e.finish().unwrap();
+        std::fs::write(&f3, b">chr1 third\nGA\n").unwrap();
+        let inputs = [f1.as_path(), f2.as_path(), f3.as_path()];
+        let merge = |policy| {
+            let mut out = Vec::<u8>::new();
+            merge_fastas(&inputs, &mut out, policy).map(|r| (r, String::from_utf8(out).unwrap()))
+        };
+        let counts = |r: &MergeReport| {
+            r.inputs
+                .iter()
+                .map(|c| (c.records, c.written, c.dropped, c.renamed))
+                .collect::<Vec<_>>()
+        };
+
+        // Error.
+
+        let e = merge(DupPolicy::Error).unwrap_err();
+        assert_eq!(e.path, f2);
+        assert_eq!(e.line, 1);
+        assert!(matches!(
+            e.kind,
+            FastaErrorKind::DuplicateId { ref id, first_line: 4, .. } if id == "spike1"
+        ));
+        let mut out = Vec::<u8>::new();
+        let r = merge_fastas(&inputs[1..], &mut out, DupPolicy::Error).unwrap();
+        assert_eq!(counts(&r), vec![(3, 3, 0, 0), (1, 1, 0, 0)]);
+
+        // KeepFirst.
+
+        let (r, out) = merge(DupPolicy::KeepFirst).unwrap();
+        assert_eq!(
+            out,
+            ">chr1 host\nACGT\nAC\n>spike1\nGGGG\n>chr1_2\nCC\n>IGHV1\nAAA\n"
+        );
+        assert_eq!(counts(&r), vec![(2, 2, 0, 0), (3, 2, 1, 0), (1, 0, 1, 0)]);
+        assert_eq!(r.duplicate_ids, vec!["chr1", "spike1"]);
+        assert_eq!(r.inputs[1].path, f2);
+
+        // KeepLast.
+
+        let (r, out) = merge(DupPolicy::KeepLast).unwrap();
+        assert_eq!(
+            out,
+            ">spike1 again\nTTTT\n>chr1_2\nCC\n>IGHV1\nAAA\n>chr1 third\nGA\n"
+        );
+        assert_eq!(counts(&r), vec![(2, 0, 2, 0), (3, 3, 0, 0), (1, 1, 0, 0)]);
+
+        // RenameWithSuffix, where chr1_2 is already in use.
+
+        let (r, out) = merge(DupPolicy::RenameWithSuffix).unwrap();
+        assert_eq!(
+            out,
+            ">chr1 host\nACGT\nAC\n>spike1\nGGGG\n>spike1_2 again\nTTTT\n>chr1_2\nCC\n\
+             >IGHV1\nAAA\n>chr1_3 third\nGA\n"
+        );
+        assert_eq!(counts(&r), vec![(2, 2, 0, 0), (3, 3, 0, 1), (1, 1, 0, 1)]);
+        assert_eq!(r.duplicate_ids, vec!["chr1", "spike1"]);
+
+        // RenameWithSuffix, where chr1_2 appears only after chr1 is first renamed, and so is
+        // not a duplicate, and chr1_3 has already been given.
+
+        let inputs = [f3.as_path(), f1.as_path(), f2.as_path(), f3.as_path()];
+        let mut out = Vec::<u8>::new();
+        let r = merge_fastas(&inputs, &mut out, DupPolicy::RenameWithSuffix).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            ">chr1 third\nGA\n>chr1_3 host\nACGT\nAC\n>spike1\nGGGG\n>spike1_2 again\nTTTT\n\
+             >chr1_2\nCC\n>IGHV1\nAAA\n>chr1_4 third\nGA\n"
+        );
+        assert_eq!(
+            counts(&r),
+            vec![(1, 1, 0, 0), (2, 2, 0, 1), (3, 3, 0, 1), (1, 1, 0, 1)]
+        );
+        assert_eq!(r.duplicate_ids, vec!["chr1", "spike1"]);
+
+        // Any line before the first header is an error, as for the fasta readers, even if
+        // the file starts with a blank line.
+
+        let f4 = path("merge4.fa");
+        std::fs::write(&f4, b"\nACGT\n>chr9\nGG\n").unwrap();
+        for policy in [DupPolicy::Error, DupPolicy::KeepLast] {
+            let mut out = Vec::<u8>::new();
+            let e = merge_fastas(&[f1.as_path(), f4.as_path()], &mut out, policy).unwrap_err();
+            assert_eq!((e.path.as_path(), e.line), (f4.as_path(), 1));
+            assert!(matches!(e.kind, FastaErrorKind::MissingHeader));
+        }
+    }
+}
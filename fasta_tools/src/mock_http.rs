@@ -0,0 +1,35 @@
+// Copyright (c) 2018 10x Genomics, Inc. All rights reserved.
+
+// A mock HTTP server, for testing the GenBank fetching functions and their callers without
+// network access.  This is compiled for the tests of this crate, and otherwise only with the
+// mock_http feature.
+
+use std::io::{Read, Write};
+
+// Serve the given responses, one per connection, on a local port, and return its base URL.
+
+pub fn mock_server(responses: Vec<String>) -> String {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    std::thread::spawn(move || {
+        for r in responses {
+            let (mut s, _) = listener.accept().unwrap();
+            let mut buf = [0u8; 4096];
+            let _ = s.read(&mut buf).unwrap();
+            s.write_all(r.as_bytes()).unwrap();
+        }
+    });
+    format!("http://127.0.0.1:{}/efetch.fcgi", port)
+}
+
+// Form a response with the given status, extra header lines (each ending with \r\n), and body.
+
+pub fn response(status: &str, headers: &str, body: &str) -> String {
+    format!(
+        "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n{}\r\n{}",
+        status,
+        body.len(),
+        headers,
+        body
+    )
+}
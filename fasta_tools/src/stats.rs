@@ -0,0 +1,238 @@
+// Copyright (c) 2018 10x Genomics, Inc. All rights reserved.
+
+// Summary statistics for fasta files.
+
+use crate::{next_fasta_line, open_fasta, FastaError, FastaErrorKind};
+use std::path::Path;
+use string_utils::add_commas;
+use tables::print_tabular_vbox;
+
+// Statistics for one record.  The GC fraction is the fraction of A, C, G and T bases that are
+// G or C, ignoring case, and is NaN if there are no such bases.  The N fraction is the
+// fraction of all bases that are N or n.
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RecordStats {
+    pub name: String,
+    pub length: usize,
+    pub gc_fraction: f64,
+    pub n_fraction: f64,
+}
+
+// Statistics for a fasta file.  The N50 is the largest length L such that records of length at
+// least L contain at least half of all bases, and similarly for the N90 and 90%.  These are
+// computed from the full record lengths, and also, for n50_excluding_n and n90_excluding_n,
+// from the record lengths after removing Ns.  The GC and N fractions are as for RecordStats,
+// over all records.  If there are no records, the lengths are all zero.
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FastaStats {
+    pub records: Vec<RecordStats>,
+    pub total_bases: usize,
+    pub min_length: usize,
+    pub max_length: usize,
+    pub mean_length: f64,
+    pub n50: usize,
+    pub n90: usize,
+    pub n50_excluding_n: usize,
+    pub n90_excluding_n: usize,
+    pub gc_fraction: f64,
+    pub n_fraction: f64,
+}
+
+// Base counts for a record: GC, AT, N, and other.
+
+#[derive(Clone, Copy, Default)]
+struct BaseCounts {
+    gc: usize,
+    at: usize,
+    n: usize,
+    other: usize,
+}
+
+impl BaseCounts {
+    fn add(&mut self, x: &[u8]) {
+        for &c in x.iter() {
+            match c {
+                b'G' | b'C' | b'g' | b'c' => self.gc += 1,
+                b'A' | b'T' | b'a' | b't' => self.at += 1,
+                b'N' | b'n' => self.n += 1,
+                _ => self.other += 1,
+            }
+        }
+    }
+
+    fn total(&self) -> usize {
+        self.gc + self.at + self.n + self.other
+    }
+
+    fn gc_fraction(&self) -> f64 {
+        self.gc as f64 / (self.gc + self.at) as f64
+    }
+
+    fn n_fraction(&self) -> f64 {
+        self.n as f64 / self.total() as f64
+    }
+}
+
+// Compute the length L such that lengths of at least L sum to at least frac of the total.
+
+fn nxx(lens: &mut [usize], frac: f64) -> usize {
+    lens.sort_unstable_by(|a, b| b.cmp(a));
+    let total = lens.iter().sum::<usize>();
+    let mut sum = 0;
+    for &l in lens.iter() {
+        sum += l;
+        if sum as f64 >= frac * total as f64 {
+            return l;
+        }
+    }
+    0
+}
+
+// Compute statistics for a fasta file or gzipped fasta file, reading it one line at a time.
+// A record with no bases is an error, as for the fasta readers with fail_on_empty_records set.
+
+pub fn fasta_stats(f: impl AsRef<Path>) -> Result<FastaStats, FastaError> {
+    let f = f.as_ref();
+    let (mut fin, gz) = open_fasta(f)?;
+    let mut records = Vec::<RecordStats>::new();
+    let (mut lens, mut lens_no_n) = (Vec::<usize>::new(), Vec::<usize>::new());
+    let mut all = BaseCounts::default();
+    let mut current: Option<(String, usize, BaseCounts)> = None;
+    let mut finish = |name: String, line: usize, c: BaseCounts| {
+        if c.total() == 0 {
+            return Err(FastaError {
+                path: f.to_path_buf(),
+                line,
+                kind: FastaErrorKind::EmptyRecord,
+            });
+        }
+        records.push(RecordStats {
+            name,
+            length: c.total(),
+            gc_fraction: c.gc_fraction(),
+            n_fraction: c.n_fraction(),
+        });
+        lens.push(c.total());
+        lens_no_n.push(c.total() - c.n);
+        Ok(())
+    };
+    let mut buf = Vec::<u8>::new();
+    let mut line = 0;
+    while next_fasta_line(&mut fin, &mut buf, f, gz, &mut line)? {
+        if let Some(h) = buf.strip_prefix(b">") {
+            let name = String::from_utf8_lossy(h).to_string();
+            if let Some((prev, l, c)) = current.replace((name, line, BaseCounts::default())) {
+                finish(prev, l, c)?;
+            }
+        } else {
+            match current.as_mut() {
+                Some((_, _, c)) => c.add(&buf),
+                None => {
+                    return Err(FastaError {
+                        path: f.to_path_buf(),
+                        line,
+                        kind: FastaErrorKind::MissingHeader,
+                    })
+                }
+            }
+            all.add(&buf);
+        }
+    }
+    if let Some((name, l, c)) = current {
+        finish(name, l, c)?;
+    }
+    let total_bases = all.total();
+    Ok(FastaStats {
+        total_bases,
+        min_length: lens.iter().copied().min().unwrap_or(0),
+        max_length: lens.iter().copied().max().unwrap_or(0),
+        mean_length: if lens.is_empty() {
+            0.0
+        } else {
+            total_bases as f64 / lens.len() as f64
+        },
+        n50: nxx(&mut lens, 0.5),
+        n90: nxx(&mut lens, 0.9),
+        n50_excluding_n: nxx(&mut lens_no_n, 0.5),
+        n90_excluding_n: nxx(&mut lens_no_n, 0.9),
+        gc_fraction: all.gc_fraction(),
+        n_fraction: all.n_fraction(),
+        records,
+    })
+}
+
+// Display the statistics for the whole file as a table.
+
+impl std::fmt::Display for FastaStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let rows = [
+            ("records", add_commas(self.records.len())),
+            ("total bases", add_commas(self.total_bases)),
+            ("min length", add_commas(self.min_length)),
+            ("mean length", format!("{:.1}", self.mean_length)),
+            ("max length", add_commas(self.max_length)),
+            ("N50", add_commas(self.n50)),
+            ("N90", add_commas(self.n90)),
+            ("N50 excluding Ns", add_commas(self.n50_excluding_n)),
+            ("N90 excluding Ns", add_commas(self.n90_excluding_n)),
+            ("GC fraction", format!("{:.4}", self.gc_fraction)),
+            ("N fraction", format!("{:.4}", self.n_fraction)),
+        ]
+        .iter()
+        .map(|(k, v)| vec![k.to_string(), v.clone()])
+        .collect::<Vec<_>>();
+        let mut log = String::new();
+        print_tabular_vbox(&mut log, &rows, 2, b"l|r", false, false);
+        write!(f, "{}", log)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fasta_stats() {
+        // Five records, of lengths 10, 4, 7, 2 and 12 (of which 6 are N), with a total of 35.
+
+        let fa = b">r1 one\nACGTACGTAC\n>r2\nGGGG\n>r3\nattAC\ncg\n>r4\nNN\n>r5\nNNNGCA\nTTTNNN\n";
+        let f = std::env::temp_dir().join(format!("fasta_tools_{}_stats.fa", std::process::id()));
+        std::fs::write(&f, &fa[..]).unwrap();
+        let s = fasta_stats(&f).unwrap();
+        assert_eq!(s.records.len(), 5);
+        assert_eq!(s.total_bases, 35);
+        assert_eq!((s.min_length, s.max_length), (2, 12));
+        assert_eq!(s.mean_length, 7.0);
+
+        // Sorted lengths are 12, 10, 7, 4, 2, with cumulative sums 12, 22, 29, 33, 35, so the
+        // N50 is 10 (22 >= 17.5) and the N90 is 4 (33 >= 31.5).  Excluding Ns, the lengths are
+        // 10, 7, 6, 4, 0, with cumulative sums 10, 17, 23, 27, 27, so the N50 is 7 and the N90
+        // is 4.
+
+        assert_eq!((s.n50, s.n90), (10, 4));
+        assert_eq!((s.n50_excluding_n, s.n90_excluding_n), (7, 4));
+
+        // GC: r1 has 5 of 10, r2 4 of 4, r3 3 of 7, r5 2 of 6, for 14 of 27.
+
+        assert_eq!(s.gc_fraction, 14.0 / 27.0);
+        assert_eq!(s.n_fraction, 8.0 / 35.0);
+        assert_eq!(
+            s.records[0],
+            RecordStats {
+                name: "r1 one".to_string(),
+                length: 10,
+                gc_fraction: 0.5,
+                n_fraction: 0.0,
+            }
+        );
+        assert_eq!(s.records[2].gc_fraction, 3.0 / 7.0);
+        assert!(s.records[3].gc_fraction.is_nan());
+        assert_eq!(s.records[3].n_fraction, 1.0);
+        assert_eq!(s.records[4].n_fraction, 0.5);
+        let table = s.to_string();
+        assert!(table.contains("│N50 excluding Ns  │       7│"));
+        assert!(table.contains("│GC fraction       │  0.5185│"));
+    }
+}
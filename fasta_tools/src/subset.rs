@@ -0,0 +1,186 @@
+// Copyright (c) 2018 10x Genomics, Inc. All rights reserved.
+
+// Extracting records from fasta files by name.
+
+use crate::{next_fasta_line, open_fasta, write_fasta_record, FastaError, FastaErrorKind};
+use std::collections::HashSet;
+use std::io::Write;
+use std::path::Path;
+
+// What was extracted: the names found, in the order of the input, and the requested names
+// that were not found, in the order requested.
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SubsetReport {
+    pub found: Vec<String>,
+    pub missing: Vec<String>,
+}
+
+// Write the records of a fasta file or gzipped fasta file whose id (the part of the header
+// before the first space) is in names, keeping the original line wrapping.  Only the first
+// record having a given id is written, and reading stops once all the names have been found.
+// Line endings are written as \n.
+
+pub fn extract_fasta_records(
+    f: impl AsRef<Path>,
+    names: &[String],
+    out: impl Write,
+) -> Result<SubsetReport, FastaError> {
+    extract_fasta_records_with(f, names, out, None)
+}
+
+// As extract_fasta_records, but if wrap is given, rewrap the bases at that width, with zero
+// meaning no wrapping.
+
+pub fn extract_fasta_records_with(
+    f: impl AsRef<Path>,
+    names: &[String],
+    mut out: impl Write,
+    wrap: Option<usize>,
+) -> Result<SubsetReport, FastaError> {
+    let f = f.as_ref();
+    let (mut fin, gz) = open_fasta(f)?;
+    let mut wanted = names.iter().map(|n| n.as_str()).collect::<HashSet<&str>>();
+    let mut found = Vec::<String>::new();
+    let (mut started, mut using) = (false, false);
+    let mut header = Vec::<u8>::new();
+    let mut bases = Vec::<u8>::new();
+    let mut buf = Vec::<u8>::new();
+    let mut line = 0;
+    let err = |line, e| FastaError {
+        path: f.to_path_buf(),
+        line,
+        kind: FastaErrorKind::IoError(e),
+    };
+    while next_fasta_line(&mut fin, &mut buf, f, gz, &mut line)? {
+        if let Some(h) = buf.strip_prefix(b">") {
+            if using {
+                write_rewrapped(&mut out, &header, &bases, wrap).map_err(|e| err(line, e))?;
+            }
+            started = true;
+            if wanted.is_empty() {
+                using = false;
+                break;
+            }
+            let id = String::from_utf8_lossy(h.split(|&c| c == b' ').next().unwrap());
+            using = wanted.remove(id.as_ref());
+            if using {
+                found.push(id.to_string());
+                header = h.to_vec();
+                bases.clear();
+                if wrap.is_none() {
+                    out.write_all(&buf).map_err(|e| err(line, e))?;
+                    out.write_all(b"\n").map_err(|e| err(line, e))?;
+                }
+            }
+        } else if !started {
+            return Err(FastaError {
+                path: f.to_path_buf(),
+                line,
+                kind: FastaErrorKind::MissingHeader,
+            });
+        } else if using {
+            if wrap.is_some() {
+                bases.extend_from_slice(&buf);
+            } else {
+                out.write_all(&buf).map_err(|e| err(line, e))?;
+                out.write_all(b"\n").map_err(|e| err(line, e))?;
+            }
+        }
+    }
+    if using {
+        write_rewrapped(&mut out, &header, &bases, wrap).map_err(|e| err(line, e))?;
+    }
+    let missing = names
+        .iter()
+        .filter(|n| wanted.contains(n.as_str()))
+        .cloned()
+        .collect::<Vec<_>>();
+    Ok(SubsetReport { found, missing })
+}
+
+// Write a record whose lines have been saved, if it is to be rewrapped.  A record with no
+// bases is passed through as its header line, as it is when not rewrapping.
+
+fn write_rewrapped<W: Write>(
+    out: &mut W,
+    header: &[u8],
+    bases: &[u8],
+    wrap: Option<usize>,
+) -> std::io::Result<()> {
+    match wrap {
+        Some(_) if bases.is_empty() => {
+            out.write_all(b">")?;
+            out.write_all(header)?;
+            out.write_all(b"\n")
+        }
+        Some(wrap) => write_fasta_record(out, &String::from_utf8_lossy(header), bases, wrap),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_fasta_records() {
+        let fa = b">chr1 one\nACGTA\nCG\n>chr14 fourteen\nAAAAA\nCCCCC\nGG\n>chr2\nTTT\n\
+            >chr14_alt\nGGGGG\nA\n>chr14 again\nCCC\n";
+        let dir = std::env::temp_dir();
+        let f = dir.join(format!("fasta_tools_{}_subset.fa", std::process::id()));
+        std::fs::write(&f, &fa[..]).unwrap();
+        let names = ["chr14_alt", "chr14", "chrX"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>();
+
+        // Keep the wrapping.
+
+        let mut out = Vec::<u8>::new();
+        let r = extract_fasta_records(&f, &names, &mut out).unwrap();
+        assert_eq!(
+            r,
+            SubsetReport {
+                found: vec!["chr14".to_string(), "chr14_alt".to_string()],
+                missing: vec!["chrX".to_string()],
+            }
+        );
+        assert_eq!(
+            std::str::from_utf8(&out).unwrap(),
+            ">chr14 fourteen\nAAAAA\nCCCCC\nGG\n>chr14_alt\nGGGGG\nA\n"
+        );
+
+        // Rewrap, and stop once everything is found.
+
+        let mut out = Vec::<u8>::new();
+        let r = extract_fasta_records_with(&f, &names[0..2], &mut out, Some(4)).unwrap();
+        assert!(r.missing.is_empty());
+        assert_eq!(
+            std::str::from_utf8(&out).unwrap(),
+            ">chr14 fourteen\nAAAA\nACCC\nCCGG\n>chr14_alt\nGGGG\nGA\n"
+        );
+        let mut out = Vec::<u8>::new();
+        extract_fasta_records_with(&f, &names[0..1], &mut out, Some(0)).unwrap();
+        assert_eq!(out, b">chr14_alt\nGGGGGA\n");
+
+        // Nothing requested.
+
+        let mut out = Vec::<u8>::new();
+        assert!(extract_fasta_records(&f, &[], &mut out)
+            .unwrap()
+            .found
+            .is_empty());
+        assert!(out.is_empty());
+
+        // A record with no bases is passed through, whether or not it is rewrapped.
+
+        std::fs::write(&f, b">a\nAC\n>b\n>c\nGT\n").unwrap();
+        let names = vec!["b".to_string()];
+        for wrap in [None, Some(0), Some(3)] {
+            let mut out = Vec::<u8>::new();
+            extract_fasta_records_with(&f, &names, &mut out, wrap).unwrap();
+            assert_eq!(out, b">b\n");
+        }
+    }
+}
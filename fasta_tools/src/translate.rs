@@ -0,0 +1,232 @@
+// Copyright (c) 2018 10x Genomics, Inc. All rights reserved.
+
+// Translation of DNA to protein, using the standard genetic code.
+
+use crate::reverse_complement;
+use amino::codon_to_aa;
+use debruijn::dna_string::DnaString;
+
+// Translate a codon, ignoring case, giving * for a stop codon and X for a codon containing
+// anything other than A, C, G or T.
+
+pub fn translate_codon(codon: &[u8]) -> u8 {
+    let c = codon.to_ascii_uppercase();
+    if c.len() != 3 || c.iter().any(|b| !b"ACGT".contains(b)) {
+        return b'X';
+    }
+    codon_to_aa(&c)
+}
+
+// Translate a sequence starting at the given frame (0, 1 or 2), ignoring an incomplete last
+// codon.
+
+pub fn translate(seq: &[u8], frame: usize) -> Vec<u8> {
+    assert!(frame < 3, "frame must be 0, 1 or 2, not {}", frame);
+    if seq.len() < frame {
+        return Vec::new();
+    }
+    seq[frame..].chunks_exact(3).map(translate_codon).collect()
+}
+
+pub fn translate_dna_string(x: &DnaString, frame: usize) -> Vec<u8> {
+    translate(&x.to_ascii_vec(), frame)
+}
+
+// Translate all six frames: frames 0, 1 and 2 of the sequence, then frames 0, 1 and 2 of its
+// reverse complement.
+
+pub fn translate_six_frames(seq: &[u8]) -> Vec<Vec<u8>> {
+    let rc = reverse_complement(seq);
+    let mut frames = Vec::with_capacity(6);
+    for x in [seq, &rc[..]] {
+        for frame in 0..3 {
+            frames.push(translate(x, frame));
+        }
+    }
+    frames
+}
+
+// ▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Strand {
+    Plus,
+    Minus,
+}
+
+// An open reading frame, from an ATG through a stop codon.  The zero-based half-open interval
+// [start, stop) is on the given sequence for both strands, and includes the stop codon.  The
+// frame is that of the ATG on its strand, so for the minus strand is measured on the reverse
+// complement.  The length is the number of codons, excluding the stop codon.
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Orf {
+    pub start: usize,
+    pub stop: usize,
+    pub frame: usize,
+    pub strand: Strand,
+}
+
+impl Orf {
+    pub fn codons(&self) -> usize {
+        (self.stop - self.start) / 3 - 1
+    }
+}
+
+// Find the open reading frames having at least min_len codons, in all six frames.  Each starts
+// at the first ATG after the previous stop codon in its frame, so nested ORFs are not reported.
+// A codon containing anything other than A, C, G or T (see translate_codon) ends an ORF without
+// a stop, and so it is not reported.  ORFs are sorted by strand, then frame, then start on
+// their strand.
+
+pub fn find_orfs(seq: &[u8], min_len: usize) -> Vec<Orf> {
+    let n = seq.len();
+    let rc = reverse_complement(seq);
+    let mut orfs = Vec::<Orf>::new();
+    for (strand, x) in [(Strand::Plus, seq), (Strand::Minus, &rc[..])] {
+        for frame in 0..3 {
+            let mut start: Option<usize> = None;
+            for (i, &a) in translate(x, frame).iter().enumerate() {
+                match a {
+                    b'M' if start.is_none() => start = Some(i),
+                    b'X' => start = None,
+                    b'*' => {
+                        if let Some(s) = start.take() {
+                            let (p, q) = (frame + 3 * s, frame + 3 * i + 3);
+                            if i - s >= min_len {
+                                let (start, stop) = match strand {
+                                    Strand::Plus => (p, q),
+                                    Strand::Minus => (n - q, n - p),
+                                };
+                                orfs.push(Orf {
+                                    start,
+                                    stop,
+                                    frame,
+                                    strand,
+                                });
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+    orfs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_translate() {
+        // All 64 codons, with the first base varying slowest, in the order T, C, A, G.
+
+        let bases = b"TCAG";
+        let mut all = Vec::<u8>::new();
+        for &x in bases.iter() {
+            for &y in bases.iter() {
+                for &z in bases.iter() {
+                    all.extend([x, y, z]);
+                }
+            }
+        }
+        assert_eq!(
+            translate(&all, 0),
+            b"FFLLSSSSYY**CC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG"
+        );
+        for stop in [b"TAA", b"TAG", b"TGA", b"taa", b"tag", b"tga"] {
+            assert_eq!(translate_codon(stop), b'*');
+        }
+
+        // Ambiguous codons, lowercase, frames, and incomplete codons.
+
+        assert_eq!(translate(b"ATGNNNGGRaaaTT", 0), b"MXXK");
+        assert_eq!(translate(b"CATGGGT", 1), b"MG");
+        assert_eq!(translate(b"CCATGGGT", 2), b"MG");
+        assert!(translate(b"A", 2).is_empty());
+
+        // Human IGHV1-8 and IGKV2-28, leader plus V segment, as added in build_vdj_ref.
+
+        let ighv1_8 = b"ATGGACTGGACCTGGAGGATCCTCTTCTTGGTGGCAGCAGCTACAAGTGCCCACTCCCAGGTGCAGCTGGTGC\
+            AGTCTGGGGCTGAGGTGAAGAAGCCTGGGGCCTCAGTGAAGGTCTCCTGCAAGGCTTCTGGATACACCTTCACCAGTTATGAT\
+            ATCAACTGGGTGCGACAGGCCACTGGACAAGGGCTTGAGTGGATGGGATGGATGAACCCTAACAGTGGTAACACAGGCTATGC\
+            ACAGAAGTTCCAGGGCAGAGTCACCATGACCAGGAACACCTCCATAAGCACAGCCTACATGGAGCTGAGCAGCCTGAGATCTG\
+            AGGACACGGCCGTGTATTACTGTGCGAGAGG";
+        assert_eq!(
+            translate(ighv1_8, 0),
+            &b"MDWTWRILFLVAAATSAHSQVQLVQSGAEVKKPGASVKVSCKASGYTFTSYDINWVRQATGQGLEWMGWMNPNSGNTGYAQK\
+               FQGRVTMTRNTSISTAYMELSSLRSEDTAVYYCAR"[..]
+        );
+        let igkv2_28 = b"ATGAGGCTCCCTGCTCAGCTCCTGGGGCTGCTAATGCTCTGGGTCTCTGGATCCAGTGGGGATATTGTGATG\
+            ACTCAGTCTCCACTCTCCCTGCCCGTCACCCCTGGAGAGCCGGCCTCCATCTCCTGCAGGTCTAGTCAGAGCCTCCTGCATAG\
+            TAATGGATACAACTATTTGGATTGGTACCTGCAGAAGCCAGGGCAGTCTCCACAGCTCCTGATCTATTTGGGTTCTAATCGGG\
+            CCTCCGGGGTCCCTGACAGGTTCAGTGGCAGTGGATCAGGCACAGATTTTACACTGAAAATCAGCAGAGTGGAGGCTGAGGAT\
+            GTTGGGGTTTATTACTGCATGCAAGCTCTACAAACTCCT";
+        let aa =
+            b"MRLPAQLLGLLMLWVSGSSGDIVMTQSPLSLPVTPGEPASISCRSSQSLLHSNGYNYLDWYLQKPGQSPQLLIYLGSNRAS\
+            GVPDRFSGSGSGTDFTLKISRVEAEDVGVYYCMQALQTP";
+        assert_eq!(translate(igkv2_28, 0), &aa[..]);
+        let x = DnaString::from_acgt_bytes(igkv2_28);
+        assert_eq!(translate_dna_string(&x, 0), &aa[..]);
+
+        // Six frames.
+
+        let f = translate_six_frames(b"ATGAAATAGC");
+        assert_eq!(f[0], b"MK*");
+        assert_eq!(f[1], b"*NS");
+        assert_eq!(f[2], b"EI");
+        assert_eq!(f[3], b"AIS");
+        assert_eq!(f[4], b"LFH");
+        assert_eq!(f[5], b"YF");
+    }
+
+    #[test]
+    fn test_find_orfs() {
+        // An ORF of five codons on the plus strand, one of five codons on the minus strand, and
+        // one that would have five codons but that is interrupted by an N.
+
+        let plus = b"ATGAAACCCGGGTTTTAA";
+        let minus = reverse_complement(b"ATGGCAGCAGCAGCATGA");
+        let broken = b"ATGAAANCCGGGTTTTAG";
+        let mut x = Vec::<u8>::new();
+        for part in [&b"CC"[..], plus, b"CC", &minus, b"CC", broken, b"CC"] {
+            x.extend(part);
+        }
+        let orfs = find_orfs(&x, 5);
+        assert_eq!(
+            orfs,
+            vec![
+                Orf {
+                    start: 2,
+                    stop: 20,
+                    frame: 2,
+                    strand: Strand::Plus,
+                },
+                Orf {
+                    start: 22,
+                    stop: 40,
+                    frame: 1,
+                    strand: Strand::Minus,
+                },
+            ]
+        );
+        assert_eq!(orfs[0].codons(), 5);
+        assert_eq!(&x[orfs[0].start..orfs[0].stop], plus);
+        assert_eq!(
+            translate(&reverse_complement(&x[orfs[1].start..orfs[1].stop]), 0),
+            b"MAAAA*"
+        );
+        assert!(find_orfs(&x, 6).is_empty());
+
+        // Without the N, there is a third ORF, which starts at an earlier ATG in the same frame,
+        // inside the minus strand ORF.
+
+        let n = x.iter().position(|&c| c == b'N').unwrap();
+        x[n] = b'C';
+        let orfs = find_orfs(&x, 5);
+        assert_eq!(orfs.len(), 3);
+        assert_eq!((orfs[0].start, orfs[0].stop, orfs[0].frame), (24, 60, 0));
+    }
+}
@@ -0,0 +1,160 @@
+// Copyright (c) 2018 10x Genomics, Inc. All rights reserved.
+
+// Storage of sequences with two bits per base, A = 00, C = 01, G = 10 and T = 11, four bases
+// per byte, with the first base in the high-order bits.  The last byte is padded with zeros.
+//
+// A collection of packed sequences can be saved in a file having the following format, in
+// which all numbers are little-endian:
+// 1. the eight bytes 2BITSEQ1;
+// 2. the number of records, as a u64;
+// 3. for each record, the length of its name as a u32, the name, and the number of bases, as
+//    a u64;
+// 4. the packed bases of each record, in order.
+
+use std::io::{self, Read, Write};
+
+const MAGIC: &[u8; 8] = b"2BITSEQ1";
+
+// Pack a sequence, ignoring case.  Return the first byte that is not a base if there is one.
+
+pub fn pack_2bit(seq: &[u8]) -> Result<Vec<u8>, u8> {
+    let mut packed = vec![0u8; seq.len().div_ceil(4)];
+    for (i, &b) in seq.iter().enumerate() {
+        let x = match b {
+            b'A' | b'a' => 0,
+            b'C' | b'c' => 1,
+            b'G' | b'g' => 2,
+            b'T' | b't' => 3,
+            _ => return Err(b),
+        };
+        packed[i / 4] |= x << (6 - 2 * (i % 4));
+    }
+    Ok(packed)
+}
+
+// Unpack the first len bases of a packed sequence, as uppercase.
+
+pub fn unpack_2bit(packed: &[u8], len: usize) -> Vec<u8> {
+    assert!(packed.len() * 4 >= len, "packed sequence is too short");
+    (0..len)
+        .map(|i| b"ACGT"[((packed[i / 4] >> (6 - 2 * (i % 4))) & 3) as usize])
+        .collect()
+}
+
+// ▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓
+
+// A collection of named packed sequences.
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PackedSeqs {
+    pub names: Vec<String>,
+    pub lens: Vec<usize>,
+    pub packed: Vec<Vec<u8>>,
+}
+
+fn invalid(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+impl PackedSeqs {
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    // Add a sequence, failing as for pack_2bit.
+
+    pub fn push(&mut self, name: &str, seq: &[u8]) -> Result<(), u8> {
+        self.packed.push(pack_2bit(seq)?);
+        self.names.push(name.to_string());
+        self.lens.push(seq.len());
+        Ok(())
+    }
+
+    // Unpack the sequence of a record.
+
+    pub fn get(&self, i: usize) -> Vec<u8> {
+        unpack_2bit(&self.packed[i], self.lens[i])
+    }
+
+    pub fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        w.write_all(MAGIC)?;
+        w.write_all(&(self.len() as u64).to_le_bytes())?;
+        for (name, &len) in self.names.iter().zip(self.lens.iter()) {
+            w.write_all(&(name.len() as u32).to_le_bytes())?;
+            w.write_all(name.as_bytes())?;
+            w.write_all(&(len as u64).to_le_bytes())?;
+        }
+        for p in self.packed.iter() {
+            w.write_all(p)?;
+        }
+        Ok(())
+    }
+
+    pub fn read<R: Read>(r: &mut R) -> io::Result<PackedSeqs> {
+        let mut magic = [0u8; 8];
+        r.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid("not a packed sequence file".to_string()));
+        }
+        let mut b4 = [0u8; 4];
+        let mut b8 = [0u8; 8];
+        r.read_exact(&mut b8)?;
+        let n = u64::from_le_bytes(b8) as usize;
+        let mut x = PackedSeqs::default();
+        for _ in 0..n {
+            r.read_exact(&mut b4)?;
+            let mut name = vec![0u8; u32::from_le_bytes(b4) as usize];
+            r.read_exact(&mut name)?;
+            let name = String::from_utf8(name)
+                .map_err(|_| invalid("record name is not valid UTF-8".to_string()))?;
+            r.read_exact(&mut b8)?;
+            x.names.push(name);
+            x.lens.push(u64::from_le_bytes(b8) as usize);
+        }
+        for &len in x.lens.iter() {
+            let mut p = vec![0u8; len.div_ceil(4)];
+            r.read_exact(&mut p)?;
+            x.packed.push(p);
+        }
+        Ok(x)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_2bit() {
+        assert_eq!(
+            pack_2bit(b"ACGTtgca").unwrap(),
+            vec![0b00011011, 0b11100100]
+        );
+        assert_eq!(pack_2bit(b"G").unwrap(), vec![0b10000000]);
+        assert_eq!(pack_2bit(b"ACNGT"), Err(b'N'));
+        let mut x = PackedSeqs::default();
+        for len in 0..=9 {
+            let seq = (0..len)
+                .map(|i| b"GATTACA"[(i * 3 + len) % 7])
+                .collect::<Vec<u8>>();
+            let p = pack_2bit(&seq).unwrap();
+            assert_eq!(p.len(), len.div_ceil(4));
+            assert_eq!(unpack_2bit(&p, len), seq);
+            x.push(&format!("seq{}", len), &seq).unwrap();
+        }
+        assert_eq!(x.push("bad", b"AC-"), Err(b'-'));
+        assert_eq!(x.len(), 10);
+        let mut f = Vec::<u8>::new();
+        x.write(&mut f).unwrap();
+        let y = PackedSeqs::read(&mut &f[..]).unwrap();
+        assert_eq!(x, y);
+        assert_eq!(y.names[9], "seq9");
+        assert_eq!(y.get(9), unpack_2bit(&x.packed[9], 9));
+        assert!(PackedSeqs::read(&mut &f[..f.len() - 1]).is_err());
+        assert!(PackedSeqs::read(&mut &b"2BITSEQ2"[..]).is_err());
+    }
+}
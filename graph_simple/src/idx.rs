@@ -0,0 +1,2786 @@
+// Copyright (c) 2018 10X Genomics, Inc. All rights reserved.
+// Some code here is based on the BroadCRD codebase.
+
+// Define generic digraph functions, using usize for all vertex and edge ids.
+//
+// The functions v_from and related functions below are linear in the vertex degree for
+// petgraph, so looping over them is quadratic.  Internally we instead use the iterators
+// edges_from and edges_to.
+
+use petgraph::{prelude::*, EdgeType};
+use std::collections::{HashMap, HashSet};
+use tables::print_tabular_vbox;
+use vector_utils::bin_member;
+
+pub trait GraphSimpleIdx<T> {
+    // =============================================================================
+    // Return the object associated to an edge id.
+    // =============================================================================
+
+    fn edge_obj(&self, e: usize) -> &T;
+
+    // =============================================================================
+    // Return the source or target of an edge.
+    // =============================================================================
+
+    fn to_left(&self, e: usize) -> usize;
+    fn to_right(&self, e: usize) -> usize;
+
+    // =============================================================================
+    // Return the number of edges exiting or entering a given vertex.
+    // =============================================================================
+
+    fn n_from(&self, v: usize) -> usize;
+    fn n_to(&self, v: usize) -> usize;
+
+    // =============================================================================
+    // Return id of the nth vertex exiting or entering a given vertex id.
+    // Note that this is O(n).
+    // =============================================================================
+
+    fn v_from(&self, v: usize, n: usize) -> usize;
+    fn v_to(&self, v: usize, n: usize) -> usize;
+
+    // =============================================================================
+    // Return id of the nth edge exiting or entering a given vertex id.
+    // Note that this is O(n).
+    // =============================================================================
+
+    fn e_from(&self, v: usize, n: usize) -> usize;
+    fn e_to(&self, v: usize, n: usize) -> usize;
+
+    // =============================================================================
+    // Return the nth edge exiting or entering a given vertex id.
+    // Note that this is O(n).
+    // =============================================================================
+
+    fn o_from(&self, v: usize, n: usize) -> &T;
+    fn o_to(&self, v: usize, n: usize) -> &T;
+
+    // =============================================================================
+    // edges_from: iterate over the edges exiting a vertex, yielding each as a pair
+    // (edge id, target vertex id).  edges_to: iterate over the edges entering a vertex,
+    // yielding pairs (edge id, source vertex id).  These take constant time per edge,
+    // and should be used in place of looping over v_from, e_from, v_to or e_to.
+    // =============================================================================
+
+    fn edges_from(&self, v: usize) -> impl Iterator<Item = (usize, usize)> + '_;
+    fn edges_to(&self, v: usize) -> impl Iterator<Item = (usize, usize)> + '_;
+
+    // =============================================================================
+    // source: return if a vertex is a source
+    // sink: return if a vertex is a sink
+    // =============================================================================
+
+    fn source(&self, v: usize) -> bool;
+    fn sink(&self, v: usize) -> bool;
+
+    // =============================================================================
+    // sources: return the ordered list of source vertices
+    // sinks: return the ordered list of sink vertices
+    // =============================================================================
+
+    fn sources(&self) -> Vec<usize>;
+    fn sinks(&self) -> Vec<usize>;
+
+    // =============================================================================
+    // cyclic_core: return the ordered list of vertices that define a subgraph having no
+    // sources and sinks, and which is empty iff the graph is acyclic.
+    // The (vertex) cyclic core is the union of all vertices that appear in cycles.
+    // =============================================================================
+
+    fn cyclic_core(&self) -> Vec<usize>;
+
+    // =============================================================================
+    // cyclic_core_edges: return the ordered list of edges that define a subgraph having no
+    // sources and sinks, and which is empty iff the graph is acyclic.
+    // The (edge) cyclic core is the union of all edges that appear in cycles, which are
+    // exactly the edges whose source and target lie in the same strongly connected
+    // component.
+    // =============================================================================
+
+    fn cyclic_core_edges(&self) -> Vec<usize>;
+
+    // =============================================================================
+    // cyclic_core_edges_loose: return the ordered list of edges whose source and target
+    // both lie in the vertex cyclic core.  This contains the edge cyclic core, but may
+    // also contain edges between strongly connected components, which lie on no cycle.
+    // =============================================================================
+
+    fn cyclic_core_edges_loose(&self) -> Vec<usize>;
+
+    // =============================================================================
+    // acyclic: return true if graph is acyclic
+    // =============================================================================
+
+    fn acyclic(&self) -> bool;
+
+    // =============================================================================
+    // get_predecessors: find all vertices which have a directed path to a vertex
+    // in v.  This includes the vertices in v by definition.  Return a sorted list
+    // x.  get_successors: go the other way.
+    // get_predecessors1 and get_successors1: start from one vertex
+    // =============================================================================
+
+    fn get_predecessors(&self, v: &[usize], x: &mut Vec<usize>);
+    fn get_predecessors1(&self, v: usize, x: &mut Vec<usize>);
+    fn get_successors(&self, v: &[usize], x: &mut Vec<usize>);
+    fn get_successors1(&self, v: usize, x: &mut Vec<usize>);
+
+    // =============================================================================
+    // get_predecessors_dense and get_successors_dense: same as get_predecessors and
+    // get_successors, but track visited vertices using a bit set sized to the graph,
+    // which is faster if the result is a large fraction of the graph.  The functions
+    // get_predecessors and get_successors switch to these automatically if the graph is
+    // small or the result becomes large.
+    // =============================================================================
+
+    fn get_predecessors_dense(&self, v: &[usize], x: &mut Vec<usize>);
+    fn get_successors_dense(&self, v: &[usize], x: &mut Vec<usize>);
+
+    // =============================================================================
+    // Determine if there is a path from one vertex to another, allowing for the
+    // case of a zero length path, where the vertices are equal.
+    // =============================================================================
+
+    fn have_path(&self, v: usize, w: usize) -> bool;
+
+    // =============================================================================
+    // Find the connected components.  Each component is a sorted list of vertices, and
+    // the components are ordered by their first vertex.
+    // =============================================================================
+
+    fn components(&self, comp: &mut Vec<Vec<usize>>);
+
+    // =============================================================================
+    // Return the index of the connected component containing each vertex, using the
+    // same numbering as components(), i.e. v lies in comp[component_ids()[v]].
+    // =============================================================================
+
+    fn component_ids(&self) -> Vec<usize>;
+
+    // =============================================================================
+    // Return the number of connected components.
+    // =============================================================================
+
+    fn n_components(&self) -> usize;
+
+    // =============================================================================
+    // Find the connected components as lists of edges.  Each component is a
+    // sorted list of edges.
+    // =============================================================================
+
+    fn components_e(&self, comp: &mut Vec<Vec<usize>>);
+
+    // =============================================================================
+    // Find the connected components as lists of edges, sorted within each component
+    // to follow the order of the graph.  The edges are sorted by the positions of their
+    // source and then target vertices in an ordering of the vertices, and then by edge id.
+    // The ordering of the vertices is topological, except within strongly connected
+    // components, where it is a depth-first search order.  In particular, if there is a
+    // path from the end of one edge to the start of another, and the edges do not lie in
+    // a common strongly connected component, the first edge comes first.
+    // =============================================================================
+
+    fn components_e_pos_sorted(&self, comp: &mut Vec<Vec<usize>>);
+
+    // =============================================================================
+    // Determine if two edge paths through the graph could like in a common
+    // superpath.
+    // =============================================================================
+
+    fn copathic(&self, p1: &[usize], p2: &[usize]) -> bool;
+
+    // =============================================================================
+    // shortest_path: find a path from one vertex to another having the fewest edges,
+    // and return it as a list of vertices, including both endpoints.  If the vertices
+    // are equal, the path consists of just that vertex.  Return None if there is no path.
+    // shortest_path_edges: same, but return the list of edges in the path.
+    // =============================================================================
+
+    fn shortest_path(&self, v: usize, w: usize) -> Option<Vec<usize>>;
+    fn shortest_path_edges(&self, v: usize, w: usize) -> Option<Vec<usize>>;
+
+    // =============================================================================
+    // all_simple_paths: find paths from one vertex to another that do not repeat a
+    // vertex, returning each as a list of vertices, including both endpoints.  Return at
+    // most max_paths paths, and only those having at most max_len vertices.  Parallel
+    // edges are not distinguished.  Paths are found in lexicographic order.
+    // =============================================================================
+
+    fn all_simple_paths(
+        &self,
+        v: usize,
+        w: usize,
+        max_paths: usize,
+        max_len: usize,
+    ) -> Vec<Vec<usize>>;
+
+    // =============================================================================
+    // strongly_connected_components: return the strongly connected components, each as
+    // a sorted list of vertices.  The components are listed in reverse topological
+    // order, so every edge between two components goes from a later one to an earlier one.
+    // =============================================================================
+
+    fn strongly_connected_components(&self) -> Vec<Vec<usize>>;
+
+    // =============================================================================
+    // reachability: build an index that answers have_path queries in constant time.
+    // This is worthwhile if many queries are to be made against a fixed graph.
+    // =============================================================================
+
+    fn reachability(&self) -> ReachabilityIndex;
+
+    // =============================================================================
+    // condensation: return the graph obtained by collapsing each strongly connected
+    // component to a vertex, which is acyclic, together with a map from vertices to
+    // components.  The vertices of the condensation are the components, in the order
+    // given by strongly_connected_components, and each carries its sorted list of
+    // vertices.  The edges of the condensation correspond to the edges of the graph
+    // between different components, and carry their edge ids.  If dedup is true, there is
+    // at most one edge between two components, carrying the sorted list of all such edge
+    // ids, and otherwise there is one edge for each, in edge id order.
+    // =============================================================================
+
+    fn condensation(&self, dedup: bool) -> (Graph<Vec<usize>, Vec<usize>, Directed>, Vec<usize>);
+
+    // =============================================================================
+    // cycles: find elementary cycles, i.e. closed paths that do not repeat a vertex,
+    // returning each as a list of vertices starting with its smallest vertex.  Return at
+    // most max_cycles cycles, and only those having at most max_len vertices.  Parallel
+    // edges are not distinguished.  This uses Johnson's algorithm, restricted to the
+    // cyclic core.
+    // =============================================================================
+
+    fn cycles(&self, max_cycles: usize, max_len: usize) -> Vec<Vec<usize>>;
+
+    // =============================================================================
+    // degree_summary: compute basic statistics about the graph, in a single pass over
+    // its edges.  Print the result using its Display implementation.
+    // =============================================================================
+
+    fn degree_summary(&self) -> GraphSummary;
+
+    // =============================================================================
+    // count_paths: count the directed paths from one vertex to another, where parallel
+    // edges give rise to distinct paths, and a vertex has one path to itself.  Return None
+    // if the graph is cyclic.  If the count exceeds u128::MAX, return u128::MAX.
+    // =============================================================================
+
+    fn count_paths(&self, v: usize, w: usize) -> Option<u128>;
+
+    // =============================================================================
+    // edges_between: return the ids of the edges from one vertex to another, in
+    // increasing order.  has_edge: determine if there is such an edge.
+    // =============================================================================
+
+    fn edges_between(&self, v: usize, w: usize) -> Vec<usize>;
+    fn has_edge(&self, v: usize, w: usize) -> bool;
+
+    // =============================================================================
+    // neighborhood: return the sorted list of vertices that can be reached from v by
+    // following at most radius edges, in the given direction.  This includes v.
+    // =============================================================================
+
+    fn neighborhood(&self, v: usize, radius: usize, direction: Dir) -> Vec<usize>;
+
+    // =============================================================================
+    // depths_from_sources: for each vertex, return the length of a shortest path to it
+    // from a source, or None if it cannot be reached from any source, as for a vertex on a
+    // cycle having no source upstream of it.
+    // depths_from_roots: the same, but for paths from the given roots.
+    // =============================================================================
+
+    fn depths_from_sources(&self) -> Vec<Option<usize>>;
+    fn depths_from_roots(&self, roots: &[usize]) -> Vec<Option<usize>>;
+
+    // =============================================================================
+    // dominators: for each vertex reachable from root, other than root, return its
+    // immediate dominator, which is the closest vertex to it that lies on every path
+    // from root to it.  For root and unreachable vertices, return None.
+    // =============================================================================
+
+    fn dominators(&self, root: usize) -> Vec<Option<usize>>;
+
+    // =============================================================================
+    // unary_chains: find the maximal paths e1, ..., ek of edges, all of whose interior
+    // vertices have exactly one edge entering and one edge exiting.  Each edge lies in
+    // exactly one chain.  Chains are returned as lists of edges, ordered by their first
+    // edge.  These are followed by the chains that are cycles, in which every vertex is
+    // interior, and each of which starts at its smallest edge.
+    // collapse_chains: return the graph obtained by replacing each chain by a single edge,
+    // carrying its list of edges, in the order of unary_chains.  The vertices carry their
+    // original ids, and are the vertices that are not interior to a chain, in increasing
+    // order, plus for each cycle, the source of its first edge.
+    // =============================================================================
+
+    fn unary_chains(&self) -> Vec<Vec<usize>>;
+    fn collapse_chains(&self) -> Graph<usize, Vec<usize>, Directed>;
+
+    // =============================================================================
+    // cut_vertices: return the sorted list of vertices whose removal would increase the
+    // number of connected components, ignoring edge directions.
+    // bridges: return the sorted list of edges whose removal would increase the number of
+    // connected components, ignoring edge directions.  Self-loops are never bridges, and
+    // neither is an edge having a parallel edge, in either direction.
+    // =============================================================================
+
+    fn cut_vertices(&self) -> Vec<usize>;
+    fn bridges(&self) -> Vec<usize>;
+
+    // =============================================================================
+    // self_loops: return the sorted list of edges whose source and target are equal.
+    // parallel_edge_groups: return the groups of two or more edges having the same
+    // source and the same target, each as a sorted list, ordered by first edge.
+    // =============================================================================
+
+    fn self_loops(&self) -> Vec<usize>;
+    fn parallel_edge_groups(&self) -> Vec<Vec<usize>>;
+
+    // =============================================================================
+    // feedback_edges: return a sorted list of edges whose removal makes the graph
+    // acyclic.  The list is small but not necessarily minimum: it is minimal, in the
+    // sense that restoring any one of its edges would create a cycle.  Every self-loop
+    // is included.
+    // =============================================================================
+
+    fn feedback_edges(&self) -> Vec<usize>;
+}
+
+// Direction in which to follow edges, for neighborhood.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Dir {
+    Outgoing,
+    Incoming,
+    Both,
+}
+
+// Statistics about a graph, as computed by degree_summary.  Sources and sinks include
+// isolated vertices.  The number of parallel edge pairs is the number of unordered pairs of
+// distinct edges having the same source and the same target, so for example three edges
+// from v to w contribute three pairs.  The degree statistics are zero for an empty graph.
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GraphSummary {
+    pub nodes: usize,
+    pub edges: usize,
+    pub sources: usize,
+    pub sinks: usize,
+    pub isolated: usize,
+    pub min_in: usize,
+    pub max_in: usize,
+    pub mean_in: f64,
+    pub min_out: usize,
+    pub max_out: usize,
+    pub mean_out: f64,
+    pub self_loops: usize,
+    pub parallel_pairs: usize,
+}
+
+impl std::fmt::Display for GraphSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let rows = vec![
+            vec!["vertices".to_string(), format!("{}", self.nodes)],
+            vec!["edges".to_string(), format!("{}", self.edges)],
+            vec!["sources".to_string(), format!("{}", self.sources)],
+            vec!["sinks".to_string(), format!("{}", self.sinks)],
+            vec![
+                "isolated vertices".to_string(),
+                format!("{}", self.isolated),
+            ],
+            vec!["self-loops".to_string(), format!("{}", self.self_loops)],
+            vec![
+                "parallel edge pairs".to_string(),
+                format!("{}", self.parallel_pairs),
+            ],
+            vec!["\\hline".to_string(); 2],
+            vec![
+                "in-degree min/max/mean".to_string(),
+                format!("{}/{}/{:.2}", self.min_in, self.max_in, self.mean_in),
+            ],
+            vec![
+                "out-degree min/max/mean".to_string(),
+                format!("{}/{}/{:.2}", self.min_out, self.max_out, self.mean_out),
+            ],
+        ];
+        let mut log = String::new();
+        print_tabular_vbox(&mut log, &rows, 2, b"l|r", false, false);
+        write!(f, "{}", log)
+    }
+}
+
+// An index for answering have_path queries, consisting of the strongly connected
+// component of each vertex, and for each component, a bit set of the components reachable
+// from it.  Memory use is quadratic in the number of components, so this is not suitable
+// for very large graphs.
+
+pub struct ReachabilityIndex {
+    comp: Vec<usize>,
+    reach: Vec<Vec<u64>>,
+}
+
+impl ReachabilityIndex {
+    pub fn new<S, T, U, V>(g: &Graph<S, T, U, V>) -> ReachabilityIndex
+    where
+        U: EdgeType,
+        V: petgraph::csr::IndexType,
+    {
+        let sccs = g.strongly_connected_components();
+        let mut comp = vec![0_usize; g.node_count()];
+        for (i, c) in sccs.iter().enumerate() {
+            for &v in c.iter() {
+                comp[v] = i;
+            }
+        }
+
+        // Because the components are in reverse topological order, the components
+        // reachable from component i are all at most i, and have been computed already.
+
+        let words = sccs.len().div_ceil(64);
+        let mut reach = Vec::<Vec<u64>>::with_capacity(sccs.len());
+        for (i, c) in sccs.iter().enumerate() {
+            let mut r = vec![0_u64; words];
+            r[i / 64] |= 1 << (i % 64);
+            for &v in c.iter() {
+                for w in g.neighbors_directed(NodeIndex::<V>::new(v), Outgoing) {
+                    let j = comp[w.index()];
+                    if j != i {
+                        for k in 0..=j / 64 {
+                            r[k] |= reach[j][k];
+                        }
+                    }
+                }
+            }
+            reach.push(r);
+        }
+        ReachabilityIndex { comp, reach }
+    }
+
+    // Determine if there is a path from v to w, with the same convention as have_path.
+
+    pub fn query(&self, v: usize, w: usize) -> bool {
+        let (i, j) = (self.comp[v], self.comp[w]);
+        self.reach[i][j / 64] & (1 << (j % 64)) != 0
+    }
+}
+
+// Build a graph from a list of edges (v, w, t), where v and w are vertex ids and t is the
+// edge object, so that edge i of the graph is edges[i].  The number of vertices is one more
+// than the largest vertex id appearing in the edges, unless num_vertices is specified, which
+// allows for isolated vertices at the end.
+
+pub fn build_graph_from_edges<T: Clone>(
+    edges: &[(usize, usize, T)],
+    num_vertices: Option<usize>,
+) -> Graph<(), T, Directed, u32> {
+    let mut n = 0;
+    for (v, w, _) in edges.iter() {
+        n = n.max(v + 1).max(w + 1);
+    }
+    if let Some(m) = num_vertices {
+        assert!(
+            m >= n,
+            "build_graph_from_edges: num_vertices = {} but the edges require {}",
+            m,
+            n
+        );
+        n = m;
+    }
+    let mut g = Graph::<(), T, Directed, u32>::with_capacity(n, edges.len());
+    for _ in 0..n {
+        g.add_node(());
+    }
+    for (v, w, t) in edges.iter() {
+        g.add_edge(NodeIndex::new(*v), NodeIndex::new(*w), t.clone());
+    }
+    g
+}
+
+// Return the subgraph induced by a list of distinct vertices, together with maps from its
+// vertex and edge ids back to those of the original graph.  Vertex i of the subgraph is
+// verts[i], and edges are kept in their original order, including parallel edges and
+// self-loops.  This is not a trait method because it requires the vertex type S.
+
+pub fn subgraph<S, T, U, V>(
+    g: &Graph<S, T, U, V>,
+    verts: &[usize],
+) -> (Graph<S, T, U, V>, Vec<usize>, Vec<usize>)
+where
+    S: Clone,
+    T: Clone,
+    U: EdgeType,
+    V: petgraph::csr::IndexType,
+{
+    let mut h = Graph::<S, T, U, V>::with_capacity(verts.len(), 0);
+    let mut to_new = vec![None; g.node_count()];
+    for &v in verts.iter() {
+        assert!(to_new[v].is_none(), "subgraph: vertex {} is repeated", v);
+        to_new[v] = Some(h.add_node(g[NodeIndex::<V>::new(v)].clone()));
+    }
+    let mut edges = Vec::<usize>::new();
+    for e in g.edge_references() {
+        if let (Some(v), Some(w)) = (to_new[e.source().index()], to_new[e.target().index()]) {
+            h.add_edge(v, w, e.weight().clone());
+            edges.push(e.id().index());
+        }
+    }
+    (h, verts.to_vec(), edges)
+}
+
+// Find the connected components using a union-find structure, in a single pass over the
+// edges.  Return the component id of each vertex, and the number of components, with the ids
+// assigned in increasing order of the first vertex in the component.
+
+fn union_find_components<S, T, U, V>(g: &Graph<S, T, U, V>) -> (Vec<usize>, usize)
+where
+    U: EdgeType,
+    V: petgraph::csr::IndexType,
+{
+    let n = g.node_count();
+    let mut parent = (0..n).collect::<Vec<usize>>();
+    let mut rank = vec![0_u8; n];
+    fn find(parent: &mut [usize], mut x: usize) -> usize {
+        while parent[x] != x {
+            parent[x] = parent[parent[x]];
+            x = parent[x];
+        }
+        x
+    }
+    for e in g.edge_references() {
+        let (a, b) = (
+            find(&mut parent, e.source().index()),
+            find(&mut parent, e.target().index()),
+        );
+        if a != b {
+            if rank[a] < rank[b] {
+                parent[a] = b;
+            } else {
+                parent[b] = a;
+                if rank[a] == rank[b] {
+                    rank[a] += 1;
+                }
+            }
+        }
+    }
+    let mut id_of_root = vec![usize::MAX; n];
+    let mut ids = vec![0; n];
+    let mut count = 0;
+    for (v, id) in ids.iter_mut().enumerate() {
+        let r = find(&mut parent, v);
+        if id_of_root[r] == usize::MAX {
+            id_of_root[r] = count;
+            count += 1;
+        }
+        *id = id_of_root[r];
+    }
+    (ids, count)
+}
+
+// Find the cut vertices and bridges of a graph, ignoring edge directions, using Tarjan's
+// algorithm, implemented by a depth-first search with an explicit stack.  Each vertex is
+// assigned its discovery time, and the smallest discovery time that can be reached from its
+// subtree using one edge not in the tree.  The edge by which a vertex was reached is
+// skipped by edge id rather than by vertex, so that parallel edges are handled correctly.
+
+fn cut_vertices_and_bridges<S, T, U, V>(g: &Graph<S, T, U, V>) -> (Vec<usize>, Vec<usize>)
+where
+    U: EdgeType,
+    V: petgraph::csr::IndexType,
+{
+    const UNDEF: usize = usize::MAX;
+    let n = g.node_count();
+    let mut adj = vec![Vec::<(usize, usize)>::new(); n];
+    for e in g.edge_references() {
+        let (v, w) = (e.source().index(), e.target().index());
+        if v != w {
+            adj[v].push((e.id().index(), w));
+            adj[w].push((e.id().index(), v));
+        }
+    }
+    let (mut disc, mut low) = (vec![UNDEF; n], vec![UNDEF; n]);
+    let (mut cuts, mut bridges) = (Vec::<usize>::new(), Vec::<usize>::new());
+    let mut time = 0;
+    for r in 0..n {
+        if disc[r] != UNDEF {
+            continue;
+        }
+        disc[r] = time;
+        low[r] = time;
+        time += 1;
+        let mut root_children = 0;
+        let mut stack = vec![(r, UNDEF, 0)];
+        while let Some(top) = stack.last_mut() {
+            let (v, pe) = (top.0, top.1);
+            if top.2 < adj[v].len() {
+                let (e, w) = adj[v][top.2];
+                top.2 += 1;
+                if e == pe {
+                    continue;
+                }
+                if disc[w] == UNDEF {
+                    disc[w] = time;
+                    low[w] = time;
+                    time += 1;
+                    if v == r {
+                        root_children += 1;
+                    }
+                    stack.push((w, e, 0));
+                } else {
+                    low[v] = low[v].min(disc[w]);
+                }
+                continue;
+            }
+            stack.pop();
+            if let Some(parent) = stack.last() {
+                let u = parent.0;
+                low[u] = low[u].min(low[v]);
+                if low[v] > disc[u] {
+                    bridges.push(pe);
+                }
+                if u != r && low[v] >= disc[u] {
+                    cuts.push(u);
+                }
+            }
+        }
+        if root_children >= 2 {
+            cuts.push(r);
+        }
+    }
+    cuts.sort_unstable();
+    cuts.dedup();
+    bridges.sort_unstable();
+    (cuts, bridges)
+}
+
+// Thresholds for get_predecessors and get_successors: use a bit set if the graph has at
+// most DENSE_MAX_NODES vertices, or once more than 1/DENSE_FRACTION of the graph is found.
+
+const DENSE_MAX_NODES: usize = 4096;
+const DENSE_FRACTION: usize = 16;
+
+// Complete the computation of the vertices reachable in the given direction, using a bit
+// set, given the vertices found so far and a stack of found vertices whose neighbors may not
+// have been examined yet.  Put the sorted result in x.
+
+fn dense_closure<S, T, U, V>(
+    g: &Graph<S, T, U, V>,
+    found: &[usize],
+    mut check: Vec<usize>,
+    dir: Direction,
+    x: &mut Vec<usize>,
+) where
+    U: EdgeType,
+    V: petgraph::csr::IndexType,
+{
+    let mut seen = vec![0_u64; g.node_count().div_ceil(64)];
+    for &v in found.iter() {
+        seen[v / 64] |= 1 << (v % 64);
+    }
+    while let Some(u) = check.pop() {
+        for y in g.neighbors_directed(NodeIndex::<V>::new(u), dir) {
+            let y = y.index();
+            if seen[y / 64] & (1 << (y % 64)) == 0 {
+                seen[y / 64] |= 1 << (y % 64);
+                check.push(y);
+            }
+        }
+    }
+    x.clear();
+    for (i, &w) in seen.iter().enumerate() {
+        let mut w = w;
+        while w != 0 {
+            x.push(64 * i + w.trailing_zeros() as usize);
+            w &= w - 1;
+        }
+    }
+}
+
+impl<S, T, U, V> GraphSimpleIdx<T> for Graph<S, T, U, V>
+where
+    U: EdgeType,
+    V: petgraph::csr::IndexType,
+{
+    fn edge_obj(&self, e: usize) -> &T {
+        &self[EdgeIndex::<V>::new(e)]
+    }
+
+    fn to_left(&self, e: usize) -> usize {
+        self.edge_endpoints(EdgeIndex::<V>::new(e))
+            .unwrap()
+            .0
+            .index()
+    }
+
+    fn to_right(&self, e: usize) -> usize {
+        self.edge_endpoints(EdgeIndex::<V>::new(e))
+            .unwrap()
+            .1
+            .index()
+    }
+
+    fn n_from(&self, v: usize) -> usize {
+        self.neighbors(NodeIndex::<V>::new(v)).count()
+    }
+
+    fn n_to(&self, v: usize) -> usize {
+        self.neighbors_directed(NodeIndex::<V>::new(v), Incoming)
+            .count()
+    }
+
+    fn v_from(&self, v: usize, n: usize) -> usize {
+        self.edges_directed(NodeIndex::<V>::new(v), Outgoing)
+            .nth(n)
+            .unwrap()
+            .target()
+            .index()
+    }
+
+    fn v_to(&self, v: usize, n: usize) -> usize {
+        self.edges_directed(NodeIndex::<V>::new(v), Incoming)
+            .nth(n)
+            .unwrap()
+            .source()
+            .index()
+    }
+
+    fn e_from(&self, v: usize, n: usize) -> usize {
+        let mut e: EdgeIndex<V> = self.first_edge(NodeIndex::<V>::new(v), Outgoing).unwrap();
+        for _j in 0..n {
+            let f = self.next_edge(e, Outgoing).unwrap();
+            e = f;
+        }
+        e.index()
+    }
+
+    fn e_to(&self, v: usize, n: usize) -> usize {
+        let mut e: EdgeIndex<V> = self.first_edge(NodeIndex::<V>::new(v), Incoming).unwrap();
+        for _j in 0..n {
+            let f = self.next_edge(e, Incoming).unwrap();
+            e = f;
+        }
+        e.index()
+    }
+
+    fn o_from(&self, v: usize, n: usize) -> &T {
+        self.edge_obj(self.e_from(v, n))
+    }
+
+    fn o_to(&self, v: usize, n: usize) -> &T {
+        self.edge_obj(self.e_to(v, n))
+    }
+
+    fn edges_from(&self, v: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.edges_directed(NodeIndex::<V>::new(v), Outgoing)
+            .map(|e| (e.id().index(), e.target().index()))
+    }
+
+    fn edges_to(&self, v: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.edges_directed(NodeIndex::<V>::new(v), Incoming)
+            .map(|e| (e.id().index(), e.source().index()))
+    }
+
+    fn source(&self, v: usize) -> bool {
+        self.n_to(v) == 0
+    }
+
+    fn sink(&self, v: usize) -> bool {
+        self.n_from(v) == 0
+    }
+
+    fn sources(&self) -> Vec<usize> {
+        let mut s = Vec::<usize>::new();
+        for v in 0..self.node_count() {
+            if self.source(v) {
+                s.push(v);
+            }
+        }
+        s
+    }
+
+    fn sinks(&self) -> Vec<usize> {
+        let mut s = Vec::<usize>::new();
+        for v in 0..self.node_count() {
+            if self.sink(v) {
+                s.push(v);
+            }
+        }
+        s
+    }
+
+    // cyclic_core successively deletes vertices and edges from the graph, without actually
+    // deleting them, but tracking instead the number of edges entering and exiting each vertex.
+    // Parallel edges and self-loops are counted once per edge, both here and when edges are
+    // deleted.
+
+    fn cyclic_core(&self) -> Vec<usize> {
+        let n = self.node_count();
+        let (mut ins, mut outs) = (vec![0; n], vec![0; n]);
+        for e in self.edge_references() {
+            outs[e.source().index()] += 1;
+            ins[e.target().index()] += 1;
+        }
+        let mut sources = (0..n).filter(|&v| ins[v] == 0).collect::<Vec<_>>();
+        let mut sinks = (0..n).filter(|&v| outs[v] == 0).collect::<Vec<_>>();
+        let mut i = 0;
+        while i < sources.len() {
+            let v = sources[i];
+            outs[v] = 0;
+            for (_, w) in self.edges_from(v) {
+                ins[w] -= 1;
+                if ins[w] == 0 {
+                    sources.push(w);
+                }
+            }
+            i += 1;
+        }
+        let mut i = 0;
+        while i < sinks.len() {
+            let v = sinks[i];
+            if ins[v] == 0 {
+                i += 1;
+                continue;
+            }
+            for (_, w) in self.edges_to(v) {
+                if ins[w] == 0 {
+                    continue;
+                }
+                outs[w] -= 1;
+                if outs[w] == 0 {
+                    sinks.push(w);
+                }
+            }
+            i += 1;
+        }
+        let mut core = Vec::<usize>::new();
+        for v in 0..n {
+            if ins[v] > 0 && outs[v] > 0 {
+                core.push(v);
+            }
+        }
+        core
+    }
+
+    fn cyclic_core_edges(&self) -> Vec<usize> {
+        let mut scc = vec![0; self.node_count()];
+        for (i, c) in self.strongly_connected_components().iter().enumerate() {
+            for &v in c.iter() {
+                scc[v] = i;
+            }
+        }
+        self.edge_references()
+            .filter(|e| scc[e.source().index()] == scc[e.target().index()])
+            .map(|e| e.id().index())
+            .collect()
+    }
+
+    fn cyclic_core_edges_loose(&self) -> Vec<usize> {
+        let vert_core = self.cyclic_core();
+        let mut edge_core = Vec::<usize>::new();
+        for v in vert_core.iter() {
+            for (e, w) in self.edges_from(*v) {
+                if bin_member(&vert_core, &{ w }) {
+                    edge_core.push(e);
+                }
+            }
+        }
+        edge_core.sort();
+        edge_core
+    }
+
+    // Depth-first search, marking vertices as on the stack or done, and stopping if an edge
+    // to a vertex on the stack is found.
+
+    fn acyclic(&self) -> bool {
+        let n = self.node_count();
+        let (on_stack, done) = (1_u8, 2_u8);
+        let mut state = vec![0_u8; n];
+        for s in 0..n {
+            if state[s] != 0 {
+                continue;
+            }
+            state[s] = on_stack;
+            let mut stack = vec![(s, self.edges_from(s))];
+            while let Some(top) = stack.last_mut() {
+                match top.1.next() {
+                    Some((_, y)) => {
+                        if state[y] == on_stack {
+                            return false;
+                        }
+                        if state[y] == 0 {
+                            state[y] = on_stack;
+                            stack.push((y, self.edges_from(y)));
+                        }
+                    }
+                    None => {
+                        state[top.0] = done;
+                        stack.pop();
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    fn get_predecessors(&self, v: &[usize], x: &mut Vec<usize>) {
+        let n = self.node_count();
+        if n <= DENSE_MAX_NODES {
+            self.get_predecessors_dense(v, x);
+            return;
+        }
+        let mut check: Vec<usize> = Vec::new();
+        let mut tov: HashSet<usize> = HashSet::new();
+        for &s in v.iter() {
+            check.push(s);
+            tov.insert(s);
+        }
+        while let Some(u) = check.pop() {
+            for (_, y) in self.edges_to(u) {
+                if tov.contains(&y) {
+                    continue;
+                }
+                check.push(y);
+                tov.insert(y);
+                if tov.len() > n / DENSE_FRACTION {
+                    check.push(u);
+                    let found = tov.iter().copied().collect::<Vec<usize>>();
+                    dense_closure(self, &found, check, Incoming, x);
+                    return;
+                }
+            }
+        }
+        x.clear();
+        for v in tov {
+            x.push(v);
+        }
+        x.sort_unstable();
+    }
+
+    fn get_predecessors1(&self, v: usize, x: &mut Vec<usize>) {
+        let vs = vec![v];
+        self.get_predecessors(&vs, x);
+    }
+
+    fn get_successors(&self, v: &[usize], x: &mut Vec<usize>) {
+        let n = self.node_count();
+        if n <= DENSE_MAX_NODES {
+            self.get_successors_dense(v, x);
+            return;
+        }
+        let mut check: Vec<usize> = Vec::new();
+        let mut fromv: HashSet<usize> = HashSet::new();
+        for &s in v.iter() {
+            check.push(s);
+            fromv.insert(s);
+        }
+        while let Some(u) = check.pop() {
+            for (_, y) in self.edges_from(u) {
+                if fromv.contains(&y) {
+                    continue;
+                }
+                check.push(y);
+                fromv.insert(y);
+                if fromv.len() > n / DENSE_FRACTION {
+                    check.push(u);
+                    let found = fromv.iter().copied().collect::<Vec<usize>>();
+                    dense_closure(self, &found, check, Outgoing, x);
+                    return;
+                }
+            }
+        }
+        x.clear();
+        for v in fromv {
+            x.push(v);
+        }
+        x.sort_unstable();
+    }
+
+    fn get_successors1(&self, v: usize, x: &mut Vec<usize>) {
+        let vs = vec![v];
+        self.get_successors(&vs, x);
+    }
+
+    fn get_predecessors_dense(&self, v: &[usize], x: &mut Vec<usize>) {
+        let check = v.to_vec();
+        dense_closure(self, &check.clone(), check, Incoming, x);
+    }
+
+    fn get_successors_dense(&self, v: &[usize], x: &mut Vec<usize>) {
+        let check = v.to_vec();
+        dense_closure(self, &check.clone(), check, Outgoing, x);
+    }
+
+    // Search forward from v, stopping as soon as w is seen.  A hash set is used to track
+    // visited vertices so that the cost is proportional to the part of the graph explored.
+
+    fn have_path(&self, v: usize, w: usize) -> bool {
+        if v == w {
+            return true;
+        }
+
+        // Breadth-first search from v, stopping as soon as w is reached.
+
+        let mut seen = HashSet::<usize>::new();
+        seen.insert(v);
+        let mut next = std::collections::VecDeque::<usize>::new();
+        next.push_back(v);
+        while let Some(x) = next.pop_front() {
+            for y in self.neighbors_directed(NodeIndex::<V>::new(x), Outgoing) {
+                let y = y.index();
+                if y == w {
+                    return true;
+                }
+                if seen.insert(y) {
+                    next.push_back(y);
+                }
+            }
+        }
+        false
+    }
+
+    fn components(&self, comp: &mut Vec<Vec<usize>>) {
+        let (ids, n) = union_find_components(self);
+        comp.clear();
+        comp.resize(n, Vec::new());
+        for (v, &id) in ids.iter().enumerate() {
+            comp[id].push(v);
+        }
+    }
+
+    fn component_ids(&self) -> Vec<usize> {
+        union_find_components(self).0
+    }
+
+    fn n_components(&self) -> usize {
+        union_find_components(self).1
+    }
+
+    fn components_e(&self, comp: &mut Vec<Vec<usize>>) {
+        let (ids, n) = union_find_components(self);
+        comp.clear();
+        comp.resize(n, Vec::new());
+        for e in self.edge_references() {
+            comp[ids[e.source().index()]].push(e.id().index());
+        }
+    }
+
+    // Order the vertices by listing the strongly connected components in topological
+    // order, and within each, listing its vertices in reverse postorder for a depth-first
+    // search from its smallest vertex, visiting neighbors in increasing order.
+
+    fn components_e_pos_sorted(&self, comp: &mut Vec<Vec<usize>>) {
+        self.components_e(comp);
+        let sccs = self.strongly_connected_components();
+        let mut scc_id = vec![0; self.node_count()];
+        for (i, c) in sccs.iter().enumerate() {
+            for &v in c.iter() {
+                scc_id[v] = i;
+            }
+        }
+        let mut pos = vec![0; self.node_count()];
+        let mut p = 0;
+        for (i, c) in sccs.iter().enumerate().rev() {
+            let next_of = |x: usize| {
+                let mut y = self
+                    .edges_from(x)
+                    .map(|(_, y)| y)
+                    .filter(|&y| scc_id[y] == i)
+                    .collect::<Vec<usize>>();
+                y.sort_unstable_by(|a, b| b.cmp(a));
+                y.dedup();
+                y
+            };
+            let mut postorder = Vec::<usize>::with_capacity(c.len());
+            let mut seen = HashSet::<usize>::new();
+            seen.insert(c[0]);
+            let mut stack = vec![(c[0], next_of(c[0]))];
+            while let Some(top) = stack.last_mut() {
+                match top.1.pop() {
+                    Some(y) => {
+                        if seen.insert(y) {
+                            stack.push((y, next_of(y)));
+                        }
+                    }
+                    None => {
+                        postorder.push(top.0);
+                        stack.pop();
+                    }
+                }
+            }
+            for &v in postorder.iter().rev() {
+                pos[v] = p;
+                p += 1;
+            }
+        }
+        for c in comp.iter_mut() {
+            c.sort_by_key(|&e| (pos[self.to_left(e)], pos[self.to_right(e)], e));
+        }
+    }
+
+    fn copathic(&self, p1: &[usize], p2: &[usize]) -> bool {
+        for i in 0..p1.len() {
+            if p2.starts_with(&p1[i..]) || p1[i..].starts_with(p2) {
+                return true;
+            }
+        }
+        for i in 0..p2.len() {
+            if p1.starts_with(&p2[i..]) || p2[i..].starts_with(p1) {
+                return true;
+            }
+        }
+        let v = self.to_right(p1[p1.len() - 1]);
+        let w = self.to_left(p2[0]);
+        let mut suc = Vec::<usize>::new();
+        self.get_successors1(v, &mut suc);
+        if bin_member(&suc, &w) {
+            return true;
+        }
+        let v = self.to_right(p2[p2.len() - 1]);
+        let w = self.to_left(p1[0]);
+        self.get_successors1(v, &mut suc);
+        if bin_member(&suc, &w) {
+            return true;
+        }
+        false
+    }
+
+    // Breadth-first search from v, recording the edge by which each vertex is first reached,
+    // and stopping when w is reached.
+
+    fn shortest_path_edges(&self, v: usize, w: usize) -> Option<Vec<usize>> {
+        if v == w {
+            return Some(Vec::new());
+        }
+        let mut parent: Vec<Option<usize>> = vec![None; self.node_count()];
+        let mut seen = vec![false; self.node_count()];
+        seen[v] = true;
+        let mut next = std::collections::VecDeque::<usize>::new();
+        next.push_back(v);
+        while let Some(x) = next.pop_front() {
+            for e in self.edges_directed(NodeIndex::<V>::new(x), Outgoing) {
+                let y = e.target().index();
+                if seen[y] {
+                    continue;
+                }
+                seen[y] = true;
+                parent[y] = Some(e.id().index());
+                if y == w {
+                    let mut path = Vec::<usize>::new();
+                    let mut z = w;
+                    while z != v {
+                        let e = parent[z].unwrap();
+                        path.push(e);
+                        z = self.to_left(e);
+                    }
+                    path.reverse();
+                    return Some(path);
+                }
+                next.push_back(y);
+            }
+        }
+        None
+    }
+
+    fn shortest_path(&self, v: usize, w: usize) -> Option<Vec<usize>> {
+        let edges = self.shortest_path_edges(v, w)?;
+        let mut path = vec![v];
+        for e in edges {
+            path.push(self.to_right(e));
+        }
+        Some(path)
+    }
+
+    // Depth-first search using an explicit stack, each entry of which holds the
+    // neighbors of a vertex on the current path that have yet to be tried, in reverse
+    // order.
+
+    fn all_simple_paths(
+        &self,
+        v: usize,
+        w: usize,
+        max_paths: usize,
+        max_len: usize,
+    ) -> Vec<Vec<usize>> {
+        let mut paths = Vec::<Vec<usize>>::new();
+        if max_paths == 0 || max_len == 0 {
+            return paths;
+        }
+        if v == w {
+            paths.push(vec![v]);
+            return paths;
+        }
+        let next_of = |x: usize| {
+            let mut y = self
+                .neighbors_directed(NodeIndex::<V>::new(x), Outgoing)
+                .map(|y| y.index())
+                .collect::<Vec<usize>>();
+            y.sort_unstable_by(|a, b| b.cmp(a));
+            y.dedup();
+            y
+        };
+        let mut on_path = vec![false; self.node_count()];
+        on_path[v] = true;
+        let mut path = vec![v];
+        let mut stack = vec![next_of(v)];
+        while let Some(next) = stack.last_mut() {
+            let y = match next.pop() {
+                Some(y) => y,
+                None => {
+                    stack.pop();
+                    on_path[path.pop().unwrap()] = false;
+                    continue;
+                }
+            };
+            if on_path[y] || path.len() >= max_len {
+                continue;
+            }
+            if y == w {
+                let mut p = path.clone();
+                p.push(y);
+                paths.push(p);
+                if paths.len() == max_paths {
+                    break;
+                }
+                continue;
+            }
+            on_path[y] = true;
+            path.push(y);
+            stack.push(next_of(y));
+        }
+        paths
+    }
+
+    fn strongly_connected_components(&self) -> Vec<Vec<usize>> {
+        let mut sccs = Vec::<Vec<usize>>::new();
+        for c in petgraph::algo::kosaraju_scc(self) {
+            let mut c = c.iter().map(|v| v.index()).collect::<Vec<usize>>();
+            c.sort_unstable();
+            sccs.push(c);
+        }
+        sccs
+    }
+
+    fn reachability(&self) -> ReachabilityIndex {
+        ReachabilityIndex::new(self)
+    }
+
+    fn condensation(&self, dedup: bool) -> (Graph<Vec<usize>, Vec<usize>, Directed>, Vec<usize>) {
+        let sccs = self.strongly_connected_components();
+        let mut comp = vec![0_usize; self.node_count()];
+        for (i, c) in sccs.iter().enumerate() {
+            for &v in c.iter() {
+                comp[v] = i;
+            }
+        }
+        let mut h = Graph::<Vec<usize>, Vec<usize>, Directed>::with_capacity(sccs.len(), 0);
+        for c in sccs {
+            h.add_node(c);
+        }
+        let mut edge_of = HashMap::<(usize, usize), EdgeIndex>::new();
+        for e in self.edge_references() {
+            let (v, w) = (comp[e.source().index()], comp[e.target().index()]);
+            if v == w {
+                continue;
+            }
+            let id = e.id().index();
+            if !dedup {
+                h.add_edge(NodeIndex::new(v), NodeIndex::new(w), vec![id]);
+            } else if let Some(&f) = edge_of.get(&(v, w)) {
+                h[f].push(id);
+            } else {
+                let f = h.add_edge(NodeIndex::new(v), NodeIndex::new(w), vec![id]);
+                edge_of.insert((v, w), f);
+            }
+        }
+        (h, comp)
+    }
+
+    // For each vertex s of the cyclic core in turn, search for cycles through s and larger
+    // vertices.  This is done by a depth-first search using an explicit stack, whose entries
+    // consist of a vertex on the current path, its neighbors that have yet to be tried (in
+    // reverse order), and whether a cycle has been found through it.  As in Johnson's
+    // algorithm, a vertex is blocked while it is on the path, and remains blocked after that
+    // if no cycle was found through it, until a cycle is found through one of its neighbors.
+    // If the length bound stops the search, we proceed as if a cycle had been found, so that
+    // nothing is blocked as a result.
+
+    fn cycles(&self, max_cycles: usize, max_len: usize) -> Vec<Vec<usize>> {
+        let mut cycles = Vec::<Vec<usize>>::new();
+        if max_cycles == 0 || max_len == 0 {
+            return cycles;
+        }
+        let n = self.node_count();
+        let mut in_core = vec![false; n];
+        let core = self.cyclic_core();
+        for &v in core.iter() {
+            in_core[v] = true;
+        }
+        let mut blocked = vec![false; n];
+        let mut blist = vec![Vec::<usize>::new(); n];
+        for &s in core.iter() {
+            let next_of = |x: usize| {
+                let mut y = self
+                    .neighbors_directed(NodeIndex::<V>::new(x), Outgoing)
+                    .map(|y| y.index())
+                    .filter(|&y| y >= s && in_core[y])
+                    .collect::<Vec<usize>>();
+                y.sort_unstable_by(|a, b| b.cmp(a));
+                y.dedup();
+                y
+            };
+            for &v in core.iter() {
+                blocked[v] = false;
+                blist[v].clear();
+            }
+            blocked[s] = true;
+            let mut path = vec![s];
+            let mut stack = vec![(s, next_of(s), false)];
+            while let Some(top) = stack.last_mut() {
+                if let Some(w) = top.1.pop() {
+                    if w == s {
+                        cycles.push(path.clone());
+                        if cycles.len() == max_cycles {
+                            return cycles;
+                        }
+                        top.2 = true;
+                    } else if !blocked[w] {
+                        if path.len() < max_len {
+                            blocked[w] = true;
+                            path.push(w);
+                            stack.push((w, next_of(w), false));
+                        } else {
+                            top.2 = true;
+                        }
+                    }
+                    continue;
+                }
+                let (v, _, found) = stack.pop().unwrap();
+                path.pop();
+                if found {
+                    let mut unblock = vec![v];
+                    while let Some(x) = unblock.pop() {
+                        if blocked[x] {
+                            blocked[x] = false;
+                            unblock.append(&mut blist[x]);
+                        }
+                    }
+                    if let Some(parent) = stack.last_mut() {
+                        parent.2 = true;
+                    }
+                } else {
+                    for w in next_of(v) {
+                        if !blist[w].contains(&v) {
+                            blist[w].push(v);
+                        }
+                    }
+                }
+            }
+        }
+        cycles
+    }
+
+    // Process the vertices in topological order, found using Kahn's algorithm, so that the
+    // count for each vertex is complete before it is passed on to its successors.
+
+    fn count_paths(&self, v: usize, w: usize) -> Option<u128> {
+        let n = self.node_count();
+        let mut ins = vec![0_usize; n];
+        for e in self.edge_references() {
+            ins[e.target().index()] += 1;
+        }
+        let mut count = vec![0_u128; n];
+        count[v] = 1;
+        let mut next = (0..n).filter(|&x| ins[x] == 0).collect::<Vec<usize>>();
+        let mut processed = 0;
+        while let Some(x) = next.pop() {
+            processed += 1;
+            for (_, y) in self.edges_from(x) {
+                count[y] = count[y].saturating_add(count[x]);
+                ins[y] -= 1;
+                if ins[y] == 0 {
+                    next.push(y);
+                }
+            }
+        }
+        if processed < n {
+            return None;
+        }
+        Some(count[w])
+    }
+
+    fn edges_between(&self, v: usize, w: usize) -> Vec<usize> {
+        let mut x = self
+            .edges_from(v)
+            .filter(|&(_, y)| y == w)
+            .map(|(e, _)| e)
+            .collect::<Vec<usize>>();
+        x.sort_unstable();
+        x
+    }
+
+    fn has_edge(&self, v: usize, w: usize) -> bool {
+        self.edges_from(v).any(|(_, y)| y == w)
+    }
+
+    // Breadth-first search, one frontier at a time.
+
+    fn neighborhood(&self, v: usize, radius: usize, direction: Dir) -> Vec<usize> {
+        let mut seen = HashSet::<usize>::new();
+        seen.insert(v);
+        let mut frontier = vec![v];
+        for _ in 0..radius {
+            let mut next = Vec::<usize>::new();
+            for &x in frontier.iter() {
+                if direction != Dir::Incoming {
+                    for (_, y) in self.edges_from(x) {
+                        if seen.insert(y) {
+                            next.push(y);
+                        }
+                    }
+                }
+                if direction != Dir::Outgoing {
+                    for (_, y) in self.edges_to(x) {
+                        if seen.insert(y) {
+                            next.push(y);
+                        }
+                    }
+                }
+            }
+            if next.is_empty() {
+                break;
+            }
+            frontier = next;
+        }
+        let mut x = seen.into_iter().collect::<Vec<usize>>();
+        x.sort_unstable();
+        x
+    }
+
+    fn depths_from_sources(&self) -> Vec<Option<usize>> {
+        self.depths_from_roots(&self.sources())
+    }
+
+    // Breadth-first search from all roots at once.  The depths are the shortest path
+    // lengths, so they do not depend on the order in which edges are visited.
+
+    fn depths_from_roots(&self, roots: &[usize]) -> Vec<Option<usize>> {
+        let mut depth = vec![None; self.node_count()];
+        let mut queue = Vec::<usize>::new();
+        for &r in roots.iter() {
+            if depth[r].is_none() {
+                depth[r] = Some(0);
+                queue.push(r);
+            }
+        }
+        let mut i = 0;
+        while i < queue.len() {
+            let v = queue[i];
+            let d = depth[v].unwrap() + 1;
+            for (_, w) in self.edges_from(v) {
+                if depth[w].is_none() {
+                    depth[w] = Some(d);
+                    queue.push(w);
+                }
+            }
+            i += 1;
+        }
+        depth
+    }
+
+    // This uses the iterative algorithm of Cooper, Harvey and Kennedy, "A Simple, Fast
+    // Dominance Algorithm" (2001), which processes the vertices in reverse postorder until
+    // nothing changes, finding the immediate dominator of each vertex as the nearest common
+    // dominator of its processed predecessors.
+
+    fn dominators(&self, root: usize) -> Vec<Option<usize>> {
+        let n = self.node_count();
+        const UNDEF: usize = usize::MAX;
+
+        // Find the postorder of the vertices reachable from root.
+
+        let mut po = vec![UNDEF; n];
+        let mut postorder = Vec::<usize>::new();
+        let mut seen = vec![false; n];
+        seen[root] = true;
+        let mut stack = vec![(root, self.edges_from(root))];
+        while let Some(top) = stack.last_mut() {
+            match top.1.next() {
+                Some((_, y)) => {
+                    if !seen[y] {
+                        seen[y] = true;
+                        stack.push((y, self.edges_from(y)));
+                    }
+                }
+                None => {
+                    po[top.0] = postorder.len();
+                    postorder.push(top.0);
+                    stack.pop();
+                }
+            }
+        }
+
+        // Iterate to a fixed point.
+
+        let mut idom = vec![UNDEF; n];
+        idom[root] = root;
+        let intersect = |idom: &[usize], mut a: usize, mut b: usize| {
+            while a != b {
+                while po[a] < po[b] {
+                    a = idom[a];
+                }
+                while po[b] < po[a] {
+                    b = idom[b];
+                }
+            }
+            a
+        };
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for &v in postorder.iter().rev() {
+                if v == root {
+                    continue;
+                }
+                let mut new_idom = UNDEF;
+                for (_, p) in self.edges_to(v) {
+                    if idom[p] == UNDEF {
+                        continue;
+                    }
+                    new_idom = if new_idom == UNDEF {
+                        p
+                    } else {
+                        intersect(&idom, p, new_idom)
+                    };
+                }
+                if idom[v] != new_idom {
+                    idom[v] = new_idom;
+                    changed = true;
+                }
+            }
+        }
+        idom[root] = UNDEF;
+        idom.iter()
+            .map(|&d| if d == UNDEF { None } else { Some(d) })
+            .collect()
+    }
+
+    fn unary_chains(&self) -> Vec<Vec<usize>> {
+        let n = self.node_count();
+        let (mut ins, mut outs) = (vec![0_usize; n], vec![0_usize; n]);
+        let mut next = vec![0_usize; n];
+        for e in self.edge_references() {
+            outs[e.source().index()] += 1;
+            ins[e.target().index()] += 1;
+            next[e.source().index()] = e.id().index();
+        }
+        let interior = |v: usize| ins[v] == 1 && outs[v] == 1;
+        let mut used = vec![false; self.edge_count()];
+        let mut chains = Vec::<Vec<usize>>::new();
+        for pass in 0..2 {
+            for e in 0..self.edge_count() {
+                if used[e] || (pass == 0 && interior(self.to_left(e))) {
+                    continue;
+                }
+                let mut c = vec![e];
+                used[e] = true;
+                loop {
+                    let w = self.to_right(*c.last().unwrap());
+                    if !interior(w) || used[next[w]] {
+                        break;
+                    }
+                    used[next[w]] = true;
+                    c.push(next[w]);
+                }
+                chains.push(c);
+            }
+        }
+        chains
+    }
+
+    fn collapse_chains(&self) -> Graph<usize, Vec<usize>, Directed> {
+        let chains = self.unary_chains();
+        let n = self.node_count();
+        let mut ins = vec![0_usize; n];
+        let mut outs = vec![0_usize; n];
+        for e in self.edge_references() {
+            outs[e.source().index()] += 1;
+            ins[e.target().index()] += 1;
+        }
+        let mut h = Graph::<usize, Vec<usize>, Directed>::new();
+        let mut to_new = vec![None; n];
+        for v in 0..n {
+            if ins[v] != 1 || outs[v] != 1 {
+                to_new[v] = Some(h.add_node(v));
+            }
+        }
+        for c in chains {
+            let v = self.to_left(c[0]);
+            let w = self.to_right(*c.last().unwrap());
+            if to_new[v].is_none() {
+                to_new[v] = Some(h.add_node(v));
+            }
+            h.add_edge(to_new[v].unwrap(), to_new[w].unwrap(), c);
+        }
+        h
+    }
+
+    fn cut_vertices(&self) -> Vec<usize> {
+        cut_vertices_and_bridges(self).0
+    }
+
+    fn bridges(&self) -> Vec<usize> {
+        cut_vertices_and_bridges(self).1
+    }
+
+    fn self_loops(&self) -> Vec<usize> {
+        self.edge_references()
+            .filter(|e| e.source() == e.target())
+            .map(|e| e.id().index())
+            .collect()
+    }
+
+    fn parallel_edge_groups(&self) -> Vec<Vec<usize>> {
+        let mut groups = HashMap::<(usize, usize), Vec<usize>>::new();
+        for e in self.edge_references() {
+            groups
+                .entry((e.source().index(), e.target().index()))
+                .or_default()
+                .push(e.id().index());
+        }
+        let mut x = groups
+            .into_values()
+            .filter(|g| g.len() >= 2)
+            .collect::<Vec<Vec<usize>>>();
+        x.sort_unstable();
+        x
+    }
+
+    // First collect the back edges of a depth-first search that does not leave the strongly
+    // connected component in which it starts.  Removing these makes the graph acyclic.  Then
+    // restore them one at a time, in order, keeping only those whose restoration would close
+    // a cycle, i.e. those v -> w such that w reaches v in the current graph.  Such a path
+    // stays within the component of v and w.  Restoring edges only adds paths, so an edge
+    // kept early on could not have been restored later.
+
+    fn feedback_edges(&self) -> Vec<usize> {
+        let n = self.node_count();
+        let mut scc = vec![0; n];
+        for (i, c) in self.strongly_connected_components().iter().enumerate() {
+            for &v in c.iter() {
+                scc[v] = i;
+            }
+        }
+        let (on_stack, done) = (1_u8, 2_u8);
+        let mut state = vec![0_u8; n];
+        let mut removed = vec![false; self.edge_count()];
+        let mut back = Vec::<usize>::new();
+        for s in 0..n {
+            if state[s] != 0 {
+                continue;
+            }
+            state[s] = on_stack;
+            let mut stack = vec![(s, self.edges_from(s))];
+            while let Some(top) = stack.last_mut() {
+                let v = top.0;
+                match top.1.next() {
+                    Some((e, y)) => {
+                        if scc[y] != scc[v] {
+                            continue;
+                        }
+                        if state[y] == on_stack {
+                            removed[e] = true;
+                            back.push(e);
+                        } else if state[y] == 0 {
+                            state[y] = on_stack;
+                            stack.push((y, self.edges_from(y)));
+                        }
+                    }
+                    None => {
+                        state[v] = done;
+                        stack.pop();
+                    }
+                }
+            }
+        }
+        back.sort_unstable();
+        let mut fb = Vec::<usize>::new();
+        for &e in back.iter() {
+            let (v, w) = (self.to_left(e), self.to_right(e));
+            removed[e] = false;
+            let mut seen = HashSet::<usize>::new();
+            seen.insert(w);
+            let mut check = vec![w];
+            while let Some(u) = check.pop() {
+                if u == v {
+                    removed[e] = true;
+                    fb.push(e);
+                    break;
+                }
+                for (f, y) in self.edges_from(u) {
+                    if !removed[f] && scc[y] == scc[v] && seen.insert(y) {
+                        check.push(y);
+                    }
+                }
+            }
+        }
+        fb
+    }
+
+    fn degree_summary(&self) -> GraphSummary {
+        let n = self.node_count();
+        let (mut ins, mut outs) = (vec![0_usize; n], vec![0_usize; n]);
+        let mut x = GraphSummary {
+            nodes: n,
+            edges: self.edge_count(),
+            ..Default::default()
+        };
+        let mut mult = HashMap::<(usize, usize), usize>::new();
+        for e in self.edge_references() {
+            let (v, w) = (e.source().index(), e.target().index());
+            outs[v] += 1;
+            ins[w] += 1;
+            if v == w {
+                x.self_loops += 1;
+            }
+            let m = mult.entry((v, w)).or_insert(0);
+            x.parallel_pairs += *m;
+            *m += 1;
+        }
+        if n > 0 {
+            x.min_in = usize::MAX;
+            x.min_out = usize::MAX;
+            x.mean_in = x.edges as f64 / n as f64;
+            x.mean_out = x.mean_in;
+        }
+        for v in 0..n {
+            x.min_in = x.min_in.min(ins[v]);
+            x.max_in = x.max_in.max(ins[v]);
+            x.min_out = x.min_out.min(outs[v]);
+            x.max_out = x.max_out.max(outs[v]);
+            if ins[v] == 0 {
+                x.sources += 1;
+            }
+            if outs[v] == 0 {
+                x.sinks += 1;
+            }
+            if ins[v] == 0 && outs[v] == 0 {
+                x.isolated += 1;
+            }
+        }
+        x
+    }
+}
+
+// tests can be run with
+// cargo test -p graph_simple -- --nocapture
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_shortest_path() {
+        use crate::GraphSimpleIdx;
+        use petgraph::graph::DiGraph;
+        let g = DiGraph::<i32, ()>::from_edges([(0, 1), (1, 2), (2, 3), (0, 4), (4, 3), (3, 5)]);
+        assert_eq!(g.shortest_path(0, 5), Some(vec![0, 4, 3, 5]));
+        assert_eq!(g.shortest_path_edges(0, 5), Some(vec![3, 4, 5]));
+        assert_eq!(g.shortest_path(1, 3), Some(vec![1, 2, 3]));
+        assert_eq!(g.shortest_path(2, 2), Some(vec![2]));
+        assert_eq!(g.shortest_path_edges(2, 2), Some(vec![]));
+        assert_eq!(g.shortest_path(5, 0), None);
+        let g = DiGraph::<i32, ()>::from_edges([(0, 1), (2, 3)]);
+        assert_eq!(g.shortest_path(0, 3), None);
+        assert_eq!(g.shortest_path_edges(0, 3), None);
+    }
+
+    #[test]
+    fn test_all_simple_paths() {
+        use crate::GraphSimpleIdx;
+        use petgraph::graph::DiGraph;
+
+        // Diamond.
+
+        let g = DiGraph::<i32, ()>::from_edges([(0, 1), (0, 2), (1, 3), (2, 3)]);
+        assert_eq!(
+            g.all_simple_paths(0, 3, 10, 10),
+            vec![vec![0, 1, 3], vec![0, 2, 3]]
+        );
+        assert_eq!(g.all_simple_paths(3, 0, 10, 10), Vec::<Vec<usize>>::new());
+        assert_eq!(g.all_simple_paths(1, 1, 10, 10), vec![vec![1]]);
+
+        // Diamond with a cycle 1 -> 4 -> 5 -> 1 hanging off it, a self-loop, and a
+        // parallel edge.
+
+        let g = DiGraph::<i32, ()>::from_edges([
+            (0, 1),
+            (0, 2),
+            (1, 3),
+            (2, 3),
+            (1, 4),
+            (4, 5),
+            (5, 1),
+            (4, 4),
+            (2, 3),
+        ]);
+        assert_eq!(
+            g.all_simple_paths(0, 3, 10, 10),
+            vec![vec![0, 1, 3], vec![0, 2, 3]]
+        );
+        assert_eq!(g.all_simple_paths(4, 3, 10, 10), vec![vec![4, 5, 1, 3]]);
+
+        // Caps.
+
+        let g = DiGraph::<i32, ()>::from_edges([
+            (0, 1),
+            (0, 2),
+            (0, 3),
+            (1, 4),
+            (2, 4),
+            (3, 5),
+            (5, 4),
+        ]);
+        assert_eq!(g.all_simple_paths(0, 4, 10, 10).len(), 3);
+        assert_eq!(
+            g.all_simple_paths(0, 4, 2, 10),
+            vec![vec![0, 1, 4], vec![0, 2, 4]]
+        );
+        assert_eq!(
+            g.all_simple_paths(0, 4, 10, 3),
+            vec![vec![0, 1, 4], vec![0, 2, 4]]
+        );
+        assert_eq!(g.all_simple_paths(0, 4, 10, 2), Vec::<Vec<usize>>::new());
+        assert_eq!(g.all_simple_paths(0, 4, 0, 10), Vec::<Vec<usize>>::new());
+    }
+
+    #[test]
+    fn test_reachability() {
+        use crate::GraphSimpleIdx;
+        use petgraph::graph::DiGraph;
+        use rand::{rngs::StdRng, Rng, SeedableRng};
+        use std::time::Instant;
+
+        // Compare to have_path on random graphs, which generally have cycles.
+
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..20 {
+            let n = 1 + rng.gen::<u32>() % 30;
+            let m = rng.gen::<u32>() % 40;
+            let mut edges = Vec::<(u32, u32)>::new();
+            for _ in 0..m {
+                edges.push((rng.gen::<u32>() % n, rng.gen::<u32>() % n));
+            }
+            let mut g = DiGraph::<i32, ()>::from_edges(&edges);
+            while g.node_count() < n as usize {
+                g.add_node(0);
+            }
+            let index = g.reachability();
+            for v in 0..n as usize {
+                for w in 0..n as usize {
+                    assert_eq!(index.query(v, w), g.have_path(v, w));
+                }
+            }
+        }
+
+        // Compare speed on a 10k-vertex DAG.  To see the times, run with --nocapture.
+
+        let n = 10_000;
+        let mut edges = Vec::<(u32, u32)>::new();
+        for v in 0..n - 1 {
+            edges.push((v, v + 1));
+            let w = v + 1 + rng.gen::<u32>() % (n - v - 1);
+            edges.push((v, w));
+        }
+        let g = DiGraph::<i32, ()>::from_edges(&edges);
+        let mut queries = Vec::<(usize, usize)>::new();
+        for _ in 0..100 {
+            queries.push((
+                (rng.gen::<u32>() % n) as usize,
+                (rng.gen::<u32>() % n) as usize,
+            ));
+        }
+        let t = Instant::now();
+        let old = queries
+            .iter()
+            .map(|&(v, w)| g.have_path(v, w))
+            .collect::<Vec<bool>>();
+        let old_time = t.elapsed();
+        let t = Instant::now();
+        let index = g.reachability();
+        let new = queries
+            .iter()
+            .map(|&(v, w)| index.query(v, w))
+            .collect::<Vec<bool>>();
+        let new_time = t.elapsed();
+        assert_eq!(old, new);
+        println!(
+            "100 queries on 10k-vertex DAG: have_path {:.3} s, reachability {:.3} s",
+            old_time.as_secs_f64(),
+            new_time.as_secs_f64()
+        );
+    }
+
+    #[test]
+    fn test_have_path() {
+        use crate::GraphSimpleIdx;
+        use petgraph::graph::DiGraph;
+        use std::time::Instant;
+
+        // On a long chain, a query between adjacent vertices should not have to look at
+        // the rest of the chain.
+
+        let n = 1_000_000;
+        let edges = (0..n - 1).map(|v| (v, v + 1)).collect::<Vec<(u32, u32)>>();
+        let g = DiGraph::<i32, ()>::from_edges(&edges);
+        let t = Instant::now();
+        for v in 0..1000 {
+            assert!(g.have_path(v, v + 2));
+        }
+        assert!(t.elapsed().as_secs_f64() < 10.0);
+        assert!(g.have_path(0, n as usize - 1));
+        assert!(!g.have_path(n as usize - 1, 0));
+        assert!(g.have_path(5, 5));
+
+        // Unreachable pairs, and a cycle.
+
+        let g = DiGraph::<i32, ()>::from_edges([(0, 1), (1, 2), (2, 1), (3, 4)]);
+        assert!(g.have_path(0, 2));
+        assert!(g.have_path(2, 1));
+        assert!(!g.have_path(1, 0));
+        assert!(!g.have_path(0, 3));
+        assert!(!g.have_path(4, 3));
+        assert!(g.have_path(3, 3));
+    }
+
+    #[test]
+    fn test_edges_from() {
+        use crate::GraphSimpleIdx;
+        use petgraph::graph::DiGraph;
+
+        let g = DiGraph::<i32, ()>::from_edges([(0, 1), (0, 2), (2, 0), (1, 2)]);
+        let mut x = g.edges_from(0).collect::<Vec<(usize, usize)>>();
+        x.sort_unstable();
+        assert_eq!(x, vec![(0, 1), (1, 2)]);
+        let mut x = g.edges_to(2).collect::<Vec<(usize, usize)>>();
+        x.sort_unstable();
+        assert_eq!(x, vec![(1, 0), (3, 1)]);
+        for v in 0..3 {
+            for (j, (e, w)) in g.edges_from(v).enumerate() {
+                assert_eq!(e, g.e_from(v, j));
+                assert_eq!(w, g.v_from(v, j));
+            }
+            for (j, (e, w)) in g.edges_to(v).enumerate() {
+                assert_eq!(e, g.e_to(v, j));
+                assert_eq!(w, g.v_to(v, j));
+            }
+        }
+
+        // A star graph with a high-degree center, on which looping over v_from would be
+        // quadratic.  The edges from the center to leaves go both ways, so the center and
+        // leaves lie in the cyclic core.
+
+        let n = 50_000;
+        let mut edges = Vec::<(u32, u32)>::new();
+        for v in 1..=n {
+            edges.push((0, v));
+            edges.push((v, 0));
+        }
+        let g = DiGraph::<i32, ()>::from_edges(&edges);
+        assert_eq!(g.cyclic_core().len(), n as usize + 1);
+        assert_eq!(g.cyclic_core_edges().len(), 2 * n as usize);
+        let mut x = Vec::<usize>::new();
+        g.get_successors1(0, &mut x);
+        assert_eq!(x.len(), n as usize + 1);
+        g.get_predecessors1(0, &mut x);
+        assert_eq!(x.len(), n as usize + 1);
+        let mut comp = Vec::<Vec<usize>>::new();
+        g.components(&mut comp);
+        assert_eq!(comp.len(), 1);
+        g.components_e(&mut comp);
+        assert_eq!(comp[0].len(), 2 * n as usize);
+    }
+
+    #[test]
+    fn test_get_successors_dense() {
+        use crate::GraphSimpleIdx;
+        use petgraph::graph::DiGraph;
+        use rand::{rngs::StdRng, Rng, SeedableRng};
+        use std::time::Instant;
+
+        // Form a layered DAG having 200 layers of 1000 vertices, with each vertex having
+        // edges to two random vertices in the next layer.
+
+        let (layers, width) = (200, 1000);
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut edges = Vec::<(u32, u32)>::new();
+        for l in 0..layers - 1 {
+            for i in 0..width {
+                for _ in 0..2 {
+                    let j = rng.gen::<u32>() % width;
+                    edges.push((l * width + i, (l + 1) * width + j));
+                }
+            }
+        }
+        let g = DiGraph::<i32, ()>::from_edges(&edges);
+
+        // Compare the automatic and dense versions, from near the top, which reaches most
+        // of the graph, and from near the bottom, where the sparse path is used throughout.
+        // To see the times, run with --nocapture.
+
+        for &v in [0, width as usize * (layers as usize - 3)].iter() {
+            let (mut x1, mut x2) = (Vec::<usize>::new(), Vec::<usize>::new());
+            let t = Instant::now();
+            g.get_successors1(v, &mut x1);
+            let t1 = t.elapsed().as_secs_f64();
+            let t = Instant::now();
+            g.get_successors_dense(&[v], &mut x2);
+            let t2 = t.elapsed().as_secs_f64();
+            assert_eq!(x1, x2);
+            println!(
+                "{} successors of {}: automatic {:.3} s, dense {:.3} s",
+                x1.len(),
+                v,
+                t1,
+                t2
+            );
+            let w = g.node_count() - 1 - v;
+            g.get_predecessors1(w, &mut x1);
+            g.get_predecessors_dense(&[w], &mut x2);
+            assert_eq!(x1, x2);
+        }
+
+        // Check the sparse path against the dense path on a small graph.
+
+        let g = DiGraph::<i32, ()>::from_edges([(0, 1), (1, 2), (2, 0), (3, 2), (4, 5)]);
+        let mut x = Vec::<usize>::new();
+        g.get_successors_dense(&[3], &mut x);
+        assert_eq!(x, vec![0, 1, 2, 3]);
+        g.get_predecessors_dense(&[0, 5], &mut x);
+        assert_eq!(x, vec![0, 1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn test_subgraph() {
+        use crate::subgraph;
+        use petgraph::prelude::*;
+
+        let mut g = DiGraph::<char, i32>::new();
+        for c in ['a', 'b', 'c', 'd'].iter() {
+            g.add_node(*c);
+        }
+        for (i, &(v, w)) in [(0, 1), (1, 2), (1, 2), (2, 2), (2, 3), (3, 1), (0, 3)]
+            .iter()
+            .enumerate()
+        {
+            g.add_edge(NodeIndex::new(v), NodeIndex::new(w), i as i32);
+        }
+        let (h, vmap, emap) = subgraph(&g, &[2, 1, 3]);
+        assert_eq!(vmap, vec![2, 1, 3]);
+        assert_eq!(emap, vec![1, 2, 3, 4, 5]);
+        assert_eq!(h.node_count(), 3);
+        assert_eq!(h.edge_count(), 5);
+        for v in 0..h.node_count() {
+            assert_eq!(h[NodeIndex::new(v)], g[NodeIndex::new(vmap[v])]);
+        }
+        for e in h.edge_references() {
+            let f = EdgeIndex::new(emap[e.id().index()]);
+            assert_eq!(e.weight(), &g[f]);
+            let (v, w) = g.edge_endpoints(f).unwrap();
+            assert_eq!(vmap[e.source().index()], v.index());
+            assert_eq!(vmap[e.target().index()], w.index());
+        }
+        let (h, vmap, emap) = subgraph(&g, &[]);
+        assert_eq!((h.node_count(), vmap.len(), emap.len()), (0, 0, 0));
+    }
+
+    #[test]
+    fn test_condensation() {
+        use crate::GraphSimpleIdx;
+        use petgraph::prelude::*;
+
+        // Two cycles 0 -> 1 -> 2 -> 0 and 3 -> 4 -> 3, joined by two edges, with a tail
+        // 4 -> 5 and an isolated vertex 6.
+
+        let mut g = DiGraph::<i32, ()>::from_edges([
+            (0, 1),
+            (1, 2),
+            (2, 0),
+            (3, 4),
+            (4, 3),
+            (2, 3),
+            (0, 4),
+            (4, 5),
+        ]);
+        g.add_node(0);
+        assert!(!g.acyclic());
+        for &dedup in [false, true].iter() {
+            let (h, comp) = g.condensation(dedup);
+            assert!(h.acyclic());
+            assert_eq!(h.node_count(), 4);
+            assert_eq!(comp[0], comp[1]);
+            assert_eq!(comp[0], comp[2]);
+            assert_eq!(comp[3], comp[4]);
+            assert_eq!(h[NodeIndex::new(comp[0])], vec![0, 1, 2]);
+            assert_eq!(h[NodeIndex::new(comp[6])], vec![6]);
+            let mut edges = h
+                .edge_references()
+                .map(|e| e.weight().clone())
+                .collect::<Vec<_>>();
+            edges.sort();
+            if dedup {
+                assert_eq!(edges, vec![vec![5, 6], vec![7]]);
+            } else {
+                assert_eq!(edges, vec![vec![5], vec![6], vec![7]]);
+            }
+            for e in h.edge_references() {
+                for &f in e.weight().iter() {
+                    let f = EdgeIndex::new(f);
+                    let (v, w) = g.edge_endpoints(f).unwrap();
+                    assert_eq!(comp[v.index()], e.source().index());
+                    assert_eq!(comp[w.index()], e.target().index());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_cycles() {
+        use crate::GraphSimpleIdx;
+        use petgraph::prelude::*;
+
+        // Figure eight, with a tail and a self-loop.
+
+        let g = DiGraph::<i32, ()>::from_edges([(2, 0), (0, 2), (0, 1), (1, 0), (3, 0), (4, 4)]);
+        assert_eq!(g.cycles(10, 10), vec![vec![0, 1], vec![0, 2], vec![4]]);
+
+        // A cycle that must be rotated to start at its smallest vertex, and whose vertices
+        // are reachable by a shortcut.
+
+        let g = DiGraph::<i32, ()>::from_edges([(3, 1), (1, 2), (2, 3), (1, 3)]);
+        assert_eq!(g.cycles(10, 10), vec![vec![1, 2, 3], vec![1, 3]]);
+
+        // The complete digraph on four vertices, which has 6 cycles of length 2, 8 of
+        // length 3, and 6 of length 4.
+
+        let mut edges = Vec::<(u32, u32)>::new();
+        for v in 0..4 {
+            for w in 0..4 {
+                if v != w {
+                    edges.push((v, w));
+                }
+            }
+        }
+        let g = DiGraph::<i32, ()>::from_edges(&edges);
+        let cycles = g.cycles(100, 10);
+        assert_eq!(cycles.len(), 20);
+        for c in cycles.iter() {
+            assert_eq!(c[0], *c.iter().min().unwrap());
+        }
+        let mut sorted = cycles.clone();
+        sorted.sort();
+        sorted.dedup();
+        assert_eq!(sorted.len(), 20);
+        assert_eq!(g.cycles(100, 3).len(), 14);
+        assert_eq!(g.cycles(100, 2).len(), 6);
+        assert_eq!(g.cycles(5, 10).len(), 5);
+        assert_eq!(g.cycles(5, 10), cycles[0..5].to_vec());
+
+        // The complete digraph on twelve vertices has hundreds of millions of cycles,
+        // but the caps make this fast.
+
+        let mut edges = Vec::<(u32, u32)>::new();
+        for v in 0..12 {
+            for w in 0..12 {
+                if v != w {
+                    edges.push((v, w));
+                }
+            }
+        }
+        let g = DiGraph::<i32, ()>::from_edges(&edges);
+        assert_eq!(g.cycles(1000, 12).len(), 1000);
+        assert_eq!(g.cycles(1_000_000, 2).len(), 66);
+    }
+
+    #[test]
+    fn test_build_graph_from_edges() {
+        use crate::{build_graph_from_edges, GraphSimpleIdx};
+
+        let edges = vec![
+            (2, 0, 'a'),
+            (0, 1, 'b'),
+            (0, 1, 'c'),
+            (1, 1, 'd'),
+            (0, 1, 'e'),
+        ];
+        let g = build_graph_from_edges(&edges, None);
+        assert_eq!(g.node_count(), 3);
+        assert_eq!(g.edge_count(), 5);
+        for (i, (v, w, t)) in edges.iter().enumerate() {
+            assert_eq!(g.to_left(i), *v);
+            assert_eq!(g.to_right(i), *w);
+            assert_eq!(g.edge_obj(i), t);
+        }
+        let g = build_graph_from_edges(&edges, Some(5));
+        assert_eq!(g.node_count(), 5);
+        assert_eq!(g.edge_count(), 5);
+        assert!(g.source(4) && g.sink(4));
+        let g = build_graph_from_edges::<()>(&[], None);
+        assert_eq!(g.node_count(), 0);
+    }
+
+    #[test]
+    fn test_degree_summary() {
+        use crate::{build_graph_from_edges, GraphSimpleIdx, GraphSummary};
+
+        let edges = [(0, 1), (0, 1), (0, 1), (1, 2), (2, 2), (2, 0), (3, 1)];
+        let edges = edges.iter().map(|&(v, w)| (v, w, ())).collect::<Vec<_>>();
+        let g = build_graph_from_edges(&edges, Some(5));
+        let x = g.degree_summary();
+        assert_eq!(
+            x,
+            GraphSummary {
+                nodes: 5,
+                edges: 7,
+                sources: 2,
+                sinks: 1,
+                isolated: 1,
+                min_in: 0,
+                max_in: 4,
+                mean_in: 1.4,
+                min_out: 0,
+                max_out: 3,
+                mean_out: 1.4,
+                self_loops: 1,
+                parallel_pairs: 3,
+            }
+        );
+        let mut s = format!("{}", x);
+        s.retain(|c| c != ' ');
+        assert!(s.contains("│paralleledgepairs│3│"));
+        assert!(s.contains("│in-degreemin/max/mean│0/4/1.40│"));
+        let g = build_graph_from_edges::<()>(&[], None);
+        assert_eq!(g.degree_summary(), GraphSummary::default());
+    }
+
+    #[test]
+    fn test_count_paths() {
+        use crate::{build_graph_from_edges, GraphSimpleIdx};
+
+        // A ladder in which each vertex i has edges to i + 1 and i + 2, so that the number
+        // of paths from 0 to n is the Fibonacci number F(n+1).
+
+        let n = 100;
+        let mut edges = Vec::<(usize, usize, ())>::new();
+        for i in 0..n {
+            edges.push((i, i + 1, ()));
+            if i + 2 <= n {
+                edges.push((i, i + 2, ()));
+            }
+        }
+        let g = build_graph_from_edges(&edges, None);
+        let (mut a, mut b) = (1_u128, 1_u128);
+        for i in 1..=n {
+            assert_eq!(g.count_paths(0, i), Some(b));
+            let c = a + b;
+            a = b;
+            b = c;
+        }
+        assert_eq!(g.count_paths(0, 0), Some(1));
+        assert_eq!(g.count_paths(5, 2), Some(0));
+
+        // A chain of doubled edges, which overflows.
+
+        let mut edges = Vec::<(usize, usize, ())>::new();
+        for i in 0..130 {
+            edges.push((i, i + 1, ()));
+            edges.push((i, i + 1, ()));
+        }
+        let g = build_graph_from_edges(&edges, None);
+        assert_eq!(g.count_paths(0, 127), Some(1 << 127));
+        assert_eq!(g.count_paths(0, 128), Some(u128::MAX));
+        assert_eq!(g.count_paths(0, 130), Some(u128::MAX));
+
+        // A cyclic graph.
+
+        let g = build_graph_from_edges(&[(0, 1, ()), (1, 2, ()), (3, 4, ()), (4, 3, ())], None);
+        assert_eq!(g.count_paths(0, 2), None);
+    }
+
+    #[test]
+    fn test_components() {
+        use crate::GraphSimpleIdx;
+        use petgraph::prelude::*;
+        use rand::{rngs::StdRng, Rng, SeedableRng};
+
+        // The previous implementation of components, by depth-first search.
+
+        fn components_dfs(g: &DiGraph<i32, ()>) -> Vec<Vec<usize>> {
+            let mut comp = Vec::<Vec<usize>>::new();
+            let mut used = vec![false; g.node_count()];
+            for v in 0..g.node_count() {
+                if used[v] {
+                    continue;
+                }
+                let mut c = Vec::<usize>::new();
+                let mut cnext = vec![v];
+                while let Some(w) = cnext.pop() {
+                    if used[w] {
+                        continue;
+                    }
+                    used[w] = true;
+                    c.push(w);
+                    for j in 0..g.n_from(w) {
+                        cnext.push(g.v_from(w, j));
+                    }
+                    for j in 0..g.n_to(w) {
+                        cnext.push(g.v_to(w, j));
+                    }
+                }
+                c.sort_unstable();
+                comp.push(c);
+            }
+            comp
+        }
+
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..100 {
+            let n = 1 + rng.gen::<u32>() % 50;
+            let m = rng.gen::<u32>() % 50;
+            let mut g = DiGraph::<i32, ()>::new();
+            for _ in 0..n {
+                g.add_node(0);
+            }
+            for _ in 0..m {
+                let (v, w) = (rng.gen::<u32>() % n, rng.gen::<u32>() % n);
+                g.add_edge(NodeIndex::new(v as usize), NodeIndex::new(w as usize), ());
+            }
+            let mut comp = Vec::<Vec<usize>>::new();
+            g.components(&mut comp);
+            let old = components_dfs(&g);
+            assert_eq!(comp, old);
+            g.components_e(&mut comp);
+            for (c, d) in comp.iter().zip(old.iter()) {
+                let mut e = Vec::<usize>::new();
+                for &v in d.iter() {
+                    for j in 0..g.n_from(v) {
+                        e.push(g.e_from(v, j));
+                    }
+                }
+                e.sort_unstable();
+                assert_eq!(*c, e);
+            }
+        }
+    }
+
+    #[test]
+    fn test_components_e_pos_sorted() {
+        use crate::GraphSimpleIdx;
+        use petgraph::prelude::*;
+
+        // A component with a branch 1 -> {2, 3} -> 4 and a cycle 4 -> 5 -> 4, whose edges
+        // are numbered so as to disagree with the order of the graph, and a second
+        // component.
+
+        let g = DiGraph::<i32, ()>::from_edges([
+            (4, 5),
+            (3, 4),
+            (5, 4),
+            (1, 3),
+            (5, 6),
+            (2, 4),
+            (0, 1),
+            (1, 2),
+            (8, 7),
+        ]);
+        let mut comp = Vec::<Vec<usize>>::new();
+        g.components_e_pos_sorted(&mut comp);
+        assert_eq!(comp, vec![vec![6, 7, 3, 5, 1, 0, 2, 4], vec![8]]);
+        for c in comp.iter() {
+            for i in 0..c.len() {
+                for j in i + 1..c.len() {
+                    let (v, w) = (g.to_right(c[j]), g.to_left(c[i]));
+                    assert!(!g.have_path(v, w) || g.have_path(w, v));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_acyclic() {
+        use crate::GraphSimpleIdx;
+        use petgraph::prelude::*;
+        use rand::{rngs::StdRng, Rng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut cyclic = 0;
+        for _ in 0..500 {
+            let n = 1 + rng.gen::<u32>() % 20;
+            let m = rng.gen::<u32>() % 25;
+            let mut g = DiGraph::<i32, ()>::new();
+            for _ in 0..n {
+                g.add_node(0);
+            }
+            for _ in 0..m {
+                let (v, w) = (rng.gen::<u32>() % n, rng.gen::<u32>() % n);
+                g.add_edge(NodeIndex::new(v as usize), NodeIndex::new(w as usize), ());
+            }
+            assert_eq!(g.acyclic(), g.cyclic_core().is_empty());
+            if !g.acyclic() {
+                cyclic += 1;
+            }
+        }
+        assert!(cyclic > 50 && cyclic < 450);
+        let g = DiGraph::<i32, ()>::from_edges([(0, 0)]);
+        assert!(!g.acyclic());
+        let g = DiGraph::<i32, ()>::new();
+        assert!(g.acyclic());
+    }
+
+    #[test]
+    fn test_edges_between() {
+        use crate::GraphSimpleIdx;
+        use petgraph::prelude::*;
+
+        let g = DiGraph::<i32, ()>::from_edges([(0, 1), (1, 0), (0, 1), (2, 2), (0, 2), (0, 1)]);
+        assert_eq!(g.edges_between(0, 1), vec![0, 2, 5]);
+        assert_eq!(g.edges_between(1, 0), vec![1]);
+        assert_eq!(g.edges_between(2, 2), vec![3]);
+        assert_eq!(g.edges_between(2, 0), Vec::<usize>::new());
+        assert!(g.has_edge(0, 1));
+        assert!(g.has_edge(2, 2));
+        assert!(!g.has_edge(1, 2));
+        assert!(!g.has_edge(1, 1));
+    }
+
+    #[test]
+    fn test_neighborhood() {
+        use crate::{Dir, GraphSimpleIdx};
+        use petgraph::prelude::*;
+
+        // A tree with root 0, children 1 and 2, and grandchildren 3 and 4 under 1, and
+        // 5 under 2, and great-grandchild 6 under 3.
+
+        let g = DiGraph::<i32, ()>::from_edges([(0, 1), (0, 2), (1, 3), (1, 4), (2, 5), (3, 6)]);
+        assert_eq!(g.neighborhood(0, 0, Dir::Outgoing), vec![0]);
+        assert_eq!(g.neighborhood(0, 1, Dir::Outgoing), vec![0, 1, 2]);
+        assert_eq!(g.neighborhood(0, 2, Dir::Outgoing), vec![0, 1, 2, 3, 4, 5]);
+        assert_eq!(
+            g.neighborhood(0, 100, Dir::Outgoing),
+            vec![0, 1, 2, 3, 4, 5, 6]
+        );
+        assert_eq!(g.neighborhood(3, 0, Dir::Incoming), vec![3]);
+        assert_eq!(g.neighborhood(3, 1, Dir::Incoming), vec![1, 3]);
+        assert_eq!(g.neighborhood(3, 2, Dir::Incoming), vec![0, 1, 3]);
+        assert_eq!(g.neighborhood(1, 0, Dir::Both), vec![1]);
+        assert_eq!(g.neighborhood(1, 1, Dir::Both), vec![0, 1, 3, 4]);
+        assert_eq!(g.neighborhood(1, 2, Dir::Both), vec![0, 1, 2, 3, 4, 6]);
+    }
+
+    #[test]
+    fn test_dominators() {
+        use crate::GraphSimpleIdx;
+        use petgraph::prelude::*;
+
+        // A loop containing an if-then-else.
+
+        let g = DiGraph::<i32, ()>::from_edges([
+            (0, 1),
+            (1, 2),
+            (1, 3),
+            (2, 4),
+            (3, 4),
+            (4, 1),
+            (4, 5),
+        ]);
+        assert_eq!(
+            g.dominators(0),
+            vec![None, Some(0), Some(1), Some(1), Some(1), Some(4)]
+        );
+        assert_eq!(
+            g.dominators(2),
+            vec![None, Some(4), None, Some(1), Some(2), Some(4)]
+        );
+
+        // The irreducible graph of figure 4 in Cooper, Harvey and Kennedy, with root 6, and
+        // an unreachable vertex 0.
+
+        let g = DiGraph::<i32, ()>::from_edges([
+            (6, 5),
+            (6, 4),
+            (5, 1),
+            (4, 2),
+            (4, 3),
+            (1, 2),
+            (2, 1),
+            (3, 2),
+            (2, 3),
+        ]);
+        assert_eq!(
+            g.dominators(6),
+            vec![None, Some(6), Some(6), Some(6), Some(6), Some(6), None]
+        );
+
+        // The graph of figure 1 in Lengauer and Tarjan, "A Fast Algorithm for Finding
+        // Dominators in a Flowgraph" (1979), with vertices R, A, B, ..., L numbered 0, 1, ...
+
+        let (r, a, b, c, d, e, f, g, h, i, j, k, l) = (0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12);
+        let lt = DiGraph::<i32, ()>::from_edges([
+            (r, a),
+            (r, b),
+            (r, c),
+            (a, d),
+            (b, a),
+            (b, d),
+            (b, e),
+            (c, f),
+            (c, g),
+            (d, l),
+            (e, h),
+            (f, i),
+            (g, i),
+            (g, j),
+            (h, e),
+            (h, k),
+            (i, k),
+            (j, i),
+            (k, i),
+            (k, r),
+            (l, h),
+        ]);
+        let dom = lt.dominators(r as usize);
+        assert_eq!(dom[0], None);
+        assert_eq!(
+            dom[1..],
+            [r, r, r, r, r, c, c, r, r, g, r, d].map(|x| Some(x as usize))
+        );
+    }
+
+    #[test]
+    fn test_unary_chains() {
+        use crate::GraphSimpleIdx;
+        use petgraph::prelude::*;
+
+        // A bubble 0 -> 1 -> 2 -> 4, 0 -> 3 -> 4, followed by a chain 4 -> 5 -> 6, a
+        // separate simple cycle 7 -> 8 -> 9 -> 7, and a self-loop at 10.
+
+        let g = DiGraph::<i32, ()>::from_edges([
+            (5, 6),
+            (0, 1),
+            (1, 2),
+            (2, 4),
+            (0, 3),
+            (3, 4),
+            (4, 5),
+            (8, 9),
+            (9, 7),
+            (7, 8),
+            (10, 10),
+        ]);
+        let chains = g.unary_chains();
+        assert_eq!(
+            chains,
+            vec![
+                vec![1, 2, 3],
+                vec![4, 5],
+                vec![6, 0],
+                vec![7, 8, 9],
+                vec![10]
+            ]
+        );
+        let h = g.collapse_chains();
+        assert_eq!(h.node_count(), 5);
+        let verts = h.node_indices().map(|v| h[v]).collect::<Vec<usize>>();
+        assert_eq!(verts, vec![0, 4, 6, 8, 10]);
+        assert_eq!(h.edge_count(), 5);
+        for (i, e) in h.edge_references().enumerate() {
+            assert_eq!(*e.weight(), chains[i]);
+            assert_eq!(h[e.source()], g.to_left(chains[i][0]));
+            assert_eq!(h[e.target()], g.to_right(*chains[i].last().unwrap()));
+        }
+        assert!(h.unary_chains().iter().all(|c| c.len() == 1));
+    }
+
+    #[test]
+    fn test_cut_vertices_and_bridges() {
+        use crate::GraphSimpleIdx;
+        use petgraph::prelude::*;
+
+        // A barbell: two triangles joined by an edge.
+
+        let g = DiGraph::<i32, ()>::from_edges([
+            (0, 1),
+            (1, 2),
+            (2, 0),
+            (4, 3),
+            (5, 4),
+            (3, 5),
+            (2, 3),
+        ]);
+        assert_eq!(g.cut_vertices(), vec![2, 3]);
+        assert_eq!(g.bridges(), vec![6]);
+
+        // A cycle.
+
+        let g = DiGraph::<i32, ()>::from_edges([(0, 1), (1, 2), (2, 3), (3, 0)]);
+        assert_eq!(g.cut_vertices(), Vec::<usize>::new());
+        assert_eq!(g.bridges(), Vec::<usize>::new());
+
+        // A path 0 - 1 - 2 - 3, in which 0 and 1 are joined by two edges in opposite
+        // directions, with a self-loop at 3, and an isolated vertex 4.
+
+        let mut g = DiGraph::<i32, ()>::from_edges([(0, 1), (1, 0), (1, 2), (3, 2), (3, 3)]);
+        g.add_node(0);
+        assert_eq!(g.cut_vertices(), vec![1, 2]);
+        assert_eq!(g.bridges(), vec![2, 3]);
+    }
+
+    #[test]
+    fn test_self_loops_and_parallel_edges() {
+        use crate::GraphSimpleIdx;
+        use petgraph::prelude::*;
+
+        // Parallel edges 0 -> 1 into a cycle 1 -> 2 -> 1, followed by a vertex 3 with a
+        // self-loop.  Then a two-cycle 4 -> 5 -> 4 in which one edge is doubled, and doubled
+        // edges 6 -> 7 into a sink.
+
+        let g = DiGraph::<i32, ()>::from_edges([
+            (0, 1),
+            (0, 1),
+            (1, 2),
+            (2, 1),
+            (2, 3),
+            (3, 3),
+            (4, 5),
+            (5, 4),
+            (4, 5),
+            (6, 7),
+            (6, 7),
+        ]);
+        assert_eq!(g.self_loops(), vec![5]);
+        assert_eq!(
+            g.parallel_edge_groups(),
+            vec![vec![0, 1], vec![6, 8], vec![9, 10]]
+        );
+        assert_eq!(g.cyclic_core(), vec![1, 2, 3, 4, 5]);
+        assert_eq!(g.cyclic_core_edges(), vec![2, 3, 5, 6, 7, 8]);
+        assert!(!g.acyclic());
+        let g = DiGraph::<i32, ()>::from_edges([(0, 1), (1, 2), (0, 1), (1, 2)]);
+        assert_eq!(g.self_loops(), Vec::<usize>::new());
+        assert_eq!(g.cyclic_core(), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn test_component_ids() {
+        use crate::GraphSimpleIdx;
+        use petgraph::prelude::*;
+        // Components {0, 3, 5}, {1}, {2, 4}, {6}, {7}, with 1, 6 and 7 isolated.
+        let mut g = DiGraph::<(), ()>::from_edges([(5, 3), (3, 0), (4, 2), (0, 5)]);
+        g.add_node(());
+        g.add_node(());
+        let mut comp = Vec::<Vec<usize>>::new();
+        g.components(&mut comp);
+        let ids = g.component_ids();
+        assert_eq!(g.n_components(), 5);
+        assert_eq!(comp.len(), 5);
+        assert_eq!(ids, vec![0, 1, 2, 0, 2, 0, 3, 4]);
+        for (i, c) in comp.iter().enumerate() {
+            for &v in c.iter() {
+                assert_eq!(ids[v], i);
+            }
+        }
+        let empty = DiGraph::<(), ()>::new();
+        assert_eq!(empty.n_components(), 0);
+        assert!(empty.component_ids().is_empty());
+    }
+
+    #[test]
+    fn test_feedback_edges() {
+        use crate::GraphSimpleIdx;
+        use petgraph::prelude::*;
+        use rand::{rngs::StdRng, Rng, SeedableRng};
+
+        fn without(g: &DiGraph<(), ()>, dels: &[usize]) -> DiGraph<(), ()> {
+            g.filter_map(
+                |_, _| Some(()),
+                |e, _| {
+                    if dels.contains(&e.index()) {
+                        None
+                    } else {
+                        Some(())
+                    }
+                },
+            )
+        }
+
+        let g = DiGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 3), (3, 4), (4, 0)]);
+        assert_eq!(g.feedback_edges().len(), 1);
+        let g = DiGraph::<(), ()>::from_edges([(0, 1), (1, 2), (0, 2)]);
+        assert!(g.feedback_edges().is_empty());
+        let g = DiGraph::<(), ()>::from_edges([(0, 0), (0, 1), (1, 1)]);
+        assert_eq!(g.feedback_edges(), vec![0, 2]);
+
+        // On random graphs, check that removing the edges yields an acyclic graph, and that
+        // restoring any one of them does not.
+
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..200 {
+            let n = 1 + rng.gen::<u32>() % 12;
+            let m = rng.gen::<u32>() % 30;
+            let mut edges = Vec::<(u32, u32)>::new();
+            for _ in 0..m {
+                edges.push((rng.gen::<u32>() % n, rng.gen::<u32>() % n));
+            }
+            let mut g = DiGraph::<(), ()>::from_edges(&edges);
+            while g.node_count() < n as usize {
+                g.add_node(());
+            }
+            let fb = g.feedback_edges();
+            assert!(without(&g, &fb).acyclic());
+            for i in 0..fb.len() {
+                let mut fb2 = fb.clone();
+                fb2.remove(i);
+                assert!(!without(&g, &fb2).acyclic());
+            }
+        }
+    }
+
+    #[test]
+    fn test_cyclic_core_edges() {
+        use crate::GraphSimpleIdx;
+        use petgraph::prelude::*;
+        // Cycles 0 -> 1 -> 2 -> 0 and 3 -> 4 -> 3, joined by the bridge 2 -> 3, which lies
+        // on no cycle, although both of its ends lie in the cyclic core.
+        let g = DiGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 0), (2, 3), (3, 4), (4, 3)]);
+        assert_eq!(g.cyclic_core(), vec![0, 1, 2, 3, 4]);
+        assert_eq!(g.cyclic_core_edges(), vec![0, 1, 2, 4, 5]);
+        assert_eq!(g.cyclic_core_edges_loose(), vec![0, 1, 2, 3, 4, 5]);
+        let g = DiGraph::<(), ()>::from_edges([(0, 1), (1, 2), (1, 1)]);
+        assert_eq!(g.cyclic_core_edges(), vec![2]);
+    }
+
+    #[test]
+    fn test_depths_from_sources() {
+        use crate::GraphSimpleIdx;
+        use petgraph::prelude::*;
+        // A DAG with sources 0 and 5, in which 3 is reached from 0 in three steps and from
+        // 5 in one step.
+        let g = DiGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 3), (5, 3), (3, 4), (0, 4)]);
+        assert_eq!(
+            g.depths_from_sources(),
+            vec![Some(0), Some(1), Some(2), Some(1), Some(1), Some(0)]
+        );
+        assert_eq!(
+            g.depths_from_roots(&[1]),
+            vec![None, Some(0), Some(1), Some(2), Some(3), None]
+        );
+        // A cycle 1 -> 2 -> 3 -> 1 fed by the source 0, and a cycle 4 -> 5 -> 4 having no
+        // source upstream of it.
+        let g = DiGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 3), (3, 1), (4, 5), (5, 4)]);
+        assert_eq!(
+            g.depths_from_sources(),
+            vec![Some(0), Some(1), Some(2), Some(3), None, None]
+        );
+        assert_eq!(
+            g.depths_from_roots(&[3, 5]),
+            vec![None, Some(1), Some(2), Some(0), Some(1), Some(0)]
+        );
+    }
+}
@@ -3,144 +3,124 @@
 
 // Define generic digraph functions.
 //
-// Some of the functions here are unnecessarily quadratic in the vertex degree for
-// petgraph as a result of calling v_from and related functions below.  The
-// quadratic behavior might be avoided but might make the code a bit less readable.
-//
-// These functions seem unnecessarily specialized to u32.
+// The functions are defined by the trait GraphSimpleIdx, which uses usize for all vertex and
+// edge ids.  The older trait GraphSimple, which variously uses i32, u32 and usize, is
+// deprecated, and its methods delegate to those of GraphSimpleIdx, converting ids.  Note
+// that because the two traits have methods with the same names, only one should be
+// imported at a time.
 
-use petgraph::{prelude::*, EdgeType};
-use std::collections::HashSet;
-use vector_utils::{bin_member, meet};
+mod idx;
 
-pub trait GraphSimple<T> {
-    // =============================================================================
-    // Return the object associated to an edge id.
-    // =============================================================================
+pub use idx::{
+    build_graph_from_edges, subgraph, Dir, GraphSimpleIdx, GraphSummary, ReachabilityIndex,
+};
 
-    fn edge_obj(&self, e: u32) -> &T;
+use petgraph::{prelude::*, EdgeType};
 
-    // =============================================================================
-    // Return the source or target of an edge.
-    // =============================================================================
+// The deprecated trait.  Each method is the same as the method of the same name in
+// GraphSimpleIdx, except for the types of vertex and edge ids.
 
+pub trait GraphSimple<T> {
+    #[deprecated(note = "use GraphSimpleIdx::edge_obj")]
+    fn edge_obj(&self, e: u32) -> &T;
+    #[deprecated(note = "use GraphSimpleIdx::to_left")]
     fn to_left(&self, e: u32) -> u32;
+    #[deprecated(note = "use GraphSimpleIdx::to_right")]
     fn to_right(&self, e: u32) -> u32;
-
-    // =============================================================================
-    // Return the number of edges exiting or entering a given vertex.
-    // =============================================================================
-
+    #[deprecated(note = "use GraphSimpleIdx::n_from")]
     fn n_from(&self, v: usize) -> usize;
+    #[deprecated(note = "use GraphSimpleIdx::n_to")]
     fn n_to(&self, v: usize) -> usize;
-
-    // =============================================================================
-    // Return id of the nth vertex exiting or entering a given vertex id.
-    // Note that this is O(n).
-    // =============================================================================
-
+    #[deprecated(note = "use GraphSimpleIdx::v_from")]
     fn v_from(&self, v: usize, n: usize) -> usize;
+    #[deprecated(note = "use GraphSimpleIdx::v_to")]
     fn v_to(&self, v: usize, n: usize) -> usize;
-
-    // =============================================================================
-    // Return id of the nth edge exiting or entering a given vertex id.
-    // Note that this is O(n).
-    // =============================================================================
-
+    #[deprecated(note = "use GraphSimpleIdx::e_from")]
     fn e_from(&self, v: usize, n: usize) -> usize;
+    #[deprecated(note = "use GraphSimpleIdx::e_to")]
     fn e_to(&self, v: usize, n: usize) -> usize;
-
-    // =============================================================================
-    // Return the nth edge exiting or entering a given vertex id.
-    // Note that this is O(n).
-    // =============================================================================
-
+    #[deprecated(note = "use GraphSimpleIdx::o_from")]
     fn o_from(&self, v: usize, n: usize) -> &T;
+    #[deprecated(note = "use GraphSimpleIdx::o_to")]
     fn o_to(&self, v: usize, n: usize) -> &T;
-
-    // =============================================================================
-    // source: return if a vertex is a source
-    // sink: return if a vertex is a sink
-    // =============================================================================
-
+    #[deprecated(note = "use GraphSimpleIdx::edges_from")]
+    fn edges_from(&self, v: usize) -> impl Iterator<Item = (u32, u32)> + '_;
+    #[deprecated(note = "use GraphSimpleIdx::edges_to")]
+    fn edges_to(&self, v: usize) -> impl Iterator<Item = (u32, u32)> + '_;
+    #[deprecated(note = "use GraphSimpleIdx::source")]
     fn source(&self, v: i32) -> bool;
+    #[deprecated(note = "use GraphSimpleIdx::sink")]
     fn sink(&self, v: i32) -> bool;
-
-    // =============================================================================
-    // sources: return the ordered list of source vertices
-    // sinks: return the ordered list of sink vertices
-    // =============================================================================
-
+    #[deprecated(note = "use GraphSimpleIdx::sources")]
     fn sources(&self) -> Vec<i32>;
+    #[deprecated(note = "use GraphSimpleIdx::sinks")]
     fn sinks(&self) -> Vec<i32>;
-
-    // =============================================================================
-    // cyclic_core: return the ordered list of vertices that define a subgraph having no
-    // sources and sinks, and which is empty iff the graph is acyclic.
-    // The (vertex) cyclic core is the union of all vertices that appear in cycles.
-    // =============================================================================
-
+    #[deprecated(note = "use GraphSimpleIdx::cyclic_core")]
     fn cyclic_core(&self) -> Vec<i32>;
-
-    // =============================================================================
-    // cyclic_core_edges: return the ordered list of edges that define a subgraph having no
-    // sources and sinks, and which is empty iff the graph is acyclic.
-    // The (edge) cyclic core is the union of all edges that appear in cycles.
-    // =============================================================================
-
+    #[deprecated(note = "use GraphSimpleIdx::cyclic_core_edges")]
     fn cyclic_core_edges(&self) -> Vec<u32>;
-
-    // =============================================================================
-    // acyclic: return true if graph is acyclic
-    // =============================================================================
-
+    #[deprecated(note = "use GraphSimpleIdx::acyclic")]
     fn acyclic(&self) -> bool;
-
-    // =============================================================================
-    // get_predecessors: find all vertices which have a directed path to a vertex
-    // in v.  This includes the vertices in v by definition.  Return a sorted list
-    // x.  get_successors: go the other way.
-    // get_predecessors1 and get_successors1: start from one vertex
-    // =============================================================================
-
+    #[deprecated(note = "use GraphSimpleIdx::get_predecessors")]
     fn get_predecessors(&self, v: &[i32], x: &mut Vec<u32>);
+    #[deprecated(note = "use GraphSimpleIdx::get_predecessors1")]
     fn get_predecessors1(&self, v: i32, x: &mut Vec<u32>);
+    #[deprecated(note = "use GraphSimpleIdx::get_successors")]
     fn get_successors(&self, v: &[i32], x: &mut Vec<u32>);
+    #[deprecated(note = "use GraphSimpleIdx::get_successors1")]
     fn get_successors1(&self, v: i32, x: &mut Vec<u32>);
-
-    // =============================================================================
-    // Determine if there is a path from one vertex to another, allowing for the
-    // case of a zero length path, where the vertices are equal.
-    // =============================================================================
-
+    #[deprecated(note = "use GraphSimpleIdx::get_predecessors_dense")]
+    fn get_predecessors_dense(&self, v: &[i32], x: &mut Vec<u32>);
+    #[deprecated(note = "use GraphSimpleIdx::get_successors_dense")]
+    fn get_successors_dense(&self, v: &[i32], x: &mut Vec<u32>);
+    #[deprecated(note = "use GraphSimpleIdx::have_path")]
     fn have_path(&self, v: i32, w: i32) -> bool;
-
-    // =============================================================================
-    // Find the connected components.  Each component is a sorted list of vertices.
-    // =============================================================================
-
+    #[deprecated(note = "use GraphSimpleIdx::components")]
     fn components(&self, comp: &mut Vec<Vec<u32>>);
-
-    // =============================================================================
-    // Find the connected components as lists of edges.  Each component is a
-    // sorted list of edges.
-    // =============================================================================
-
+    #[deprecated(note = "use GraphSimpleIdx::components_e")]
     fn components_e(&self, comp: &mut Vec<Vec<u32>>);
-
-    // =============================================================================
-    // Find the connected components as lists of edges, sorted within each component
-    // to try to follow the order of the graph.  This is slow and suboptimal.
-    // =============================================================================
-
+    #[deprecated(note = "use GraphSimpleIdx::components_e_pos_sorted")]
     fn components_e_pos_sorted(&self, comp: &mut Vec<Vec<u32>>);
-
-    // =============================================================================
-    // Determine if two edge paths through the graph could like in a common
-    // superpath.
-    // =============================================================================
-
+    #[deprecated(note = "use GraphSimpleIdx::copathic")]
     fn copathic(&self, p1: &Vec<u32>, p2: &Vec<u32>) -> bool;
+    #[deprecated(note = "use GraphSimpleIdx::shortest_path")]
+    fn shortest_path(&self, v: i32, w: i32) -> Option<Vec<u32>>;
+    #[deprecated(note = "use GraphSimpleIdx::shortest_path_edges")]
+    fn shortest_path_edges(&self, v: i32, w: i32) -> Option<Vec<u32>>;
+    #[deprecated(note = "use GraphSimpleIdx::all_simple_paths")]
+    fn all_simple_paths(&self, v: i32, w: i32, max_paths: usize, max_len: usize) -> Vec<Vec<u32>>;
+    #[deprecated(note = "use GraphSimpleIdx::strongly_connected_components")]
+    fn strongly_connected_components(&self) -> Vec<Vec<u32>>;
+    #[deprecated(note = "use GraphSimpleIdx::reachability")]
+    fn reachability(&self) -> ReachabilityIndex;
+    #[deprecated(note = "use GraphSimpleIdx::condensation")]
+    fn condensation(&self, dedup: bool) -> (Graph<Vec<u32>, Vec<u32>, Directed>, Vec<u32>);
+    #[deprecated(note = "use GraphSimpleIdx::cycles")]
+    fn cycles(&self, max_cycles: usize, max_len: usize) -> Vec<Vec<u32>>;
+    #[deprecated(note = "use GraphSimpleIdx::degree_summary")]
+    fn degree_summary(&self) -> GraphSummary;
+    #[deprecated(note = "use GraphSimpleIdx::count_paths")]
+    fn count_paths(&self, v: i32, w: i32) -> Option<u128>;
+    #[deprecated(note = "use GraphSimpleIdx::edges_between")]
+    fn edges_between(&self, v: usize, w: usize) -> Vec<u32>;
+    #[deprecated(note = "use GraphSimpleIdx::has_edge")]
+    fn has_edge(&self, v: usize, w: usize) -> bool;
+    #[deprecated(note = "use GraphSimpleIdx::neighborhood")]
+    fn neighborhood(&self, v: i32, radius: usize, direction: Dir) -> Vec<u32>;
+    #[deprecated(note = "use GraphSimpleIdx::dominators")]
+    fn dominators(&self, root: i32) -> Vec<i32>;
+    #[deprecated(note = "use GraphSimpleIdx::unary_chains")]
+    fn unary_chains(&self) -> Vec<Vec<u32>>;
+    #[deprecated(note = "use GraphSimpleIdx::collapse_chains")]
+    fn collapse_chains(&self) -> Graph<u32, Vec<u32>, Directed>;
+    #[deprecated(note = "use GraphSimpleIdx::cut_vertices")]
+    fn cut_vertices(&self) -> Vec<u32>;
+    #[deprecated(note = "use GraphSimpleIdx::bridges")]
+    fn bridges(&self) -> Vec<u32>;
+    #[deprecated(note = "use GraphSimpleIdx::self_loops")]
+    fn self_loops(&self) -> Vec<u32>;
+    #[deprecated(note = "use GraphSimpleIdx::parallel_edge_groups")]
+    fn parallel_edge_groups(&self) -> Vec<Vec<u32>>;
 }
 
 impl<S, T, U, V> GraphSimple<T> for Graph<S, T, U, V>
@@ -149,351 +129,255 @@ where
     V: petgraph::csr::IndexType,
 {
     fn edge_obj(&self, e: u32) -> &T {
-        &self[EdgeIndex::<V>::new(e as usize)]
+        GraphSimpleIdx::edge_obj(self, e as usize)
     }
 
     fn to_left(&self, e: u32) -> u32 {
-        self.edge_endpoints(EdgeIndex::<V>::new(e as usize))
-            .unwrap()
-            .0
-            .index() as u32
+        GraphSimpleIdx::to_left(self, e as usize) as u32
     }
 
     fn to_right(&self, e: u32) -> u32 {
-        self.edge_endpoints(EdgeIndex::<V>::new(e as usize))
-            .unwrap()
-            .1
-            .index() as u32
+        GraphSimpleIdx::to_right(self, e as usize) as u32
     }
 
     fn n_from(&self, v: usize) -> usize {
-        self.neighbors(NodeIndex::<V>::new(v)).count()
+        GraphSimpleIdx::n_from(self, v)
     }
 
     fn n_to(&self, v: usize) -> usize {
-        self.neighbors_directed(NodeIndex::<V>::new(v), Incoming)
-            .count()
+        GraphSimpleIdx::n_to(self, v)
     }
 
     fn v_from(&self, v: usize, n: usize) -> usize {
-        self.edges_directed(NodeIndex::<V>::new(v), Outgoing)
-            .nth(n)
-            .unwrap()
-            .target()
-            .index()
+        GraphSimpleIdx::v_from(self, v, n)
     }
 
     fn v_to(&self, v: usize, n: usize) -> usize {
-        self.edges_directed(NodeIndex::<V>::new(v), Incoming)
-            .nth(n)
-            .unwrap()
-            .source()
-            .index()
+        GraphSimpleIdx::v_to(self, v, n)
     }
 
     fn e_from(&self, v: usize, n: usize) -> usize {
-        let mut e: EdgeIndex<V> = self.first_edge(NodeIndex::<V>::new(v), Outgoing).unwrap();
-        for _j in 0..n {
-            let f = self.next_edge(e, Outgoing).unwrap();
-            e = f;
-        }
-        e.index()
+        GraphSimpleIdx::e_from(self, v, n)
     }
 
     fn e_to(&self, v: usize, n: usize) -> usize {
-        let mut e: EdgeIndex<V> = self.first_edge(NodeIndex::<V>::new(v), Incoming).unwrap();
-        for _j in 0..n {
-            let f = self.next_edge(e, Incoming).unwrap();
-            e = f;
-        }
-        e.index()
+        GraphSimpleIdx::e_to(self, v, n)
     }
 
     fn o_from(&self, v: usize, n: usize) -> &T {
-        self.edge_obj(self.e_from(v, n) as u32)
+        GraphSimpleIdx::o_from(self, v, n)
     }
 
     fn o_to(&self, v: usize, n: usize) -> &T {
-        self.edge_obj(self.e_to(v, n) as u32)
+        GraphSimpleIdx::o_to(self, v, n)
+    }
+
+    fn edges_from(&self, v: usize) -> impl Iterator<Item = (u32, u32)> + '_ {
+        GraphSimpleIdx::edges_from(self, v).map(|(e, w)| (e as u32, w as u32))
+    }
+
+    fn edges_to(&self, v: usize) -> impl Iterator<Item = (u32, u32)> + '_ {
+        GraphSimpleIdx::edges_to(self, v).map(|(e, w)| (e as u32, w as u32))
     }
 
     fn source(&self, v: i32) -> bool {
-        self.n_to(v as usize) == 0
+        GraphSimpleIdx::source(self, v as usize)
     }
 
     fn sink(&self, v: i32) -> bool {
-        self.n_from(v as usize) == 0
+        GraphSimpleIdx::sink(self, v as usize)
     }
 
     fn sources(&self) -> Vec<i32> {
-        let mut s = Vec::<i32>::new();
-        for v in 0..self.node_count() as i32 {
-            if self.source(v) {
-                s.push(v);
-            }
-        }
-        s
+        to_i32(GraphSimpleIdx::sources(self))
     }
 
     fn sinks(&self) -> Vec<i32> {
-        let mut s = Vec::<i32>::new();
-        for v in 0..self.node_count() as i32 {
-            if self.sink(v) {
-                s.push(v);
-            }
-        }
-        s
+        to_i32(GraphSimpleIdx::sinks(self))
     }
 
-    // cyclic_core successively deletes vertices and edges from the graph, without actually
-    // deleting them, but tracking instead the number of edges entering and exiting each vertex.
-
     fn cyclic_core(&self) -> Vec<i32> {
-        let n = self.node_count();
-        let (mut sources, mut sinks) = (self.sources(), self.sinks());
-        let (mut ins, mut outs) = (vec![0; n], vec![0; n]);
-        for v in 0..n {
-            ins[v] = self.n_to(v);
-            outs[v] = self.n_from(v);
-        }
-        let mut i = 0;
-        while i < sources.len() {
-            let v = sources[i] as usize;
-            outs[v] = 0;
-            for j in 0..self.n_from(v) {
-                let w = self.v_from(v, j);
-                ins[w] -= 1;
-                if ins[w] == 0 {
-                    sources.push(w as i32);
-                }
-            }
-            i += 1;
-        }
-        let mut i = 0;
-        while i < sinks.len() {
-            let v = sinks[i] as usize;
-            if ins[v] == 0 {
-                i += 1;
-                continue;
-            }
-            for j in 0..self.n_to(v) {
-                let w = self.v_to(v, j);
-                if ins[w] == 0 {
-                    continue;
-                }
-                outs[w] -= 1;
-                if outs[w] == 0 {
-                    sinks.push(w as i32);
-                }
-            }
-            i += 1;
-        }
-        let mut core = Vec::<i32>::new();
-        for v in 0..n {
-            if ins[v] > 0 && outs[v] > 0 {
-                core.push(v as i32);
-            }
-        }
-        core
+        to_i32(GraphSimpleIdx::cyclic_core(self))
     }
 
     fn cyclic_core_edges(&self) -> Vec<u32> {
-        let vert_core = self.cyclic_core();
-        let mut edge_core = Vec::<u32>::new();
-        for v in vert_core.iter() {
-            for i in 0..self.n_from(*v as usize) {
-                let e = self.e_from(*v as usize, i);
-                let w = self.to_right(e as u32);
-                if bin_member(&vert_core, &(w as i32)) {
-                    edge_core.push(e as u32);
-                }
-            }
-        }
-        edge_core.sort();
-        edge_core
+        to_u32(GraphSimpleIdx::cyclic_core_edges(self))
     }
 
     fn acyclic(&self) -> bool {
-        self.cyclic_core().is_empty()
+        GraphSimpleIdx::acyclic(self)
     }
 
     fn get_predecessors(&self, v: &[i32], x: &mut Vec<u32>) {
-        let mut check: Vec<u32> = Vec::new();
-        let mut tov: HashSet<u32> = HashSet::new();
-        for j in 0..v.len() {
-            let s: u32 = v[j] as u32;
-            check.push(s);
-            tov.insert(s);
-        }
-        while !check.is_empty() {
-            let x = check.pop().unwrap();
-            let n = self.n_to(x as usize);
-            for i in 0..n {
-                let y = self.v_to(x as usize, i);
-                if tov.contains(&(y as u32)) {
-                    continue;
-                }
-                check.push(y as u32);
-                tov.insert(y as u32);
-            }
-        }
-        x.clear();
-        for v in tov {
-            x.push(v);
-        }
-        x.sort_unstable();
+        let mut y = Vec::new();
+        GraphSimpleIdx::get_predecessors(self, &to_usize(v), &mut y);
+        *x = to_u32(y);
     }
 
     fn get_predecessors1(&self, v: i32, x: &mut Vec<u32>) {
-        let vs = vec![v];
-        self.get_predecessors(&vs, x);
+        let mut y = Vec::new();
+        GraphSimpleIdx::get_predecessors1(self, v as usize, &mut y);
+        *x = to_u32(y);
     }
 
     fn get_successors(&self, v: &[i32], x: &mut Vec<u32>) {
-        let mut check: Vec<u32> = Vec::new();
-        let mut fromv: HashSet<u32> = HashSet::new();
-        for j in 0..v.len() {
-            let s: u32 = v[j] as u32;
-            check.push(s);
-            fromv.insert(s);
-        }
-        while !check.is_empty() {
-            let x = check.pop().unwrap();
-            let n = self.n_from(x as usize);
-            for i in 0..n {
-                let y = self.v_from(x as usize, i);
-                if fromv.contains(&(y as u32)) {
-                    continue;
-                }
-                check.push(y as u32);
-                fromv.insert(y as u32);
-            }
-        }
-        x.clear();
-        for v in fromv {
-            x.push(v);
-        }
-        x.sort_unstable();
+        let mut y = Vec::new();
+        GraphSimpleIdx::get_successors(self, &to_usize(v), &mut y);
+        *x = to_u32(y);
     }
 
     fn get_successors1(&self, v: i32, x: &mut Vec<u32>) {
-        let vs = vec![v];
-        self.get_successors(&vs, x);
+        let mut y = Vec::new();
+        GraphSimpleIdx::get_successors1(self, v as usize, &mut y);
+        *x = to_u32(y);
+    }
+
+    fn get_predecessors_dense(&self, v: &[i32], x: &mut Vec<u32>) {
+        let mut y = Vec::new();
+        GraphSimpleIdx::get_predecessors_dense(self, &to_usize(v), &mut y);
+        *x = to_u32(y);
     }
 
-    // Implementation below is stupid.  Should just see if w is in successors(v).
+    fn get_successors_dense(&self, v: &[i32], x: &mut Vec<u32>) {
+        let mut y = Vec::new();
+        GraphSimpleIdx::get_successors_dense(self, &to_usize(v), &mut y);
+        *x = to_u32(y);
+    }
 
     fn have_path(&self, v: i32, w: i32) -> bool {
-        let mut vsuc: Vec<u32> = Vec::new();
-        self.get_successors1(v, &mut vsuc);
-        let mut wpre: Vec<u32> = Vec::new();
-        self.get_predecessors1(w, &mut wpre);
-        meet(&vsuc, &wpre)
+        GraphSimpleIdx::have_path(self, v as usize, w as usize)
     }
 
     fn components(&self, comp: &mut Vec<Vec<u32>>) {
-        comp.clear();
-        let mut used: Vec<bool> = vec![false; self.node_count()];
-        let mut c: Vec<u32> = Vec::new();
-        let mut cnext: Vec<u32> = Vec::new();
-        for v in 0..self.node_count() {
-            if used[v] {
-                continue;
-            }
-            c.clear();
-            cnext.clear();
-            cnext.push(v as u32);
-            while !cnext.is_empty() {
-                let w = cnext.pop().unwrap();
-                if used[w as usize] {
-                    continue;
-                }
-                used[w as usize] = true;
-                c.push(w);
-                let n = self.n_from(w as usize);
-                for j in 0..n {
-                    cnext.push(self.v_from(w as usize, j) as u32);
-                }
-                let n = self.n_to(w as usize);
-                for j in 0..n {
-                    cnext.push(self.v_to(w as usize, j) as u32);
-                }
-            }
-            c.sort_unstable();
-            comp.push(c.clone());
-        }
+        let mut c = Vec::new();
+        GraphSimpleIdx::components(self, &mut c);
+        *comp = to_u32s(c);
     }
 
     fn components_e(&self, comp: &mut Vec<Vec<u32>>) {
-        self.components(comp);
-        for j in 0..comp.len() {
-            let mut c = Vec::<u32>::new();
-            for i in 0..comp[j].len() {
-                let v = comp[j][i];
-                let n = self.n_from(v as usize);
-                for l in 0..n {
-                    c.push(self.e_from(v as usize, l) as u32);
-                }
-            }
-            c.sort();
-            comp[j] = c;
-        }
+        let mut c = Vec::new();
+        GraphSimpleIdx::components_e(self, &mut c);
+        *comp = to_u32s(c);
     }
 
     fn components_e_pos_sorted(&self, comp: &mut Vec<Vec<u32>>) {
-        self.components_e(comp);
-        for u in 0..comp.len() {
-            comp[u].sort_by(|a, b| {
-                if a == b {
-                    return std::cmp::Ordering::Equal;
-                }
-                let v = self.to_right(*a);
-                let w = self.to_left(*b);
-                if self.have_path(v as i32, w as i32) {
-                    return std::cmp::Ordering::Less;
-                }
-                let v = self.to_right(*b);
-                let w = self.to_left(*a);
-                if self.have_path(v as i32, w as i32) {
-                    return std::cmp::Ordering::Greater;
-                }
-                std::cmp::Ordering::Equal
-            });
-        }
+        let mut c = Vec::new();
+        GraphSimpleIdx::components_e_pos_sorted(self, &mut c);
+        *comp = to_u32s(c);
     }
 
     fn copathic(&self, p1: &Vec<u32>, p2: &Vec<u32>) -> bool {
-        for i in 0..p1.len() {
-            if p2.starts_with(&p1[i..]) || p1[i..].starts_with(&p2) {
-                return true;
-            }
-        }
-        for i in 0..p2.len() {
-            if p1.starts_with(&p2[i..]) || p2[i..].starts_with(&p1) {
-                return true;
-            }
-        }
-        let v = self.to_right(p1[p1.len() - 1]);
-        let w = self.to_left(p2[0]);
-        let mut suc = Vec::<u32>::new();
-        self.get_successors1(v as i32, &mut suc);
-        if bin_member(&suc, &w) {
-            return true;
-        }
-        let v = self.to_right(p2[p2.len() - 1]);
-        let w = self.to_left(p1[0]);
-        self.get_successors1(v as i32, &mut suc);
-        if bin_member(&suc, &w) {
-            return true;
-        }
-        false
+        let p1 = p1.iter().map(|&e| e as usize).collect::<Vec<usize>>();
+        let p2 = p2.iter().map(|&e| e as usize).collect::<Vec<usize>>();
+        GraphSimpleIdx::copathic(self, &p1, &p2)
+    }
+
+    fn shortest_path(&self, v: i32, w: i32) -> Option<Vec<u32>> {
+        GraphSimpleIdx::shortest_path(self, v as usize, w as usize).map(to_u32)
+    }
+
+    fn shortest_path_edges(&self, v: i32, w: i32) -> Option<Vec<u32>> {
+        GraphSimpleIdx::shortest_path_edges(self, v as usize, w as usize).map(to_u32)
+    }
+
+    fn all_simple_paths(&self, v: i32, w: i32, max_paths: usize, max_len: usize) -> Vec<Vec<u32>> {
+        to_u32s(GraphSimpleIdx::all_simple_paths(
+            self, v as usize, w as usize, max_paths, max_len,
+        ))
+    }
+
+    fn strongly_connected_components(&self) -> Vec<Vec<u32>> {
+        to_u32s(GraphSimpleIdx::strongly_connected_components(self))
+    }
+
+    fn reachability(&self) -> ReachabilityIndex {
+        GraphSimpleIdx::reachability(self)
+    }
+
+    fn condensation(&self, dedup: bool) -> (Graph<Vec<u32>, Vec<u32>, Directed>, Vec<u32>) {
+        let (h, comp) = GraphSimpleIdx::condensation(self, dedup);
+        let h = h.map(|_, c| to_u32(c.clone()), |_, e| to_u32(e.clone()));
+        (h, to_u32(comp))
+    }
+
+    fn cycles(&self, max_cycles: usize, max_len: usize) -> Vec<Vec<u32>> {
+        to_u32s(GraphSimpleIdx::cycles(self, max_cycles, max_len))
+    }
+
+    fn degree_summary(&self) -> GraphSummary {
+        GraphSimpleIdx::degree_summary(self)
+    }
+
+    fn count_paths(&self, v: i32, w: i32) -> Option<u128> {
+        GraphSimpleIdx::count_paths(self, v as usize, w as usize)
+    }
+
+    fn edges_between(&self, v: usize, w: usize) -> Vec<u32> {
+        to_u32(GraphSimpleIdx::edges_between(self, v, w))
+    }
+
+    fn has_edge(&self, v: usize, w: usize) -> bool {
+        GraphSimpleIdx::has_edge(self, v, w)
     }
+
+    fn neighborhood(&self, v: i32, radius: usize, direction: Dir) -> Vec<u32> {
+        to_u32(GraphSimpleIdx::neighborhood(
+            self, v as usize, radius, direction,
+        ))
+    }
+
+    fn dominators(&self, root: i32) -> Vec<i32> {
+        GraphSimpleIdx::dominators(self, root as usize)
+            .iter()
+            .map(|d| d.map_or(-1, |d| d as i32))
+            .collect()
+    }
+
+    fn unary_chains(&self) -> Vec<Vec<u32>> {
+        to_u32s(GraphSimpleIdx::unary_chains(self))
+    }
+
+    fn collapse_chains(&self) -> Graph<u32, Vec<u32>, Directed> {
+        GraphSimpleIdx::collapse_chains(self).map(|_, &v| v as u32, |_, c| to_u32(c.clone()))
+    }
+
+    fn cut_vertices(&self) -> Vec<u32> {
+        to_u32(GraphSimpleIdx::cut_vertices(self))
+    }
+
+    fn bridges(&self) -> Vec<u32> {
+        to_u32(GraphSimpleIdx::bridges(self))
+    }
+
+    fn self_loops(&self) -> Vec<u32> {
+        to_u32(GraphSimpleIdx::self_loops(self))
+    }
+
+    fn parallel_edge_groups(&self) -> Vec<Vec<u32>> {
+        to_u32s(GraphSimpleIdx::parallel_edge_groups(self))
+    }
+}
+
+// Conversions between id types.
+
+fn to_usize(x: &[i32]) -> Vec<usize> {
+    x.iter().map(|&v| v as usize).collect()
 }
 
-// tests can be run with
-// cargo test -p graph_simple -- --nocapture
+fn to_i32(x: Vec<usize>) -> Vec<i32> {
+    x.into_iter().map(|v| v as i32).collect()
+}
+
+fn to_u32(x: Vec<usize>) -> Vec<u32> {
+    x.into_iter().map(|v| v as u32).collect()
+}
+
+fn to_u32s(x: Vec<Vec<usize>>) -> Vec<Vec<u32>> {
+    x.into_iter().map(to_u32).collect()
+}
 
 #[cfg(test)]
+#[allow(deprecated)]
 mod tests {
     #[test]
     fn test_cyclic_core() {
@@ -514,4 +398,70 @@ mod tests {
         let core = g.cyclic_core();
         assert_eq!(core.len(), 0);
     }
+
+    #[test]
+    fn test_both_apis() {
+        use petgraph::prelude::*;
+        use rand::{rngs::StdRng, Rng, SeedableRng};
+
+        type Results = (
+            Vec<usize>,
+            Vec<usize>,
+            Vec<Vec<usize>>,
+            Vec<usize>,
+            Vec<i64>,
+        );
+
+        fn old_api(g: &DiGraph<i32, ()>) -> Results {
+            use crate::GraphSimple;
+            let mut comp = Vec::<Vec<u32>>::new();
+            g.components_e(&mut comp);
+            let mut suc = Vec::<u32>::new();
+            g.get_successors1(0, &mut suc);
+            (
+                g.cyclic_core().iter().map(|&v| v as usize).collect(),
+                suc.iter().map(|&v| v as usize).collect(),
+                comp.iter()
+                    .map(|c| c.iter().map(|&e| e as usize).collect())
+                    .collect(),
+                (0..g.edge_count())
+                    .map(|e| g.to_right(e as u32) as usize)
+                    .collect(),
+                g.dominators(0).iter().map(|&v| v as i64).collect(),
+            )
+        }
+
+        fn new_api(g: &DiGraph<i32, ()>) -> Results {
+            use crate::GraphSimpleIdx;
+            let mut comp = Vec::<Vec<usize>>::new();
+            g.components_e(&mut comp);
+            let mut suc = Vec::<usize>::new();
+            g.get_successors1(0, &mut suc);
+            (
+                g.cyclic_core(),
+                suc,
+                comp,
+                (0..g.edge_count()).map(|e| g.to_right(e)).collect(),
+                g.dominators(0)
+                    .iter()
+                    .map(|v| v.map_or(-1, |v| v as i64))
+                    .collect(),
+            )
+        }
+
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..50 {
+            let n = 1 + rng.gen::<u32>() % 20;
+            let m = rng.gen::<u32>() % 30;
+            let mut g = DiGraph::<i32, ()>::new();
+            for _ in 0..n {
+                g.add_node(0);
+            }
+            for _ in 0..m {
+                let (v, w) = (rng.gen::<u32>() % n, rng.gen::<u32>() % n);
+                g.add_edge(NodeIndex::new(v as usize), NodeIndex::new(w as usize), ());
+            }
+            assert_eq!(old_api(&g), new_api(&g));
+        }
+    }
 }
@@ -17,7 +17,7 @@ use debruijn::graph::DebruijnGraph;
 use debruijn::kmer::Kmer20;
 use debruijn::{filter, kmer, Exts, Kmer, Mer, Vmer};
 use equiv::EquivRel;
-use graph_simple::GraphSimple;
+use graph_simple::GraphSimpleIdx;
 use kmer_lookup::make_kmer_lookup_20_single;
 use petgraph::prelude::*;
 use std::cmp::max;
@@ -195,7 +195,7 @@ impl HyperBasevector {
     pub fn test_unique(&self) {
         let mut edges = Vec::<DnaString>::new();
         for e in 0..self.g.edge_count() {
-            edges.push(self.g.edge_obj(e as u32).clone());
+            edges.push(self.g.edge_obj(e).clone());
         }
         let mut kmers_plus = Vec::<(Kmer20, i32, i32)>::new();
         make_kmer_lookup_20_single(&edges, &mut kmers_plus);
@@ -266,9 +266,9 @@ impl Hyper {
         if p.is_empty() {
             return s;
         }
-        s = self.h.g.edge_obj(p[0] as u32).clone();
+        s = self.h.g.edge_obj(p[0] as usize).clone();
         for j in 1..p.len() as i32 {
-            let b = &self.h.g.edge_obj(p[j as usize] as u32);
+            let b = &self.h.g.edge_obj(p[j as usize] as usize);
             for i in self.h.k - 1..b.len() as i32 {
                 s.push(b.get(i as usize));
             }
@@ -281,21 +281,21 @@ impl Hyper {
     // =============================================================================
 
     pub fn print(&self) {
-        let mut comp = Vec::<Vec<u32>>::new();
+        let mut comp = Vec::<Vec<usize>>::new();
         self.h.g.components_e(&mut comp);
         for j in 0..comp.len() {
             println!("\nCOMPONENT {}", j + 1);
             for i in 0..comp[j].len() {
                 let e = comp[j][i];
-                let v = self.h.g.to_left(e as u32);
-                let w = self.h.g.to_right(e as u32);
-                let b: DnaString = self.h.g[EdgeIndex::<u32>::new(e as usize)].clone();
+                let v = self.h.g.to_left(e);
+                let w = self.h.g.to_right(e);
+                let b: DnaString = self.h.g[EdgeIndex::<u32>::new(e)].clone();
                 println!(
                     "\n{} ==(e={},len={},supp={})==> {}",
                     v,
                     e,
                     b.len() - self.h.k as usize + 1,
-                    self.supp(e as usize),
+                    self.supp(e),
                     w
                 );
                 println!("{}", b.to_string());
@@ -311,13 +311,13 @@ impl Hyper {
     // ============================================================================================
 
     pub fn print_with_annotations(&self, ann: &[String], require_ann: bool, hide_seq: bool) {
-        let mut comp = Vec::<Vec<u32>>::new();
+        let mut comp = Vec::<Vec<usize>>::new();
         self.h.g.components_e(&mut comp);
         let mut n = 0;
         for j in 0..comp.len() {
             let mut have_ann = false;
             for i in 0..comp[j].len() {
-                let e = comp[j][i] as usize;
+                let e = comp[j][i];
                 if !ann[e].is_empty() {
                     have_ann = true;
                 }
@@ -328,9 +328,9 @@ impl Hyper {
             n += 1;
             println!("\nCOMPONENT {}", n);
             for i in 0..comp[j].len() {
-                let e = comp[j][i] as usize;
-                let v = self.h.g.to_left(e as u32);
-                let w = self.h.g.to_right(e as u32);
+                let e = comp[j][i];
+                let v = self.h.g.to_left(e);
+                let w = self.h.g.to_right(e);
                 let b: DnaString = self.h.g[EdgeIndex::<u32>::new(e)].clone();
                 println!(
                     "\n{} ==(e={},len={},supp={})==> {}\n",
@@ -427,7 +427,7 @@ impl Hyper {
         let mut kmers_plus = Vec::<(Kmer20, i32, i32)>::new();
         let mut edges = Vec::<DnaString>::new();
         for e in 0..self.h.g.edge_count() {
-            edges.push(self.h.g.edge_obj(e as u32).clone());
+            edges.push(self.h.g.edge_obj(e).clone());
         }
         make_kmer_lookup_20_single(&edges, &mut kmers_plus);
         drop(edges);
@@ -463,10 +463,10 @@ impl Hyper {
                     }
                     let mut next = false;
                     if epos == self.h.bases(e as u32) as i32 {
-                        let v = self.h.g.to_right(e as u32);
+                        let v = self.h.g.to_right(e as usize);
                         for j in 0..self.h.g.n_from(v as usize) {
                             let f = self.h.g.e_from(v as usize, j);
-                            if self.h.g.edge_obj(f as u32).get((k - 1) as usize)
+                            if self.h.g.edge_obj(f as usize).get((k - 1) as usize)
                                 == reads[id].get(rpos)
                             {
                                 e = f as i32;
@@ -482,7 +482,7 @@ impl Hyper {
                         }
                     }
                     if !next {
-                        if reads[id].get(rpos) != self.h.g.edge_obj(e as u32).get(epos as usize) {
+                        if reads[id].get(rpos) != self.h.g.edge_obj(e as usize).get(epos as usize) {
                             break;
                         }
                         rpos += 1;
@@ -558,7 +558,7 @@ impl Hyper {
         let mut kmers_plus = Vec::<(Kmer48, i32, i32)>::new();
         let mut edges = Vec::<DnaString>::new();
         for e in 0..self.h.g.edge_count() {
-            edges.push(self.h.g.edge_obj(e as u32).clone());
+            edges.push(self.h.g.edge_obj(e).clone());
         }
         make_kmer_lookup_48_single(&edges, &mut kmers_plus);
         drop(edges);
@@ -594,10 +594,10 @@ impl Hyper {
                     }
                     let mut next = false;
                     if epos == self.h.bases(e as u32) as i32 {
-                        let v = self.h.g.to_right(e as u32);
+                        let v = self.h.g.to_right(e as usize);
                         for j in 0..self.h.g.n_from(v as usize) {
                             let f = self.h.g.e_from(v as usize, j);
-                            if self.h.g.edge_obj(f as u32).get((k - 1) as usize)
+                            if self.h.g.edge_obj(f as usize).get((k - 1) as usize)
                                 == reads[id].get(rpos)
                             {
                                 e = f as i32;
@@ -613,7 +613,7 @@ impl Hyper {
                         }
                     }
                     if !next {
-                        if reads[id].get(rpos) != self.h.g.edge_obj(e as u32).get(epos as usize) {
+                        if reads[id].get(rpos) != self.h.g.edge_obj(e as usize).get(epos as usize) {
                             break;
                         }
                         rpos += 1;
@@ -689,7 +689,7 @@ impl Hyper {
         let mut kmers_plus = Vec::<(Kmer40, i32, i32)>::new();
         let mut edges = Vec::<DnaString>::new();
         for e in 0..self.h.g.edge_count() {
-            edges.push(self.h.g.edge_obj(e as u32).clone());
+            edges.push(self.h.g.edge_obj(e).clone());
         }
         make_kmer_lookup_40_single(&edges, &mut kmers_plus);
         drop(edges);
@@ -725,10 +725,10 @@ impl Hyper {
                     }
                     let mut next = false;
                     if epos == self.h.bases(e as u32) as i32 {
-                        let v = self.h.g.to_right(e as u32);
+                        let v = self.h.g.to_right(e as usize);
                         for j in 0..self.h.g.n_from(v as usize) {
                             let f = self.h.g.e_from(v as usize, j);
-                            if self.h.g.edge_obj(f as u32).get((k - 1) as usize)
+                            if self.h.g.edge_obj(f as usize).get((k - 1) as usize)
                                 == reads[id].get(rpos)
                             {
                                 e = f as i32;
@@ -744,7 +744,7 @@ impl Hyper {
                         }
                     }
                     if !next {
-                        if reads[id].get(rpos) != self.h.g.edge_obj(e as u32).get(epos as usize) {
+                        if reads[id].get(rpos) != self.h.g.edge_obj(e as usize).get(epos as usize) {
                             break;
                         }
                         rpos += 1;
@@ -774,15 +774,15 @@ impl Hyper {
         assert!(self.h.g.n_to(w) > 0 || self.h.g.n_from(w) > 0);
         let v_rc;
         if self.h.g.n_to(v) > 0 {
-            v_rc = self.h.g.to_left(self.inv[self.h.g.e_to(v, 0)]);
+            v_rc = self.h.g.to_left(self.inv[self.h.g.e_to(v, 0)] as usize);
         } else {
-            v_rc = self.h.g.to_right(self.inv[self.h.g.e_from(v, 0)]);
+            v_rc = self.h.g.to_right(self.inv[self.h.g.e_from(v, 0)] as usize);
         }
         let w_rc;
         if self.h.g.n_to(w) > 0 {
-            w_rc = self.h.g.to_left(self.inv[self.h.g.e_to(w, 0)]);
+            w_rc = self.h.g.to_left(self.inv[self.h.g.e_to(w, 0)] as usize);
         } else {
-            w_rc = self.h.g.to_right(self.inv[self.h.g.e_from(w, 0)]);
+            w_rc = self.h.g.to_right(self.inv[self.h.g.e_from(w, 0)] as usize);
         }
         self.h.g.add_edge(
             NodeIndex::<u32>::new(v),
@@ -793,8 +793,8 @@ impl Hyper {
             self.inv.push(n);
         } else {
             self.h.g.add_edge(
-                NodeIndex::<u32>::new(w_rc as usize),
-                NodeIndex::<u32>::new(v_rc as usize),
+                NodeIndex::<u32>::new(w_rc),
+                NodeIndex::<u32>::new(v_rc),
                 edge.rc(),
             );
             self.inv.push(n + 1);
@@ -946,7 +946,7 @@ impl Hyper {
                     break;
                 }
             }
-            if eleft < self.inv[eright as usize] as usize {
+            if eleft < self.inv[eright] as usize {
                 bound.push((eleft as u32, eright as u32));
                 bound.push((self.inv[eright], self.inv[eleft]));
             }
@@ -959,7 +959,7 @@ impl Hyper {
         while !bound.is_empty() {
             let bounds = bound.pop().unwrap();
             let new_edge_no: usize = self.h.g.edge_count();
-            let mut new_edge = self.h.g.edge_obj(bounds.0).clone();
+            let mut new_edge = self.h.g.edge_obj(bounds.0 as usize).clone();
             have = self.ids[bounds.0 as usize].clone();
             for j in 0..have.len() {
                 havex[have[j] as usize] = true;
@@ -983,8 +983,8 @@ impl Hyper {
                 }
                 let edge_id = self.h.g.first_edge(v, Outgoing).unwrap();
                 let edge = self.h.g[edge_id].clone();
-                for j in 0..self.ids[edge_id.index() as usize].len() {
-                    let id = self.ids[edge_id.index() as usize][j];
+                for j in 0..self.ids[edge_id.index()].len() {
+                    let id = self.ids[edge_id.index()][j];
                     if !havex[id as usize] {
                         have.push(id);
                         havex[id as usize] = true;
@@ -1056,21 +1056,18 @@ impl Hyper {
                     let e = self.h.g.e_to(v, 0);
                     let f = self.h.g.e_from(v, 0);
                     let (e_rc, f_rc) = (self.inv[e], self.inv[f]);
-                    let mut vs = Vec::<u32>::new();
-                    vs.push(self.h.g.to_left(e as u32));
-                    vs.push(self.h.g.to_right(e as u32));
-                    vs.push(self.h.g.to_right(f as u32));
-                    vs.push(self.h.g.to_left(f_rc));
-                    vs.push(self.h.g.to_right(f_rc));
-                    vs.push(self.h.g.to_right(e_rc));
+                    let mut vs = vec![
+                        self.h.g.to_left(e),
+                        self.h.g.to_right(e),
+                        self.h.g.to_right(f),
+                        self.h.g.to_left(f_rc as usize),
+                        self.h.g.to_right(f_rc as usize),
+                        self.h.g.to_right(e_rc as usize),
+                    ];
                     unique_sort(&mut vs);
                     if vs.len() == 6 {
                         let ef = self.cat(&[e as i32, f as i32]);
-                        self.add_edge_and_rc(
-                            &ef,
-                            self.h.g.to_left(e as u32) as usize,
-                            self.h.g.to_right(f as u32) as usize,
-                        );
+                        self.add_edge_and_rc(&ef, self.h.g.to_left(e), self.h.g.to_right(f));
                         let mut ids = self.ids[e].clone();
                         ids.append(&mut self.ids[f].clone());
                         unique_sort(&mut ids);
@@ -1114,7 +1111,7 @@ impl Hyper {
                 );
                 panic!("Involution test failed.");
             }
-            assert_eq!(self.inv[self.inv[e as usize] as usize], e as u32);
+            assert_eq!(self.inv[self.inv[e] as usize], e as u32);
             let t = self.h.g[EdgeIndex::<u32>::new(e)].rc().to_string();
             if self.h.g[EdgeIndex::<u32>::new(self.inv[e] as usize)].to_string() != t {
                 panic!(
@@ -1133,7 +1130,7 @@ impl Hyper {
                 for j2 in 0..self.h.g.n_from(v) {
                     let e2 = self.h.g.e_from(v, j2);
                     let re2 = self.inv[e2];
-                    if self.h.g.to_right(re2) != self.h.g.to_left(re1) {
+                    if self.h.g.to_right(re2 as usize) != self.h.g.to_left(re1 as usize) {
                         homomorphism_fails.push((e1, e2));
                     } else {
                         oks += 1;
@@ -1175,11 +1172,11 @@ impl Hyper {
     pub fn test_overlaps(&self) {
         for v in 0..self.h.g.node_count() {
             for j1 in 0..self.h.g.n_to(v) {
-                let b1 = self.h.g.edge_obj(self.h.g.e_to(v, j1) as u32);
+                let b1 = self.h.g.edge_obj(self.h.g.e_to(v, j1));
                 let n1 = b1.len();
                 let k = self.h.k as usize;
                 for j2 in 0..self.h.g.n_from(v) {
-                    let b2 = self.h.g.edge_obj(self.h.g.e_from(v, j2) as u32);
+                    let b2 = self.h.g.edge_obj(self.h.g.e_from(v, j2));
                     assert_eq!(b1.slice(n1 - (k - 1), n1), b2.slice(0, k - 1));
                 }
             }
@@ -1269,7 +1266,7 @@ impl Hyper {
 
     #[allow(dead_code)]
     fn print_comp_sizes(&mut self) {
-        let mut comp = Vec::<Vec<u32>>::new();
+        let mut comp = Vec::<Vec<usize>>::new();
         self.h.g.components_e(&mut comp);
         let mut sizes = Vec::<usize>::new();
         for j in 0..comp.len() {
@@ -2,7 +2,11 @@
 
 // This file contains some miscellaneous string utilities.
 
-use std::cmp::max;
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use std::sync::RwLock;
 use vector_utils::next_diff;
 
 // ▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓
@@ -19,7 +23,42 @@ pub fn stringme(s: &[u8]) -> String {
     String::from_utf8(s.to_vec()).unwrap()
 }
 
+// Versions of strme and stringme that replace invalid UTF-8 by U+FFFD rather than panicking,
+// for use in diagnostics, and a version of strme that returns the error.
+
+pub fn strme_lossy(s: &[u8]) -> Cow<'_, str> {
+    String::from_utf8_lossy(s)
+}
+
+pub fn stringme_lossy(s: &[u8]) -> String {
+    String::from_utf8_lossy(s).into_owned()
+}
+
+pub fn try_strme(s: &[u8]) -> Result<&str, std::str::Utf8Error> {
+    std::str::from_utf8(s)
+}
+
+// Error from the try_ conversion functions below, giving the string that could not be
+// converted, and the name of the type it was to be converted to.
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseForceError {
+    pub value: String,
+    pub target: &'static str,
+}
+
+impl std::fmt::Display for ParseForceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "could not convert \"{}\" to {}", self.value, self.target)
+    }
+}
+
+impl std::error::Error for ParseForceError {}
+
 pub trait TextUtils<'a> {
+    // s.force_usize() etc.: convert s to a number, ignoring leading and trailing ASCII
+    // whitespace (or panic if that fails)
+
     fn force_usize(&self) -> usize;
     fn force_i32(&self) -> i32;
     fn force_i64(&self) -> i64;
@@ -27,6 +66,15 @@ pub trait TextUtils<'a> {
     fn force_u64(&self) -> u64;
     fn force_f64(&self) -> f64;
 
+    // Versions of the above that return an error rather than panicking.
+
+    fn try_usize(&self) -> Result<usize, ParseForceError>;
+    fn try_i32(&self) -> Result<i32, ParseForceError>;
+    fn try_i64(&self) -> Result<i64, ParseForceError>;
+    fn try_u16(&self) -> Result<u16, ParseForceError>;
+    fn try_u64(&self) -> Result<u64, ParseForceError>;
+    fn try_f64(&self) -> Result<f64, ParseForceError>;
+
     // s.before(t): return the part of s before the first instance of t
     // (or panic if t is not contained in s)
 
@@ -56,80 +104,211 @@ pub trait TextUtils<'a> {
     // return what's after that
 
     fn rev_after(&'a self, t: &str) -> &'a str;
+
+    // Versions of the above that return None, rather than panicking, if a pattern is
+    // not found.
+
+    fn try_before(&'a self, t: &str) -> Option<&'a str>;
+    fn try_after(&'a self, t: &str) -> Option<&'a str>;
+    fn try_between(&'a self, t: &str, u: &str) -> Option<&'a str>;
+    fn try_between2(&'a self, t: &str, u: &str) -> Option<&'a str>;
+    fn try_rev_before(&'a self, t: &str) -> Option<&'a str>;
+    fn try_rev_after(&'a self, t: &str) -> Option<&'a str>;
+
+    // s.count_occurrences(t): return the number of non-overlapping instances of t in s,
+    // scanning from the left, so that for example "aaaa" has two instances of "aa"
+
+    fn count_occurrences(&self, t: &str) -> usize;
+
+    // s.before_nth(t,n): return the part of s before the nth instance of t, counting from
+    // one, and as for count_occurrences (or panic if there are fewer than n instances)
+
+    fn before_nth(&'a self, t: &str, n: usize) -> &'a str;
+
+    // s.after_nth(t,n): return the part of s after the nth instance of t
+
+    fn after_nth(&'a self, t: &str, n: usize) -> &'a str;
+
+    // s.between_nth(t,n,u): return the part of s after the nth instance of t and before
+    // the first instance of u after that
+
+    fn between_nth(&'a self, t: &str, n: usize, u: &str) -> &'a str;
+
+    fn try_before_nth(&'a self, t: &str, n: usize) -> Option<&'a str>;
+    fn try_after_nth(&'a self, t: &str, n: usize) -> Option<&'a str>;
+    fn try_between_nth(&'a self, t: &str, n: usize, u: &str) -> Option<&'a str>;
+}
+
+fn parse_trimmed<T: std::str::FromStr>(
+    s: &str,
+    target: &'static str,
+) -> Result<T, ParseForceError> {
+    s.trim_matches(|c: char| c.is_ascii_whitespace())
+        .parse::<T>()
+        .map_err(|_| ParseForceError {
+            value: s.to_string(),
+            target,
+        })
+}
+
+// Find the start of the nth non-overlapping instance of t in s, counting from one.
+
+fn find_nth(s: &str, t: &str, n: usize) -> Option<usize> {
+    if n == 0 {
+        return None;
+    }
+    s.match_indices(t).nth(n - 1).map(|(p, _)| p)
 }
 
 impl<'a> TextUtils<'a> for str {
     fn force_usize(&self) -> usize {
-        self.parse::<usize>()
-            .unwrap_or_else(|_| panic!("could not convert \"{}\" to usize", self))
+        self.try_usize().unwrap_or_else(|e| panic!("{}", e))
     }
     fn force_i32(&self) -> i32 {
-        self.parse::<i32>()
-            .unwrap_or_else(|_| panic!("could not convert \"{}\" to i32", self))
+        self.try_i32().unwrap_or_else(|e| panic!("{}", e))
     }
     fn force_i64(&self) -> i64 {
-        self.parse::<i64>()
-            .unwrap_or_else(|_| panic!("could not convert \"{}\" to i64", self))
+        self.try_i64().unwrap_or_else(|e| panic!("{}", e))
     }
     fn force_u16(&self) -> u16 {
-        self.parse::<u16>()
-            .unwrap_or_else(|_| panic!("could not convert \"{}\" to u16", self))
+        self.try_u16().unwrap_or_else(|e| panic!("{}", e))
     }
     fn force_u64(&self) -> u64 {
-        self.parse::<u64>()
-            .unwrap_or_else(|_| panic!("could not convert \"{}\" to u64", self))
+        self.try_u64().unwrap_or_else(|e| panic!("{}", e))
     }
     fn force_f64(&self) -> f64 {
-        self.parse::<f64>()
-            .unwrap_or_else(|_| panic!("could not convert \"{}\" to f64", self))
+        self.try_f64().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    fn try_usize(&self) -> Result<usize, ParseForceError> {
+        parse_trimmed(self, "usize")
+    }
+    fn try_i32(&self) -> Result<i32, ParseForceError> {
+        parse_trimmed(self, "i32")
+    }
+    fn try_i64(&self) -> Result<i64, ParseForceError> {
+        parse_trimmed(self, "i64")
+    }
+    fn try_u16(&self) -> Result<u16, ParseForceError> {
+        parse_trimmed(self, "u16")
+    }
+    fn try_u64(&self) -> Result<u64, ParseForceError> {
+        parse_trimmed(self, "u64")
+    }
+    fn try_f64(&self) -> Result<f64, ParseForceError> {
+        parse_trimmed(self, "f64")
     }
 
     fn before(&'a self, u: &str) -> &'a str {
-        let r = self
-            .find(u)
-            .unwrap_or_else(|| panic!("failed to find \"{}\" in \"{}\"", u, self));
-        &self[0..r]
+        self.try_before(u)
+            .unwrap_or_else(|| panic!("failed to find \"{}\" in \"{}\"", u, self))
     }
 
     fn after(&'a self, t: &str) -> &'a str {
-        let l = self
-            .find(t)
+        self.try_after(t)
             .unwrap_or_else(|| panic!("after failed to find \"{}\" in \"{}\"", t, self))
-            + t.len();
-        &self[l..self.len()]
     }
 
     fn between(&'a self, t: &str, u: &str) -> &'a str {
         let a = self.after(t);
-        let r = a.find(u).unwrap_or_else(|| {
+        a.try_before(u).unwrap_or_else(|| {
             panic!(
                 "between( \"{}\", \"{}\", \"{}\" ) failed at second part",
                 self, t, u
             )
-        });
-        &a[0..r]
+        })
     }
 
     fn between2(&'a self, t: &str, u: &str) -> &'a str {
         let a = self.after(t);
-        let r = a.rfind(u).unwrap_or_else(|| {
+        a.try_rev_before(u).unwrap_or_else(|| {
             panic!(
                 "between2( \"{}\", \"{}\", \"{}\" ) failed at second part",
                 self, t, u
             )
-        });
-        &a[0..r]
+        })
     }
 
     fn rev_before(&'a self, t: &str) -> &'a str {
-        let l = 0;
-        let r = self.rfind(t).unwrap();
-        &self[l..r]
+        self.try_rev_before(t).unwrap()
     }
 
     fn rev_after(&'a self, t: &str) -> &'a str {
-        let l = self.rfind(t).unwrap();
-        &self[l + t.len()..self.len()]
+        self.try_rev_after(t).unwrap()
+    }
+
+    fn try_before(&'a self, t: &str) -> Option<&'a str> {
+        self.find(t).map(|r| &self[0..r])
+    }
+
+    fn try_after(&'a self, t: &str) -> Option<&'a str> {
+        self.find(t).map(|l| &self[l + t.len()..])
+    }
+
+    fn try_between(&'a self, t: &str, u: &str) -> Option<&'a str> {
+        self.try_after(t)?.try_before(u)
+    }
+
+    fn try_between2(&'a self, t: &str, u: &str) -> Option<&'a str> {
+        self.try_after(t)?.try_rev_before(u)
+    }
+
+    fn try_rev_before(&'a self, t: &str) -> Option<&'a str> {
+        self.rfind(t).map(|r| &self[0..r])
+    }
+
+    fn try_rev_after(&'a self, t: &str) -> Option<&'a str> {
+        self.rfind(t).map(|l| &self[l + t.len()..])
+    }
+
+    fn count_occurrences(&self, t: &str) -> usize {
+        self.matches(t).count()
+    }
+
+    fn before_nth(&'a self, t: &str, n: usize) -> &'a str {
+        self.try_before_nth(t, n).unwrap_or_else(|| {
+            panic!(
+                "before_nth failed to find instance {} of \"{}\" in \"{}\", which has {}",
+                n,
+                t,
+                self,
+                self.count_occurrences(t)
+            )
+        })
+    }
+
+    fn after_nth(&'a self, t: &str, n: usize) -> &'a str {
+        self.try_after_nth(t, n).unwrap_or_else(|| {
+            panic!(
+                "after_nth failed to find instance {} of \"{}\" in \"{}\", which has {}",
+                n,
+                t,
+                self,
+                self.count_occurrences(t)
+            )
+        })
+    }
+
+    fn between_nth(&'a self, t: &str, n: usize, u: &str) -> &'a str {
+        let a = self.after_nth(t, n);
+        a.try_before(u).unwrap_or_else(|| {
+            panic!(
+                "between_nth( \"{}\", \"{}\", {}, \"{}\" ) failed at second part",
+                self, t, n, u
+            )
+        })
+    }
+
+    fn try_before_nth(&'a self, t: &str, n: usize) -> Option<&'a str> {
+        find_nth(self, t, n).map(|r| &self[0..r])
+    }
+
+    fn try_after_nth(&'a self, t: &str, n: usize) -> Option<&'a str> {
+        find_nth(self, t, n).map(|l| &self[l + t.len()..])
+    }
+
+    fn try_between_nth(&'a self, t: &str, n: usize, u: &str) -> Option<&'a str> {
+        self.try_after_nth(t, n)?.try_before(u)
     }
 }
 
@@ -138,194 +317,2704 @@ impl<'a> TextUtils<'a> for str {
 // ▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓
 
 // Parse a line, breaking at commas, but not if they're in quotes.  And strip the quotes.
+// A field that starts with a quote is quoted, as in RFC 4180, and within it, a doubled
+// quote stands for a single quote, and commas and newlines are kept.  In a field that
+// does not start with a quote, quotes are kept as is, but commas between them still do
+// not break the field.  A quoted field that is not closed, or has text after its closing
+// quote, is not unescaped, but only stripped of its outer quotes if it both starts and ends
+// with one.  A line that ends with a comma inside an open quote gets an extra empty field.
 
 pub fn parse_csv(x: &str) -> Vec<String> {
+    split_fields(x, ',')
+}
+
+// Split at a separator, as parse_csv does at commas.
+
+fn split_fields(x: &str, sep: char) -> Vec<String> {
     let mut y = Vec::<String>::new();
-    let mut w = Vec::<char>::new();
-    for c in x.chars() {
-        w.push(c);
+    if x.is_empty() {
+        return y;
     }
-    let (mut quotes, mut i) = (0, 0);
-    while i < w.len() {
-        let mut j = i;
-        while j < w.len() {
-            if quotes % 2 == 0 && w[j] == ',' {
-                break;
-            }
-            if w[j] == '"' {
-                quotes += 1;
-            }
-            j += 1;
+
+    // Handle a malformed quoted field, which is put back as it was, less its outer quotes.
+
+    let malformed = |raw: &str| {
+        if raw.len() >= 2 && raw.starts_with('"') && raw.ends_with('"') {
+            raw[1..raw.len() - 1].to_string()
+        } else {
+            raw.to_string()
         }
-        let (mut start, mut stop) = (i, j);
-        if stop - start >= 2 && w[start] == '"' && w[stop - 1] == '"' {
-            start += 1;
-            stop -= 1;
+    };
+    let mut field = String::new();
+    let (mut start, mut quoted, mut in_quotes) = (0, false, false);
+    let (mut closed, mut bad) = (false, false);
+    let mut chars = x.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        if i == start && c == '"' {
+            quoted = true;
+            in_quotes = true;
+            continue;
         }
-        let mut s = String::new();
-        for m in start..stop {
-            s.push(w[m]);
+        if c == '"' {
+            if !quoted {
+                field.push(c);
+                in_quotes = !in_quotes;
+            } else if in_quotes && chars.peek().map(|p| p.1) == Some('"') {
+                field.push(c);
+                chars.next();
+            } else {
+                in_quotes = !in_quotes;
+                bad |= closed;
+                closed = true;
+            }
+        } else if c == sep && !in_quotes {
+            let f = std::mem::take(&mut field);
+            y.push(if bad { malformed(&x[start..i]) } else { f });
+            start = i + c.len_utf8();
+            quoted = false;
+            closed = false;
+            bad = false;
+        } else {
+            bad |= closed;
+            field.push(c);
         }
-        y.push(s);
-        i = j + 1;
     }
-    if !w.is_empty() && *w.last().unwrap() == ',' {
+    y.push(if bad || (quoted && in_quotes) {
+        malformed(&x[start..])
+    } else {
+        field
+    });
+    if in_quotes && x.ends_with(sep) {
         y.push(String::new());
     }
     y
 }
 
-// Quote a bunch of strings.
+// Split a string at each occurrence of any of the given delimiters, returning each piece
+// together with the delimiter that ended it, or None for the last piece.  For example,
+// IGHV1-69-2*01 splits into (IGHV1, -), (69, -), (2, *), (01, None).  Concatenating the
+// pieces and delimiters gives back the original string.  There is always at least one piece,
+// and pieces may be empty, as at consecutive delimiters or after a trailing delimiter.
 
-pub fn quote_vec(x: &[&str]) -> Vec<String> {
-    let mut y = vec![String::new(); x.len()];
-    for i in 0..x.len() {
-        y[i] = format!("\"{}\"", x[i]);
+pub fn split_keep_delims(s: &str, delims: &[char]) -> Vec<(String, Option<char>)> {
+    let mut pieces = Vec::new();
+    let mut piece = String::new();
+    for c in s.chars() {
+        if delims.contains(&c) {
+            pieces.push((std::mem::take(&mut piece), Some(c)));
+        } else {
+            piece.push(c);
+        }
     }
-    y
+    pieces.push((piece, None));
+    pieces
 }
 
-// Convert a sorted list into a an abbreviated string.
+// Parse a list of key-value pairs, as for example the attributes in a GTF file, which look
+// like gene_id "ENSG00000223972"; gene_name "DDX11L1";, and would be parsed using
+// parse_kv(s, ';', ' ').  Pairs are split as parse_csv splits fields, but at pair_sep.  Each
+// pair is split at its first kv_sep, after removing surrounding whitespace, and the key
+// and value are stripped of surrounding whitespace.  A value in quotes is unquoted, with
+// doubled quotes in it becoming single quotes.  A pair lacking kv_sep has an empty value,
+// and empty pairs are skipped.  Pairs are returned in order, including repeated keys.
 
-pub fn abbrev_list<T: Eq + std::fmt::Display>(x: &[T]) -> String {
+pub fn parse_kv(s: &str, pair_sep: char, kv_sep: char) -> Vec<(String, String)> {
+    let mut kv = Vec::<(String, String)>::new();
+    for pair in split_fields(s, pair_sep) {
+        let pair = pair.trim();
+        if pair.is_empty() {
+            continue;
+        }
+        let (key, value) = match pair.find(kv_sep) {
+            Some(p) => (&pair[..p], pair[p + kv_sep.len_utf8()..].trim()),
+            None => (pair, ""),
+        };
+        let value = if value.len() >= 2 && value.starts_with('"') && value.ends_with('"') {
+            value[1..value.len() - 1].replace("\"\"", "\"")
+        } else {
+            value.to_string()
+        };
+        kv.push((key.trim().to_string(), value));
+    }
+    kv
+}
+
+// Parse a list of key-value pairs as for parse_kv, and return a map from each key to its
+// values, in order.
+
+pub fn parse_kv_map(s: &str, pair_sep: char, kv_sep: char) -> HashMap<String, Vec<String>> {
+    let mut m = HashMap::<String, Vec<String>>::new();
+    for (k, v) in parse_kv(s, pair_sep, kv_sep) {
+        m.entry(k).or_default().push(v);
+    }
+    m
+}
+
+// Parse CSV text consisting of records separated by newlines, as for parse_csv, except
+// that a newline inside quotes does not end a record.  A carriage return at the end of a
+// record is removed, and empty lines are skipped.
+
+pub fn parse_csv_all(text: &str) -> Vec<Vec<String>> {
+    let mut records = Vec::<Vec<String>>::new();
+    let mut rec = String::new();
+    let mut quotes = 0;
+    for line in text.split('\n') {
+        rec.push_str(line);
+        quotes += line.matches('"').count();
+        if quotes % 2 == 1 {
+            rec.push('\n');
+            continue;
+        }
+        if rec.ends_with('\r') {
+            rec.pop();
+        }
+        if !rec.is_empty() {
+            records.push(parse_csv(&rec));
+        }
+        rec.clear();
+        quotes = 0;
+    }
+    if !rec.is_empty() {
+        rec.pop();
+        records.push(parse_csv(&rec));
+    }
+    records
+}
+
+// Read CSV records from a reader, one at a time, as for parse_csv_all, but returning an
+// error for a quote that is still open at the end of the input.  Errors give the line
+// number.  If skip_header is true, the first record is skipped.
+
+pub struct CsvReader<R: BufRead> {
+    reader: R,
+    line: usize,
+    skip_header: bool,
+}
+
+impl<R: BufRead> CsvReader<R> {
+    pub fn new(reader: R, skip_header: bool) -> CsvReader<R> {
+        CsvReader {
+            reader,
+            line: 0,
+            skip_header,
+        }
+    }
+
+    // Return the number of lines read so far.
+
+    pub fn line_number(&self) -> usize {
+        self.line
+    }
+
+    pub fn next_record(&mut self) -> io::Result<Option<Vec<String>>> {
+        loop {
+            let mut rec = String::new();
+            let mut quotes = 0;
+            let start = self.line + 1;
+            loop {
+                let n = self.reader.read_line(&mut rec).map_err(|e| {
+                    io::Error::new(e.kind(), format!("line {}: {}", self.line + 1, e))
+                })?;
+                if n == 0 {
+                    if quotes % 2 == 1 {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            format!("unterminated quote in record starting at line {}", start),
+                        ));
+                    }
+                    break;
+                }
+                self.line += 1;
+                quotes += rec[rec.len() - n..].matches('"').count();
+                if quotes % 2 == 0 {
+                    break;
+                }
+            }
+            if rec.is_empty() {
+                return Ok(None);
+            }
+            if rec.ends_with('\n') {
+                rec.pop();
+            }
+            if rec.ends_with('\r') {
+                rec.pop();
+            }
+            if rec.is_empty() {
+                continue;
+            }
+            if self.skip_header {
+                self.skip_header = false;
+                continue;
+            }
+            return Ok(Some(parse_csv(&rec)));
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for CsvReader<R> {
+    type Item = io::Result<Vec<String>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_record().transpose()
+    }
+}
+
+// Form a CSV line from fields, the inverse of parse_csv.  A field containing a comma, quote,
+// carriage return or newline is quoted, with its quotes doubled.  A line having just one
+// field, which is empty, is written as a pair of quotes, as otherwise it would be an empty
+// line.
+
+pub fn to_csv_line(fields: &[&str]) -> String {
+    if fields.len() == 1 && fields[0].is_empty() {
+        return "\"\"".to_string();
+    }
     let mut s = String::new();
-    let mut i = 0;
-    while i < x.len() {
+    for (i, f) in fields.iter().enumerate() {
         if i > 0 {
-            s.push_str(", ");
+            s.push(',');
         }
-        let j = next_diff(x, i);
-        if j - i == 1 {
-            s.push_str(&format!("{}", x[i]));
+        if f.contains(&[',', '"', '\r', '\n'][..]) {
+            s.push('"');
+            s.push_str(&f.replace('"', "\"\""));
+            s.push('"');
         } else {
-            s.push_str(&format!("{}^{}", x[i], j - i));
+            s.push_str(f);
         }
-        i = j;
     }
     s
 }
 
-// capitalize first letter
+// Write a CSV line, followed by a newline.
 
-pub fn cap1(s: &str) -> String {
-    let mut x = s.as_bytes().to_vec();
-    let c = x[0].to_ascii_uppercase();
-    x[0] = c;
-    String::from_utf8(x.to_vec()).unwrap()
+pub fn write_csv_row<W: Write>(w: &mut W, fields: &[&str]) -> io::Result<()> {
+    writeln!(w, "{}", to_csv_line(fields))
 }
 
-// stolen from internet, add commas to number
+// Write CSV lines.
 
-pub fn add_commas(n: usize) -> String {
-    let s = format!("{}", n);
-    let mut result = String::with_capacity(s.len() + ((s.len() - 1) / 3));
-    let mut i = s.len();
-    for c in s.chars() {
-        result.push(c);
-        i -= 1;
-        if i > 0 && i % 3 == 0 {
-            result.push(',');
-        }
+pub fn write_csv<W: Write>(w: &mut W, rows: &[Vec<String>]) -> io::Result<()> {
+    for row in rows.iter() {
+        let fields = row.iter().map(|f| f.as_str()).collect::<Vec<&str>>();
+        write_csv_row(w, &fields)?;
     }
-    result
+    Ok(())
 }
 
-// decimal_diffs: given two strings, determine if they are identical except for
-// numerical differences, as e.g.
-// woof_1.2x_3
-// woof_10.3x_7
-// would be (two differences).  And return the positions of the differing strings
-// and their float vallues, as
-// diffs = {(start1,stop1,start2,start2,stop2,x1,x2)}.  If the two strings are
-// identical or do not satisfy the requirements, an empty vector of diffs is
-// returned.
+// Parse a tab-separated line.  If backslash is true, the escapes \t, \n, \r and \\
+// within a field are replaced by tab, newline, carriage return and backslash, as in
+// database dumps, and any other backslash is kept as is.
 
-pub fn decimal_diffs(
-    s1: &[u8],
-    s2: &[u8],
-    diffs: &mut Vec<(usize, usize, usize, usize, f64, f64)>,
-) {
-    let (n1, n2) = (s1.len(), s2.len());
-    diffs.clear();
-    let (mut i1, mut i2) = (0, 0);
-    loop {
-        if i1 == n1 && i2 == n2 {
-            return;
+pub fn parse_tsv(x: &str, backslash: bool) -> Vec<String> {
+    let mut y = Vec::<String>::new();
+    if x.is_empty() {
+        return y;
+    }
+    for f in x.split('\t') {
+        if !backslash {
+            y.push(f.to_string());
+            continue;
         }
-        if i1 == n1 || i2 == n2 {
-            diffs.clear();
-            return;
+        let mut field = String::new();
+        let mut chars = f.chars().peekable();
+        while let Some(c) = chars.next() {
+            let d = if c == '\\' { chars.peek() } else { None };
+            match d {
+                Some('t') => field.push('\t'),
+                Some('n') => field.push('\n'),
+                Some('r') => field.push('\r'),
+                Some('\\') => field.push('\\'),
+                _ => {
+                    field.push(c);
+                    continue;
+                }
+            }
+            chars.next();
         }
-        let d1 = (s1[i1] >= b'0' && s1[i1] <= b'9') || s1[i1] == b'.';
-        let d2 = (s2[i2] >= b'0' && s2[i2] <= b'9') || s2[i2] == b'.';
-        if d1 != d2 || (!d1 && s1[i1] != s2[i2]) {
-            diffs.clear();
-            return;
+        y.push(field);
+    }
+    y
+}
+
+// Form a tab-separated line from fields, the inverse of parse_tsv.  If backslash is true,
+// tabs, newlines, carriage returns and backslashes are escaped.  Otherwise a field
+// containing a tab or newline cannot be represented, and we panic.
+
+pub fn to_tsv_line(fields: &[&str], backslash: bool) -> String {
+    let mut s = String::new();
+    for (i, f) in fields.iter().enumerate() {
+        if i > 0 {
+            s.push('\t');
         }
-        if !d1 {
-            i1 += 1;
-            i2 += 1;
+        if !backslash {
+            assert!(
+                !f.contains(&['\t', '\n'][..]),
+                "to_tsv_line: field \"{}\" contains a tab or newline",
+                f
+            );
+            s.push_str(f);
             continue;
         }
-        let (mut j1, mut j2) = (i1 + 1, i2 + 1);
-        let (mut dots1, mut dots2) = (0, 0);
-        while j1 < n1 {
-            if s1[j1] == b'.' {
-                if dots1 == 1 {
-                    break;
-                }
-                dots1 += 1;
-            } else if !(s1[j1] >= b'0' && s1[j1] <= b'9') {
-                break;
+        for c in f.chars() {
+            match c {
+                '\t' => s.push_str("\\t"),
+                '\n' => s.push_str("\\n"),
+                '\r' => s.push_str("\\r"),
+                '\\' => s.push_str("\\\\"),
+                _ => s.push(c),
             }
-            j1 += 1;
         }
-        while j2 < n2 {
-            if s2[j2] == b'.' {
-                if dots2 == 1 {
-                    break;
-                }
-                dots2 += 1;
-            } else if !(s2[j2] >= b'0' && s2[j2] <= b'9') {
-                break;
-            }
-            j2 += 1;
+    }
+    s
+}
+
+// Quote a bunch of strings, escaping quotes within them as \".  Backslashes are left as is.
+
+pub fn quote_vec(x: &[&str]) -> Vec<String> {
+    quote_vec_with(x, QuoteStyle::Backslash)
+}
+
+// Ways of escaping a quote within a quoted string: as \", or as "", as in CSV.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QuoteStyle {
+    Backslash,
+    Doubled,
+}
+
+pub fn quote_vec_with(x: &[&str], style: QuoteStyle) -> Vec<String> {
+    let esc = match style {
+        QuoteStyle::Backslash => "\\\"",
+        QuoteStyle::Doubled => "\"\"",
+    };
+    x.iter()
+        .map(|s| format!("\"{}\"", s.replace('"', esc)))
+        .collect()
+}
+
+// Quote a bunch of strings, as for quote_vec, and join them with a separator.
+
+pub fn quote_join(x: &[&str], sep: &str) -> String {
+    quote_vec(x).join(sep)
+}
+
+// Convert a sorted list into a an abbreviated string.
+
+pub fn abbrev_list<T: Eq + std::fmt::Display>(x: &[T]) -> String {
+    abbrev_tokens(x).join(", ")
+}
+
+// Convert a sorted list into an abbreviated string as for abbrev_list, but keep only as
+// many groups as fit in max_len characters, as computed by visible_width, together with
+// "… (k more)", where k is the number of omitted groups, if any groups were omitted.  If not
+// even "… (k more)" for all the groups fits, the result is "…", or empty if max_len is zero.
+
+pub fn abbrev_list_max<T: Eq + std::fmt::Display>(x: &[T], max_len: usize) -> String {
+    let tokens = abbrev_tokens(x);
+    let all = tokens.join(", ");
+    if visible_width(&all) <= max_len {
+        return all;
+    }
+    for i in (0..tokens.len()).rev() {
+        let mut s = tokens[..i].join(", ");
+        if i > 0 {
+            s.push_str(", ");
         }
-        if s1[i1..j1] != s2[i2..j2] {
-            let x1 = strme(&s1[i1..j1]).force_f64();
-            let x2 = strme(&s2[i2..j2]).force_f64();
-            diffs.push((i1, j1, i2, j2, x1, x2));
+        s.push_str(&format!("… ({} more)", tokens.len() - i));
+        if visible_width(&s) <= max_len {
+            return s;
         }
-        i1 = j1;
-        i2 = j2;
+    }
+    if max_len > 0 {
+        "…".to_string()
+    } else {
+        String::new()
     }
 }
 
-// Horizontal concatention.  Consider two vectors of strings, to be thought of as
-// rows to be printed.  Create a new vector of strings that is the horizontal
-// concatenation of these rows, first padding the first vector with blanks on the
-// right to achieve equal length and then adding additional specified separation.
+// Form the groups for abbrev_list, each being an element, followed by ^n if it is
+// repeated n > 1 times.
 
-pub fn hcat(col1: &[String], col2: &[String], sep: usize) -> Vec<String> {
-    let mut cat = Vec::<String>::new();
-    let height = max(col1.len(), col2.len());
-    let mut width1 = 0;
-    for x in col1 {
-        width1 = max(width1, x.len() + sep);
-    }
-    for i in 0..height {
-        let mut s = if i < col1.len() {
-            col1[i].clone()
+fn abbrev_tokens<T: Eq + std::fmt::Display>(x: &[T]) -> Vec<String> {
+    let mut tokens = Vec::<String>::new();
+    let mut i = 0;
+    while i < x.len() {
+        let j = next_diff(x, i);
+        if j - i == 1 {
+            tokens.push(format!("{}", x[i]));
         } else {
-            String::new()
-        };
-        while s.len() < width1 {
-            s += " ";
+            tokens.push(format!("{}^{}", x[i], j - i));
         }
-        if i < col2.len() {
-            s += &col2[i];
-        }
-        cat.push(s);
+        i = j;
+    }
+    tokens
+}
+
+// Integer types that can be abbreviated by abbrev_list_with_ranges.
+
+pub trait RangeElement: Copy + Ord + std::fmt::Display {
+    // Return the next value, or None if self is the largest value of its type.
+
+    fn successor(self) -> Option<Self>;
+
+    // Format the value in hexadecimal, with prefix 0x, following the sign if negative.
+
+    fn to_hex(self) -> String;
+}
+
+macro_rules! impl_range_element_unsigned {
+    ($($t:ty),*) => {$(
+        impl RangeElement for $t {
+            fn successor(self) -> Option<Self> {
+                self.checked_add(1)
+            }
+            fn to_hex(self) -> String {
+                format!("{:#x}", self)
+            }
+        }
+    )*};
+}
+
+macro_rules! impl_range_element_signed {
+    ($($t:ty),*) => {$(
+        impl RangeElement for $t {
+            fn successor(self) -> Option<Self> {
+                self.checked_add(1)
+            }
+            fn to_hex(self) -> String {
+                if self < 0 {
+                    format!("-{:#x}", self.unsigned_abs())
+                } else {
+                    format!("{:#x}", self)
+                }
+            }
+        }
+    )*};
+}
+
+impl_range_element_unsigned!(u8, u16, u32, u64, u128, usize);
+impl_range_element_signed!(i8, i16, i32, i64, i128, isize);
+
+// Convert a sorted list into an abbreviated string, in which each run of consecutive values
+// x[j] = x[j-1]+1 of length at least two is shown as a range first-last, as in
+// 1-3, 5, 7-8.  Other values, including repeated ones, are shown individually.  If hex is
+// true, values are shown in hexadecimal, as in 0x1-0x3, 0xa.
+
+pub fn abbrev_list_with_ranges<T: RangeElement>(x: &[T], hex: bool) -> String {
+    let fmt = |v: T| if hex { v.to_hex() } else { format!("{}", v) };
+    let mut tokens = Vec::<String>::new();
+    let mut i = 0;
+    while i < x.len() {
+        let mut j = i + 1;
+        while j < x.len() && x[j - 1].successor() == Some(x[j]) {
+            j += 1;
+        }
+        if j - i == 1 {
+            tokens.push(fmt(x[i]));
+        } else {
+            tokens.push(format!("{}-{}", fmt(x[i]), fmt(x[j - 1])));
+        }
+        i = j;
+    }
+    tokens.join(", ")
+}
+
+// Find the longest common prefix or suffix of some strings, in whole characters.  This is
+// empty if there are no strings.
+
+pub fn common_prefix(strs: &[&str]) -> String {
+    if strs.is_empty() {
+        return String::new();
+    }
+    let mut n = strs[0].len();
+    for s in strs[1..].iter() {
+        n = strs[0][..n]
+            .char_indices()
+            .zip(s.chars())
+            .find(|((_, c), d)| c != d)
+            .map_or(n.min(s.len()), |((i, _), _)| i);
+    }
+    strs[0][..n].to_string()
+}
+
+pub fn common_suffix(strs: &[&str]) -> String {
+    if strs.is_empty() {
+        return String::new();
+    }
+    let n = strs[0].len();
+    let mut m = n;
+    for s in strs[1..].iter() {
+        m = strs[0][n - m..]
+            .chars()
+            .rev()
+            .zip(s.chars().rev())
+            .take_while(|(c, d)| c == d)
+            .map(|(c, _)| c.len_utf8())
+            .sum();
+    }
+    strs[0][n - m..].to_string()
+}
+
+// Remove the longest common prefix from some strings, and return it.
+
+pub fn strip_common_prefix(strs: &mut [String]) -> String {
+    let p = common_prefix(&strs.iter().map(|s| s.as_str()).collect::<Vec<&str>>());
+    for s in strs.iter_mut() {
+        s.replace_range(..p.len(), "");
+    }
+    p
+}
+
+// Compare strings in natural order, so that for example lane2 comes before lane10.  Runs of
+// digits are compared as numbers, of any length, and everything else is compared by
+// character.  Among strings that are otherwise equal, at the first run of digits that
+// differs only in its leading zeros, the one with fewer leading zeros comes first.
+
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (mut x, mut y) = (a.chars().peekable(), b.chars().peekable());
+    let mut tiebreak = Ordering::Equal;
+    loop {
+        match (x.peek(), y.peek()) {
+            (None, None) => return tiebreak,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(c), Some(d)) if c.is_ascii_digit() && d.is_ascii_digit() => {
+                let run = |z: &mut std::iter::Peekable<std::str::Chars>| {
+                    let mut r = String::new();
+                    while let Some(c) = z.next_if(|c| c.is_ascii_digit()) {
+                        r.push(c);
+                    }
+                    r
+                };
+                let (r1, r2) = (run(&mut x), run(&mut y));
+                let (t1, t2) = (r1.trim_start_matches('0'), r2.trim_start_matches('0'));
+                let ord = t1.len().cmp(&t2.len()).then_with(|| t1.cmp(t2));
+                if ord != Ordering::Equal {
+                    return ord;
+                }
+                if tiebreak == Ordering::Equal {
+                    tiebreak = r1.len().cmp(&r2.len());
+                }
+            }
+            (Some(c), Some(d)) => {
+                if c != d {
+                    return c.cmp(d);
+                }
+                x.next();
+                y.next();
+            }
+        }
+    }
+}
+
+// Sort strings in natural order.
+
+pub fn sort_natural(v: &mut [String]) {
+    v.sort_by(|a, b| natural_cmp(a, b));
+}
+
+// capitalize first letter, which may become more than one letter, as ß becomes SS
+
+pub fn cap1(s: &str) -> String {
+    let mut x = s.chars();
+    match x.next() {
+        Some(c) => c.to_uppercase().chain(x).collect(),
+        None => String::new(),
+    }
+}
+
+// uncapitalize first letter
+
+pub fn uncap1(s: &str) -> String {
+    let mut x = s.chars();
+    match x.next() {
+        Some(c) => c.to_lowercase().chain(x).collect(),
+        None => String::new(),
+    }
+}
+
+// Convert a string to title case, capitalizing the first letter of each word, where words are
+// separated by whitespace, which is preserved.  The words of, the, and, in and for are made
+// lowercase instead, unless they are the first word.  For title_case_with, this treatment of
+// these stop words may be turned off, so that every word is capitalized.
+
+const TITLE_STOP_WORDS: [&str; 5] = ["of", "the", "and", "in", "for"];
+
+pub fn title_case(s: &str) -> String {
+    title_case_with(s, true)
+}
+
+pub fn title_case_with(s: &str, stop_words: bool) -> String {
+    let mut x = String::new();
+    let mut word = String::new();
+    let mut first = true;
+    let mut flush = |word: &mut String, x: &mut String| {
+        if word.is_empty() {
+            return;
+        }
+        let lower = word.to_lowercase();
+        if stop_words && !first && TITLE_STOP_WORDS.contains(&lower.as_str()) {
+            *x += &lower;
+        } else {
+            *x += &cap1(word);
+        }
+        first = false;
+        word.clear();
+    };
+    for c in s.chars() {
+        if c.is_whitespace() {
+            flush(&mut word, &mut x);
+            x.push(c);
+        } else {
+            word.push(c);
+        }
+    }
+    flush(&mut word, &mut x);
+    x
+}
+
+// Compute the Levenshtein distance between two strings, treated as sequences of chars,
+// using two rows of the standard dynamic programming matrix.
+
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let (mut a, mut b) = (
+        a.chars().collect::<Vec<char>>(),
+        b.chars().collect::<Vec<char>>(),
+    );
+    if a.len() < b.len() {
+        std::mem::swap(&mut a, &mut b);
+    }
+    let mut prev = (0..=b.len()).collect::<Vec<usize>>();
+    let mut cur = vec![0; b.len() + 1];
+    for i in 1..=a.len() {
+        cur[0] = i;
+        for j in 1..=b.len() {
+            let sub = prev[j - 1] + usize::from(a[i - 1] != b[j - 1]);
+            cur[j] = sub.min(prev[j] + 1).min(cur[j - 1] + 1);
+        }
+        std::mem::swap(&mut prev, &mut cur);
+    }
+    prev[b.len()]
+}
+
+// Compute the Levenshtein distance between two strings, as for edit_distance, but return
+// None if it exceeds max.  Only cells within max of the diagonal are computed, and we
+// stop as soon as every cell in a row exceeds max.
+
+pub fn edit_distance_bounded(a: &str, b: &str, max: usize) -> Option<usize> {
+    let (mut a, mut b) = (
+        a.chars().collect::<Vec<char>>(),
+        b.chars().collect::<Vec<char>>(),
+    );
+    if a.len() < b.len() {
+        std::mem::swap(&mut a, &mut b);
+    }
+    let (la, lb) = (a.len(), b.len());
+    if la - lb > max {
+        return None;
+    }
+    let inf = max + 1;
+    let mut prev = (0..=lb).map(|j| j.min(inf)).collect::<Vec<usize>>();
+    let mut cur = vec![inf; lb + 1];
+    for i in 1..=la {
+        let lo = if i > max { i - max } else { 1 };
+        let hi = lb.min(i + max);
+        cur[lo - 1] = if lo == 1 { i.min(inf) } else { inf };
+        let mut best = cur[lo - 1];
+        for j in lo..=hi {
+            let sub = prev[j - 1] + usize::from(a[i - 1] != b[j - 1]);
+            cur[j] = sub.min(prev[j] + 1).min(cur[j - 1] + 1).min(inf);
+            best = best.min(cur[j]);
+        }
+        if hi < lb {
+            cur[hi + 1] = inf;
+        }
+        if best > max {
+            return None;
+        }
+        std::mem::swap(&mut prev, &mut cur);
+    }
+    if prev[lb] <= max {
+        Some(prev[lb])
+    } else {
+        None
+    }
+}
+
+// stolen from internet, add commas to number
+
+pub fn add_commas(n: usize) -> String {
+    add_separators(n as u128, ',', Grouping::Thousands)
+}
+
+// Ways of grouping the digits of a number: in threes, as in 1,234,567, or in the Indian
+// style, with the last three digits grouped, and the rest grouped in twos, as in 12,34,567.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Grouping {
+    Thousands,
+    Indian,
+}
+
+// Add separators to a number, as for example add_separators(1234567, '.', Thousands) =
+// 1.234.567.
+
+pub fn add_separators(n: u128, sep: char, grouping: Grouping) -> String {
+    separate_digits(&format!("{}", n), sep, grouping)
+}
+
+// Format numbers for printing in a column, right justified to a common width.  For
+// format_aligned_f64, every value is shown with the given number of decimal places, so that
+// the decimal points line up.  For format_aligned_usize, values are optionally shown with
+// commas, as by add_commas.
+
+pub fn format_aligned_f64(xs: &[f64], decimals: usize) -> Vec<String> {
+    align_right(xs.iter().map(|x| format!("{:.*}", decimals, x)).collect())
+}
+
+pub fn format_aligned_usize(xs: &[usize], commas: bool) -> Vec<String> {
+    align_right(
+        xs.iter()
+            .map(|&x| {
+                if commas {
+                    add_commas(x)
+                } else {
+                    format!("{}", x)
+                }
+            })
+            .collect(),
+    )
+}
+
+fn align_right(x: Vec<String>) -> Vec<String> {
+    let width = x.iter().map(|s| s.chars().count()).max().unwrap_or(0);
+    x.iter().map(|s| pad_left(s, width)).collect()
+}
+
+// Form the ordinal of a number, as in 1st, 2nd, 3rd, 4th, 11th, 12th, 13th, 21st or 101st.
+
+pub fn ordinal(n: usize) -> String {
+    let suffix = if (11..=13).contains(&(n % 100)) {
+        "th"
+    } else {
+        match n % 10 {
+            1 => "st",
+            2 => "nd",
+            3 => "rd",
+            _ => "th",
+        }
+    };
+    format!("{}{}", n, suffix)
+}
+
+// Abbreviate a count, as in 999, 1.2K, 3.4M, 5.6B or 7.8T.  The value is truncated to one
+// decimal place, rather than rounded, so that for example 999,950 is 999.9K, not 1.0M.
+
+pub fn abbrev_count(n: usize) -> String {
+    if n < 1000 {
+        return format!("{}", n);
+    }
+    let units = [("K", 1_000_u128), ("M", 1_000_000), ("B", 1_000_000_000)];
+    let mut unit = ("T", 1_000_000_000_000);
+    for u in units.iter() {
+        if (n as u128) < u.1 * 1000 {
+            unit = *u;
+            break;
+        }
+    }
+    let tenths = n as u128 / (unit.1 / 10);
+    format!("{}.{}{}", tenths / 10, tenths % 10, unit.0)
+}
+
+// Add commas to a signed number.
+
+pub fn add_commas_i64(n: i64) -> String {
+    if n < 0 {
+        format!("-{}", commas_to_digits(&format!("{}", n.unsigned_abs())))
+    } else {
+        commas_to_digits(&format!("{}", n))
+    }
+}
+
+// Add commas to the integer part of a floating point number, which is shown with the given
+// number of digits after the decimal point.  Infinite and NaN values are printed as is.
+
+pub fn add_commas_f64(x: f64, decimals: usize) -> String {
+    let s = format!("{:.*}", decimals, x);
+    if !x.is_finite() {
+        return s;
+    }
+    let (sign, s) = match s.strip_prefix('-') {
+        Some(t) => ("-", t),
+        None => ("", s.as_str()),
+    };
+    match s.find('.') {
+        Some(p) => format!("{}{}{}", sign, commas_to_digits(&s[..p]), &s[p..]),
+        None => format!("{}{}", sign, commas_to_digits(s)),
+    }
+}
+
+fn commas_to_digits(s: &str) -> String {
+    separate_digits(s, ',', Grouping::Thousands)
+}
+
+fn separate_digits(s: &str, sep: char, grouping: Grouping) -> String {
+    let mut result = String::with_capacity(s.len() + ((s.len() - 1) / 2));
+    let mut i = s.len();
+    for c in s.chars() {
+        result.push(c);
+        i -= 1;
+        let split = match grouping {
+            Grouping::Thousands => i > 0 && i.is_multiple_of(3),
+            Grouping::Indian => i == 3 || (i > 3 && (i - 3).is_multiple_of(2)),
+        };
+        if split {
+            result.push(sep);
+        }
+    }
+    result
+}
+
+// Parse a number that may have its digits grouped in threes by commas or underscores, as
+// produced by add_commas, so for example 1,234,567 and 1_234_567 are accepted, as is
+// 1234567, but 12,34 is not.  Only one kind of separator may be used.  For the signed and
+// floating point versions, a sign is allowed, and for the floating point version, digits
+// after a decimal point, which are not grouped.
+
+pub fn parse_grouped_usize(s: &str) -> Option<usize> {
+    ungroup_digits(s)?.parse::<usize>().ok()
+}
+
+pub fn parse_grouped_i64(s: &str) -> Option<i64> {
+    let (sign, t) = split_sign(s);
+    format!("{}{}", sign, ungroup_digits(t)?)
+        .parse::<i64>()
+        .ok()
+}
+
+pub fn parse_grouped_f64(s: &str) -> Option<f64> {
+    let (sign, t) = split_sign(s);
+    let (int, frac) = match t.find('.') {
+        Some(p) => (&t[..p], &t[p..]),
+        None => (t, ""),
+    };
+    if !frac[frac.len().min(1)..]
+        .bytes()
+        .all(|c| c.is_ascii_digit())
+    {
+        return None;
+    }
+    let int = if int.is_empty() && frac.len() > 1 {
+        String::new()
+    } else {
+        ungroup_digits(int)?
+    };
+    format!("{}{}{}", sign, int, frac).parse::<f64>().ok()
+}
+
+fn split_sign(s: &str) -> (&str, &str) {
+    if s.starts_with('-') || s.starts_with('+') {
+        (&s[..1], &s[1..])
+    } else {
+        ("", s)
+    }
+}
+
+// Check that s consists of digits, optionally validly grouped, and return the digits.
+
+fn ungroup_digits(s: &str) -> Option<String> {
+    let sep = if s.contains(',') { ',' } else { '_' };
+    let groups = s.split(sep).collect::<Vec<&str>>();
+    for (i, g) in groups.iter().enumerate() {
+        let ok = if groups.len() == 1 {
+            !g.is_empty()
+        } else if i == 0 {
+            !g.is_empty() && g.len() <= 3
+        } else {
+            g.len() == 3
+        };
+        if !ok || !g.bytes().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+    }
+    Some(groups.concat())
+}
+
+// Format a number of bytes using decimal units, as in 512 B, 27 KB, 312 MB or 1.4 GB.  A
+// value below ten in its unit is shown with one decimal place, and otherwise none.
+// format_bytes_binary is the same but uses units of 1024, as in 1.4 GiB.
+
+pub fn format_bytes(n: u64) -> String {
+    format_in_units(n, 1000.0, &["B", "KB", "MB", "GB", "TB", "PB", "EB"])
+}
+
+pub fn format_bytes_binary(n: u64) -> String {
+    format_in_units(n, 1024.0, &["B", "KiB", "MiB", "GiB", "TiB", "PiB", "EiB"])
+}
+
+fn format_in_units(n: u64, base: f64, units: &[&str]) -> String {
+    let mut x = n as f64;
+    let mut u = 0;
+    while u < units.len() - 1 && x.round() >= base {
+        x /= base;
+        u += 1;
+    }
+    if u == 0 {
+        format!("{} {}", n, units[0])
+    } else if (x * 10.0).round() < 100.0 {
+        format!("{:.1} {}", x, units[u])
+    } else {
+        format!("{:.0} {}", x, units[u])
+    }
+}
+
+// Parse a number of bytes, as in 1.4GB or 1.4 GiB.  Units are case-insensitive, and may be
+// a single letter, as in 2G, which is decimal.  A number without units is in bytes.
+
+pub fn parse_bytes(s: &str) -> Option<u64> {
+    let s = s.trim();
+    let p = s
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(s.len());
+    let x = s[..p].parse::<f64>().ok()?;
+    let unit = s[p..].trim_start().to_ascii_lowercase();
+    let unit = unit.strip_suffix('b').unwrap_or(&unit);
+    let (prefix, base) = match unit.strip_suffix('i') {
+        Some(u) if !u.is_empty() => (u, 1024_f64),
+        Some(_) => return None,
+        None => (unit, 1000_f64),
+    };
+    let e = match prefix {
+        "" => 0,
+        "k" => 1,
+        "m" => 2,
+        "g" => 3,
+        "t" => 4,
+        "p" => 5,
+        "e" => 6,
+        _ => return None,
+    };
+    let y = (x * base.powi(e)).round();
+    if y >= u64::MAX as f64 {
+        return None;
+    }
+    Some(y as u64)
+}
+
+// Format a duration compactly, using its two most significant units, as in 0.43s, 12.3s,
+// 4m 07s, 1h 23m or 2d 3h.  Values are rounded to the nearest value in the smaller unit,
+// which may move them to the next larger unit, as 59.97 seconds becomes 1m 00s.
+
+pub fn format_duration(secs: f64) -> String {
+    if secs < 0.0 {
+        return format!("-{}", format_duration(-secs));
+    }
+    if (secs * 100.0).round() < 100.0 {
+        return format!("{:.2}s", secs);
+    }
+    if (secs * 10.0).round() < 600.0 {
+        return format!("{:.1}s", secs);
+    }
+    let s = secs.round() as u64;
+    if s < 3600 {
+        return format!("{}m {:02}s", s / 60, s % 60);
+    }
+    let m = (secs / 60.0).round() as u64;
+    if m < 24 * 60 {
+        return format!("{}h {:02}m", m / 60, m % 60);
+    }
+    let h = (secs / 3600.0).round() as u64;
+    format!("{}d {}h", h / 24, h % 24)
+}
+
+pub fn format_std_duration(d: std::time::Duration) -> String {
+    format_duration(d.as_secs_f64())
+}
+
+// Format numer/denom as a percentage with the given number of decimal places, as in 37.5%.
+// Rounding is computed exactly, with halves rounded up, so 1/400 is 0.3% whereas
+// format!("{:.1}", 0.25) would give 0.2.  The decimal places are always shown, including for
+// 100.0%.  If denom is zero, format_percent returns "—", and format_percent_or returns the
+// given string.
+
+pub fn format_percent(numer: usize, denom: usize, decimals: usize) -> String {
+    format_percent_or(numer, denom, decimals, "—")
+}
+
+pub fn format_percent_or(numer: usize, denom: usize, decimals: usize, if_zero: &str) -> String {
+    if denom == 0 {
+        return if_zero.to_string();
+    }
+    let (numer, denom) = (numer as u128, denom as u128);
+    let scale = 10u128.pow(decimals as u32);
+    let scaled = numer * 100 * scale;
+    let mut q = scaled / denom;
+    if 2 * (scaled % denom) >= denom {
+        q += 1;
+    }
+    if decimals == 0 {
+        format!("{}%", q)
+    } else {
+        format!("{}.{:0width$}%", q / scale, q % scale, width = decimals)
+    }
+}
+
+// Format numer/denom with its raw counts and percentage, as in 123/456 (27.0%).
+
+pub fn format_fraction(numer: usize, denom: usize, decimals: usize) -> String {
+    format!(
+        "{}/{} ({})",
+        numer,
+        denom,
+        format_percent(numer, denom, decimals)
+    )
+}
+
+// Format a float with the given number of significant digits (at least one).  Values whose
+// magnitude lies in [1e-4, 1e7) are shown in fixed notation, and others in exponent form, as
+// in 1.23e-5 or 4.6e9.  Trailing zeros after the decimal point are dropped, as is a bare
+// decimal point, so 2.50 is 2.5 and 5.0 is 5.  Zero is 0, and NaN and infinities are shown
+// as NaN, inf and -inf.
+
+pub fn format_sig(x: f64, sig: usize) -> String {
+    if x.is_nan() {
+        return "NaN".to_string();
+    }
+    if x.is_infinite() {
+        return if x > 0.0 { "inf" } else { "-inf" }.to_string();
+    }
+    if x == 0.0 {
+        return "0".to_string();
+    }
+    let sig = sig.max(1);
+
+    // Let the standard library do the rounding, then read off the digits and exponent.
+
+    let e = format!("{:.*e}", sig - 1, x.abs());
+    let (mantissa, exp) = e.split_once('e').unwrap();
+    let exp: i32 = exp.parse().unwrap();
+    let digits = mantissa.replace('.', "");
+    let sign = if x < 0.0 { "-" } else { "" };
+    if !(-4..7).contains(&exp) {
+        let m = trim_decimal_zeros(&format!("{}.{}", &digits[..1], &digits[1..]));
+        return format!("{}{}e{}", sign, m, exp);
+    }
+    let fixed = if exp < 0 {
+        format!("0.{}{}", "0".repeat((-exp - 1) as usize), digits)
+    } else {
+        let int_len = exp as usize + 1;
+        if digits.len() <= int_len {
+            format!("{}{}", digits, "0".repeat(int_len - digits.len()))
+        } else {
+            format!("{}.{}", &digits[..int_len], &digits[int_len..])
+        }
+    };
+    format!("{}{}", sign, trim_decimal_zeros(&fixed))
+}
+
+fn trim_decimal_zeros(s: &str) -> String {
+    if s.contains('.') {
+        s.trim_end_matches('0').trim_end_matches('.').to_string()
+    } else {
+        s.to_string()
+    }
+}
+
+// decimal_diffs: given two strings, determine if they are identical except for
+// numerical differences, as e.g.
+// woof_1.2x_3
+// woof_10.3x_7
+// would be (two differences).  And return the positions of the differing strings
+// and their float vallues, as
+// diffs = {(start1,stop1,start2,start2,stop2,x1,x2)}.  If the two strings are
+// identical or do not satisfy the requirements, an empty vector of diffs is
+// returned.
+//
+// A number consists of digits with at most one decimal point, optionally preceded by a
+// sign, and optionally followed by an exponent, as in -1.5e-3.  A sign is only part of a
+// number if the character before it is not alphanumeric, so that in woof-1, the number is
+// 1.
+
+pub fn decimal_diffs(
+    s1: &[u8],
+    s2: &[u8],
+    diffs: &mut Vec<(usize, usize, usize, usize, f64, f64)>,
+) {
+    let (n1, n2) = (s1.len(), s2.len());
+    diffs.clear();
+    let (mut i1, mut i2) = (0, 0);
+    loop {
+        if i1 == n1 && i2 == n2 {
+            return;
+        }
+        if i1 == n1 || i2 == n2 {
+            diffs.clear();
+            return;
+        }
+        let (j1, j2) = (number_end(s1, i1), number_end(s2, i2));
+        if j1.is_some() != j2.is_some() || (j1.is_none() && s1[i1] != s2[i2]) {
+            diffs.clear();
+            return;
+        }
+        if j1.is_none() {
+            i1 += 1;
+            i2 += 1;
+            continue;
+        }
+        let (j1, j2) = (j1.unwrap(), j2.unwrap());
+        if s1[i1..j1] != s2[i2..j2] {
+            let x1 = strme(&s1[i1..j1]).force_f64();
+            let x2 = strme(&s2[i2..j2]).force_f64();
+            diffs.push((i1, j1, i2, j2, x1, x2));
+        }
+        i1 = j1;
+        i2 = j2;
+    }
+}
+
+// Determine if two strings are identical except for numbers, as for decimal_diffs, that
+// differ by a small amount, i.e. |x1 - x2| <= abs_tol + rel_tol * max(|x1|, |x2|).
+
+pub fn strings_equal_within(s1: &str, s2: &str, rel_tol: f64, abs_tol: f64) -> bool {
+    check_strings_within(s1, s2, rel_tol, abs_tol).is_ok()
+}
+
+// As for strings_equal_within, but if the strings are not equal in this sense, return the
+// numbers that are most out of tolerance, and their start position in s1.  If the strings
+// are not identical except for numbers, return instead the first position at which they
+// differ, with NaN for both numbers.
+
+pub fn check_strings_within(
+    s1: &str,
+    s2: &str,
+    rel_tol: f64,
+    abs_tol: f64,
+) -> Result<(), (usize, f64, f64)> {
+    if s1 == s2 {
+        return Ok(());
+    }
+    let mut diffs = Vec::new();
+    decimal_diffs(s1.as_bytes(), s2.as_bytes(), &mut diffs);
+    if diffs.is_empty() {
+        let (b1, b2) = (s1.as_bytes(), s2.as_bytes());
+        let mut p = 0;
+        while p < b1.len() && p < b2.len() && b1[p] == b2[p] {
+            p += 1;
+        }
+        return Err((p, f64::NAN, f64::NAN));
+    }
+    let mut worst: Option<(f64, (usize, f64, f64))> = None;
+    for &(start1, _, _, _, x1, x2) in diffs.iter() {
+        let excess = (x1 - x2).abs() - (abs_tol + rel_tol * x1.abs().max(x2.abs()));
+        if excess > 0.0 && worst.is_none_or(|w| excess > w.0) {
+            worst = Some((excess, (start1, x1, x2)));
+        }
+    }
+    match worst {
+        Some(w) => Err(w.1),
+        None => Ok(()),
+    }
+}
+
+// If a number, as defined for decimal_diffs, starts at position i in s, return its end.
+
+fn number_end(s: &[u8], i: usize) -> Option<usize> {
+    let n = s.len();
+    let mut j = i;
+    if (s[j] == b'-' || s[j] == b'+') && (i == 0 || !s[i - 1].is_ascii_alphanumeric()) {
+        j += 1;
+    }
+    let (mut digits, mut dots) = (0, 0);
+    while j < n {
+        if s[j] == b'.' {
+            if dots == 1 {
+                break;
+            }
+            dots += 1;
+        } else if s[j].is_ascii_digit() {
+            digits += 1;
+        } else {
+            break;
+        }
+        j += 1;
+    }
+    if digits == 0 {
+        return None;
+    }
+    if j < n && (s[j] == b'e' || s[j] == b'E') {
+        let mut k = j + 1;
+        if k < n && (s[k] == b'-' || s[k] == b'+') {
+            k += 1;
+        }
+        if k < n && s[k].is_ascii_digit() {
+            while k < n && s[k].is_ascii_digit() {
+                k += 1;
+            }
+            j = k;
+        }
+    }
+    Some(j)
+}
+
+// Characters that have been registered as having width two, in addition to those that
+// is_wide_char knows about.
+
+static WIDE_CHARS: RwLock<Vec<char>> = RwLock::new(Vec::new());
+
+// Register a character as having visible width two.
+
+pub fn register_wide_char(c: char) {
+    let mut w = WIDE_CHARS.write().unwrap();
+    if !w.contains(&c) {
+        w.push(c);
+    }
+}
+
+// Determine if a character has visible width two.  These are the East Asian Wide and
+// Fullwidth characters (Hangul Jamo, CJK, Hangul syllables, CJK compatibility ideographs and
+// forms, fullwidth forms, and the supplementary ideographic planes), the emoji blocks, ✅ and
+// ❌, and registered characters.
+
+pub fn is_wide_char(c: char) -> bool {
+    matches!(c,
+        '\u{1100}'..='\u{115F}'
+        | '\u{2E80}'..='\u{A4CF}'
+        | '\u{AC00}'..='\u{D7A3}'
+        | '\u{F900}'..='\u{FAFF}'
+        | '\u{FE30}'..='\u{FE4F}'
+        | '\u{FF00}'..='\u{FF60}'
+        | '\u{FFE0}'..='\u{FFE6}'
+        | '\u{1F300}'..='\u{1F64F}'
+        | '\u{1F680}'..='\u{1F6FF}'
+        | '\u{1F900}'..='\u{1F9FF}'
+        | '\u{1FA70}'..='\u{1FAFF}'
+        | '\u{20000}'..='\u{3FFFD}'
+        | '✅'
+        | '❌')
+        || (!c.is_ascii() && WIDE_CHARS.read().unwrap().contains(&c))
+}
+
+// Determine if a character is a combining mark, zero-width joiner or variation selector, which
+// attaches to the preceding character and takes no space of its own.
+
+fn is_combining(c: char) -> bool {
+    matches!(c,
+        '\u{0300}'..='\u{036F}'
+        | '\u{1AB0}'..='\u{1AFF}'
+        | '\u{1DC0}'..='\u{1DFF}'
+        | '\u{200D}'
+        | '\u{20D0}'..='\u{20FF}'
+        | '\u{FE00}'..='\u{FE0F}'
+        | '\u{FE20}'..='\u{FE2F}')
+}
+
+// Compute the visible width of a character that is not part of an escape sequence: two for a
+// wide character, zero for a combining mark, and one otherwise.
+
+fn char_width(c: char) -> usize {
+    if is_wide_char(c) {
+        2
+    } else if is_combining(c) {
+        0
+    } else {
+        1
+    }
+}
+
+// Compute the visible length of a string, counting unicode characters as width one and
+// ignoring some ASCII escape sequences.  Characters that are known to be wide count as width two,
+// and combining marks count as width zero.
+
+pub fn visible_width(s: &str) -> usize {
+    let mut n = 0;
+    let mut escaped = false;
+    for c in s.chars() {
+        if escaped && c != 'm' {
+        } else if c == '' {
+            escaped = true;
+        } else if escaped && c == 'm' {
+            escaped = false;
+        } else {
+            n += char_width(c);
+        }
+    }
+    n
+}
+
+// Truncate a string to at most the given visible width, as computed by visible_width,
+// optionally marking truncation with an ellipsis, which counts towards the width.  A character
+// is never split from the combining marks that follow it, nor an escape sequence cut.  If an
+// escape sequence other than a reset is in effect at the cut, a reset is appended.  A string
+// that fits is returned unchanged.
+
+pub fn truncate_visible(s: &str, max: usize, ellipsis: bool) -> String {
+    if visible_width(s) <= max {
+        return s.to_string();
+    }
+    let budget = if ellipsis { max.saturating_sub(1) } else { max };
+    let c = s.chars().collect::<Vec<char>>();
+    let mut x = String::new();
+    let (mut n, mut open) = (0, false);
+    let mut i = 0;
+    while i < c.len() {
+        let mut j = i + 1;
+        if c[i] == '\x1b' {
+            while j < c.len() && c[j - 1] != 'm' {
+                j += 1;
+            }
+            let e = c[i..j].iter().collect::<String>();
+            open = e != "\x1b[0m" && e != "\x1b[m";
+            x += &e;
+        } else {
+            while j < c.len() && is_combining(c[j]) {
+                j += 1;
+            }
+            let w = char_width(c[i]);
+            if n + w > budget {
+                break;
+            }
+            x.extend(c[i..j].iter());
+            n += w;
+        }
+        i = j;
+    }
+    if ellipsis && max > 0 {
+        x.push('…');
+    }
+    if open {
+        x += "\x1b[0m";
+    }
+    x
+}
+
+// Wrap text greedily to lines of at most the given visible width, breaking at whitespace,
+// and breaking words that are too long.  Newlines are kept as line breaks, and other runs
+// of whitespace become single spaces, or line breaks.  If an escape sequence is in effect at
+// the end of a line, the line is ended with a reset sequence, and the escape sequences in
+// effect are repeated at the start of the next line.
+
+pub fn wrap_text(s: &str, width: usize) -> Vec<String> {
+    assert!(width > 0);
+    let mut lines = Vec::<String>::new();
+    let mut active = Vec::<String>::new();
+    let finish = |mut line: String, active: &[String], lines: &mut Vec<String>| {
+        if !active.is_empty() {
+            line.push_str("\x1b[0m");
+        }
+        lines.push(line);
+    };
+    for para in s.split('\n') {
+        let (mut line, mut w, mut empty) = (active.concat(), 0, true);
+        for word in para.split(char::is_whitespace).filter(|w| !w.is_empty()) {
+            if !empty {
+                if w + 1 + visible_width(word) <= width {
+                    line.push(' ');
+                    w += 1;
+                } else {
+                    finish(line, &active, &mut lines);
+                    line = active.concat();
+                    w = 0;
+                }
+            }
+            let mut chars = word.chars();
+            while let Some(c) = chars.next() {
+                if c == '\x1b' {
+                    let mut e = c.to_string();
+                    for d in chars.by_ref() {
+                        e.push(d);
+                        if d == 'm' {
+                            break;
+                        }
+                    }
+                    if e == "\x1b[0m" || e == "\x1b[m" {
+                        active.clear();
+                    } else {
+                        active.push(e.clone());
+                    }
+                    line.push_str(&e);
+                    continue;
+                }
+                let cw = char_width(c);
+                if w > 0 && w + cw > width {
+                    finish(line, &active, &mut lines);
+                    line = active.concat();
+                    w = 0;
+                }
+                line.push(c);
+                w += cw;
+            }
+            empty = false;
+        }
+        finish(line, &active, &mut lines);
+    }
+    lines
+}
+
+// Case-insensitive comparison of strings, in which characters are compared after conversion
+// to lowercase, as by char::to_lowercase, without allocating lowercase copies.  Because
+// conversion is not locale-aware, the Turkish dotted capital İ, which becomes i followed by a
+// combining dot, does not match a plain i, and the dotless ı does not match I.
+//
+// find_ignore_case returns the byte position in haystack of the first match.  A match must
+// consist of whole characters of haystack.
+
+pub fn find_ignore_case(haystack: &str, needle: &str) -> Option<usize> {
+    if needle.is_empty() {
+        return Some(0);
+    }
+    haystack
+        .char_indices()
+        .map(|(i, _)| i)
+        .find(|&i| starts_with_ignore_case(&haystack[i..], needle))
+}
+
+pub fn contains_ignore_case(haystack: &str, needle: &str) -> bool {
+    find_ignore_case(haystack, needle).is_some()
+}
+
+// Test if two strings are equal ignoring case and surrounding whitespace.
+
+pub fn eq_ignore_case_trimmed(a: &str, b: &str) -> bool {
+    a.trim()
+        .chars()
+        .flat_map(char::to_lowercase)
+        .eq(b.trim().chars().flat_map(char::to_lowercase))
+}
+
+fn starts_with_ignore_case(s: &str, prefix: &str) -> bool {
+    let mut p = prefix.chars().flat_map(char::to_lowercase).peekable();
+    for c in s.chars() {
+        for lc in c.to_lowercase() {
+            if p.next() != Some(lc) {
+                return false;
+            }
+        }
+        if p.peek().is_none() {
+            return true;
+        }
+    }
+    false
+}
+
+// Convert \r\n and bare \r line endings to \n.  The string is borrowed if it contains no
+// \r, and so is unchanged.
+
+pub fn normalize_newlines(s: &str) -> Cow<'_, str> {
+    if !s.contains('\r') {
+        return Cow::Borrowed(s);
+    }
+    let mut x = String::with_capacity(s.len());
+    let mut c = s.chars().peekable();
+    while let Some(a) = c.next() {
+        if a == '\r' {
+            c.next_if_eq(&'\n');
+            x.push('\n');
+        } else {
+            x.push(a);
+        }
+    }
+    Cow::Owned(x)
+}
+
+// The line endings used in a string, as found by detect_line_ending.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineEnding {
+    None,
+    Lf,
+    CrLf,
+    Cr,
+    Mixed,
+}
+
+// Determine which line ending a string uses, returning None if it has no line endings, and
+// Mixed if it has more than one kind.
+
+pub fn detect_line_ending(s: &str) -> LineEnding {
+    let mut found = LineEnding::None;
+    let b = s.as_bytes();
+    let mut i = 0;
+    while i < b.len() {
+        let e = match b[i] {
+            b'\r' if b.get(i + 1) == Some(&b'\n') => {
+                i += 1;
+                LineEnding::CrLf
+            }
+            b'\r' => LineEnding::Cr,
+            b'\n' => LineEnding::Lf,
+            _ => {
+                i += 1;
+                continue;
+            }
+        };
+        i += 1;
+        if found == LineEnding::None {
+            found = e;
+        } else if found != e {
+            return LineEnding::Mixed;
+        }
+    }
+    found
+}
+
+// Remove the same number of leading whitespace characters from every line, as many as
+// possible, ignoring lines that are entirely whitespace, and which are made empty.  A tab
+// counts as one character.
+
+pub fn dedent(s: &str) -> String {
+    let blank = |x: &str| x.chars().all(char::is_whitespace);
+    let n = s
+        .split('\n')
+        .filter(|x| !blank(x))
+        .map(|x| x.chars().take_while(|c| c.is_whitespace()).count())
+        .min()
+        .unwrap_or(0);
+    s.split('\n')
+        .map(|x| {
+            if blank(x) {
+                ""
+            } else {
+                &x[x.char_indices().nth(n).unwrap().0..]
+            }
+        })
+        .collect::<Vec<&str>>()
+        .join("\n")
+}
+
+// Add a prefix to every line that is not entirely whitespace.
+
+pub fn indent(s: &str, prefix: &str) -> String {
+    s.split('\n')
+        .map(|x| {
+            if x.chars().all(char::is_whitespace) {
+                x.to_string()
+            } else {
+                format!("{}{}", prefix, x)
+            }
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+// Errors from split_quoted.  Positions are byte offsets in the string.
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SplitError {
+    // a quote starting at pos is not closed
+    UnterminatedQuote { quote: char, pos: usize },
+    // the string ends with a backslash that escapes nothing
+    TrailingBackslash,
+}
+
+impl std::fmt::Display for SplitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            SplitError::UnterminatedQuote { quote, pos } => {
+                write!(
+                    f,
+                    "unterminated {} quote starting at position {}",
+                    quote, pos
+                )
+            }
+            SplitError::TrailingBackslash => write!(f, "trailing backslash"),
+        }
+    }
+}
+
+impl std::error::Error for SplitError {}
+
+// Split a string into words, roughly as a POSIX shell would, without expansions.  Words are
+// separated by unquoted whitespace.  Outside quotes, a backslash makes the next character
+// literal.  Within single quotes everything is literal, and within double quotes, only \"
+// and \\ are escapes.  Adjacent quoted and unquoted parts form one word, as "a"'b' is ab.
+
+pub fn split_quoted(s: &str) -> Result<Vec<String>, SplitError> {
+    let mut words = Vec::<String>::new();
+    let (mut word, mut in_word) = (String::new(), false);
+    let mut chars = s.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => {
+                let (_, d) = chars.next().ok_or(SplitError::TrailingBackslash)?;
+                word.push(d);
+            }
+            '\'' | '"' => loop {
+                let (_, d) = chars
+                    .next()
+                    .ok_or(SplitError::UnterminatedQuote { quote: c, pos: i })?;
+                if d == c {
+                    break;
+                }
+                if c == '"' && d == '\\' {
+                    if let Some((_, e)) = chars.next_if(|&(_, e)| e == '"' || e == '\\') {
+                        word.push(e);
+                        continue;
+                    }
+                }
+                word.push(d);
+            },
+            _ if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+                continue;
+            }
+            _ => word.push(c),
+        }
+        in_word = true;
+    }
+    if in_word {
+        words.push(word);
+    }
+    Ok(words)
+}
+
+// Pad a string with spaces on the right, on the left, or on both sides, to the given
+// visible width.  For pad_center, if the padding is odd, the extra space is on the right.
+// A string that is already at least as wide is returned unchanged.
+
+pub fn pad_right(s: &str, width: usize) -> String {
+    let n = width.saturating_sub(visible_width(s));
+    format!("{}{}", s, " ".repeat(n))
+}
+
+pub fn pad_left(s: &str, width: usize) -> String {
+    let n = width.saturating_sub(visible_width(s));
+    format!("{}{}", " ".repeat(n), s)
+}
+
+pub fn pad_center(s: &str, width: usize) -> String {
+    let n = width.saturating_sub(visible_width(s));
+    format!("{}{}{}", " ".repeat(n / 2), s, " ".repeat(n - n / 2))
+}
+
+// Horizontal concatention.  Consider two vectors of strings, to be thought of as
+// rows to be printed.  Create a new vector of strings that is the horizontal
+// concatenation of these rows, first padding the first vector with blanks on the
+// right to achieve equal length and then adding additional specified separation.
+
+pub fn hcat(col1: &[String], col2: &[String], sep: usize) -> Vec<String> {
+    hcat_n(&[col1, col2], sep)
+}
+
+// Horizontal concatenation of any number of column blocks, as for hcat.  Each block but the
+// last is padded on the right to its maximum width plus sep, except that an empty block
+// has zero width.  Blocks shorter than the tallest are padded with empty lines.  Widths are
+// visible widths, so that escape sequences are not counted.
+
+pub fn hcat_n(cols: &[&[String]], sep: usize) -> Vec<String> {
+    let height = cols.iter().map(|c| c.len()).max().unwrap_or(0);
+    let mut stops = Vec::<usize>::new();
+    let mut stop = 0;
+    for c in cols.iter() {
+        stop += c.iter().map(|x| visible_width(x) + sep).max().unwrap_or(0);
+        stops.push(stop);
+    }
+    let mut cat = Vec::<String>::new();
+    for i in 0..height {
+        let (mut s, mut width) = (String::new(), 0);
+        for (j, c) in cols.iter().enumerate() {
+            if i < c.len() {
+                s += &c[i];
+                width += visible_width(&c[i]);
+            }
+            if j < cols.len() - 1 {
+                while width < stops[j] {
+                    s += " ";
+                    width += 1;
+                }
+            }
+        }
+        cat.push(s);
+    }
+    cat
+}
+
+#[cfg(test)]
+mod tests {
+
+    #[test]
+    fn test_try_before_after() {
+        use crate::TextUtils;
+        let s = "a/bc/d/";
+        assert_eq!(s.try_before("a"), Some(""));
+        assert_eq!(s.try_before("/"), Some("a"));
+        assert_eq!(s.try_before("d/"), Some("a/bc/"));
+        assert_eq!(s.try_before("x"), None);
+        assert_eq!(s.try_after("a"), Some("/bc/d/"));
+        assert_eq!(s.try_after("/"), Some("bc/d/"));
+        assert_eq!(s.try_after("d/"), Some(""));
+        assert_eq!(s.try_after("x"), None);
+        assert_eq!(s.try_rev_before("a"), Some(""));
+        assert_eq!(s.try_rev_before("/"), Some("a/bc/d"));
+        assert_eq!(s.try_rev_before("x"), None);
+        assert_eq!(s.try_rev_after("a"), Some("/bc/d/"));
+        assert_eq!(s.try_rev_after("/"), Some(""));
+        assert_eq!(s.try_rev_after("c/"), Some("d/"));
+        assert_eq!(s.try_rev_after("x"), None);
+        assert_eq!(s.before("/"), "a");
+        assert_eq!(s.after("/"), "bc/d/");
+        assert_eq!(s.rev_before("/"), "a/bc/d");
+        assert_eq!(s.rev_after("c/"), "d/");
+    }
+
+    #[test]
+    fn test_try_between() {
+        use crate::TextUtils;
+        let s = "<a><b><c>";
+        assert_eq!(s.try_between("<", ">"), Some("a"));
+        assert_eq!(s.try_between2("<", ">"), Some("a><b><c"));
+        assert_eq!(s.try_between("<b", ">"), Some(""));
+        assert_eq!(s.try_between2("<b", ">"), Some("><c"));
+        assert_eq!(s.try_between("<c>", ""), Some(""));
+        assert_eq!(s.try_between("x", ">"), None);
+        assert_eq!(s.try_between("<", "x"), None);
+        assert_eq!(s.try_between2("x", ">"), None);
+        assert_eq!(s.try_between2("<c", "<"), None);
+        assert_eq!(s.between("<", ">"), "a");
+        assert_eq!(s.between2("<", ">"), "a><b><c");
+    }
+
+    #[test]
+    fn test_parse_csv() {
+        use crate::{parse_csv, parse_csv_all};
+        assert!(parse_csv("").is_empty());
+        assert_eq!(parse_csv(","), vec!["", ""]);
+        assert_eq!(parse_csv("a,b,"), vec!["a", "b", ""]);
+        assert_eq!(parse_csv("a,,b,,"), vec!["a", "", "b", "", ""]);
+        assert_eq!(parse_csv("\"a,b\",c"), vec!["a,b", "c"]);
+        assert_eq!(parse_csv("x \"a,b\",c"), vec!["x \"a,b\"", "c"]);
+        assert_eq!(parse_csv("\"\",a,\"\""), vec!["", "a", ""]);
+        assert_eq!(
+            parse_csv("\"he said \"\"hi\"\"\",\"\"\"\"\"\""),
+            vec!["he said \"hi\"", "\"\""]
+        );
+        assert_eq!(parse_csv("\"a\nb\",c"), vec!["a\nb", "c"]);
+
+        // Malformed quoting, which is left as it was before unescaping was added.
+
+        assert_eq!(parse_csv("a,\"b,c"), vec!["a", "\"b,c"]);
+        assert_eq!(parse_csv("a,\"b,"), vec!["a", "\"b,", ""]);
+        assert_eq!(parse_csv("a\"b,"), vec!["a\"b,", ""]);
+        assert_eq!(parse_csv("\"a\"\"b,c"), vec!["\"a\"\"b,c"]);
+        assert_eq!(parse_csv("\"a\"b,c"), vec!["\"a\"b", "c"]);
+        assert_eq!(parse_csv("\"a\"b\"x\",c"), vec!["a\"b\"x", "c"]);
+        assert_eq!(parse_csv("\"a\" ,\""), vec!["\"a\" ", "\""]);
+        let text = "name,note\r\nx,\"line 1\nline \"\"2\"\"\"\n\ny,\n\"z\",\"\"\n";
+        assert_eq!(
+            parse_csv_all(text),
+            vec![
+                vec!["name", "note"],
+                vec!["x", "line 1\nline \"2\""],
+                vec!["y", ""],
+                vec!["z", ""],
+            ]
+        );
+        assert_eq!(parse_csv_all("a,\"b\nc"), vec![vec!["a", "\"b\nc"]]);
+        assert!(parse_csv_all("").is_empty());
+    }
+
+    #[test]
+    fn test_csv_reader() {
+        use crate::CsvReader;
+        let text = "id,name\r\n1,\"a\r\nb\"\r\n\r\n2,\"c,d\"\n3,";
+        let recs = CsvReader::new(text.as_bytes(), true)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(
+            recs,
+            vec![vec!["1", "a\r\nb"], vec!["2", "c,d"], vec!["3", ""]]
+        );
+        let mut r = CsvReader::new(text.as_bytes(), false);
+        assert_eq!(r.next_record().unwrap().unwrap(), vec!["id", "name"]);
+        assert_eq!(r.line_number(), 1);
+        r.next_record().unwrap();
+        assert_eq!(r.line_number(), 3);
+        let mut r = CsvReader::new("a\nb,\"c\nd\n".as_bytes(), false);
+        assert_eq!(r.next_record().unwrap().unwrap(), vec!["a"]);
+        let e = r.next_record().unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "unterminated quote in record starting at line 2"
+        );
+        assert!(CsvReader::new("".as_bytes(), true).next().is_none());
+    }
+
+    #[test]
+    fn test_to_csv_line() {
+        use crate::{parse_csv, parse_csv_all, to_csv_line, write_csv};
+        assert_eq!(to_csv_line(&["a", "b c", ""]), "a,b c,");
+        assert_eq!(
+            to_csv_line(&["a,b", "say \"hi\""]),
+            "\"a,b\",\"say \"\"hi\"\"\""
+        );
+        let rows = [
+            vec!["a", "b,c", "", "\"", "\"\""],
+            vec!["line 1\nline 2", "x\r\ny", " \"q\" "],
+            vec![""],
+            vec!["", ""],
+        ];
+        for r in rows.iter() {
+            assert_eq!(parse_csv(&to_csv_line(r)), *r);
+        }
+        let rows = rows
+            .iter()
+            .map(|r| r.iter().map(|f| f.to_string()).collect::<Vec<String>>())
+            .collect::<Vec<_>>();
+        let mut out = Vec::<u8>::new();
+        write_csv(&mut out, &rows).unwrap();
+        assert_eq!(parse_csv_all(&String::from_utf8(out).unwrap()), rows);
+    }
+
+    #[test]
+    fn test_parse_tsv() {
+        use crate::{parse_tsv, to_tsv_line};
+        assert!(parse_tsv("", true).is_empty());
+        assert_eq!(parse_tsv("a\t\tb\\tc\t", false), vec!["a", "", "b\\tc", ""]);
+        assert_eq!(parse_tsv("a\t\tb\\tc\t", true), vec!["a", "", "b\tc", ""]);
+        assert_eq!(parse_tsv("\\x\\\\t\\", true), vec!["\\x\\t\\"]);
+        assert_eq!(to_tsv_line(&["a", "b\\c", ""], false), "a\tb\\c\t");
+        let rows = [
+            vec!["a\tb", "c\nd", "e\\f", "\\t", "\\\\n"],
+            vec!["", "x\r\ny", "\\"],
+            vec!["", ""],
+        ];
+        for r in rows.iter() {
+            let line = to_tsv_line(r, true);
+            assert!(!line.contains('\n'));
+            assert_eq!(parse_tsv(&line, true), *r);
+        }
+    }
+
+    #[test]
+    fn test_abbrev_list_max() {
+        use crate::{abbrev_list, abbrev_list_max, visible_width};
+        let x = [1, 1, 2, 30, 30, 30, 400];
+        assert_eq!(abbrev_list(&x), "1^2, 2, 30^3, 400");
+        assert_eq!(abbrev_list_max(&x, 0), "");
+        assert_eq!(abbrev_list_max(&x, 9), "…");
+        assert_eq!(abbrev_list_max(&x, 10), "… (4 more)");
+        assert_eq!(abbrev_list_max(&x, 15), "1^2, … (3 more)");
+        assert_eq!(abbrev_list_max(&x, 16), "1^2, … (3 more)");
+        assert_eq!(abbrev_list_max(&x, 17), "1^2, 2, 30^3, 400");
+        assert_eq!(abbrev_list_max(&x, 100), "1^2, 2, 30^3, 400");
+        assert_eq!(abbrev_list_max::<u32>(&[], 0), "");
+        let y = (0..12).collect::<Vec<u32>>();
+        assert_eq!(abbrev_list_max(&y, 18), "0, 1, … (10 more)");
+        assert_eq!(abbrev_list_max(&y, 19), "0, 1, 2, … (9 more)");
+        for max_len in 0..40 {
+            assert!(visible_width(&abbrev_list_max(&x, max_len)) <= max_len);
+            assert!(visible_width(&abbrev_list_max(&y, max_len)) <= max_len);
+        }
+    }
+
+    #[test]
+    fn test_add_commas() {
+        use crate::{add_commas, add_commas_f64, add_commas_i64};
+        assert_eq!(add_commas(0), "0");
+        assert_eq!(add_commas(999), "999");
+        assert_eq!(add_commas(1000), "1,000");
+        assert_eq!(add_commas(1234567), "1,234,567");
+        assert_eq!(add_commas_i64(0), "0");
+        assert_eq!(add_commas_i64(-999), "-999");
+        assert_eq!(add_commas_i64(-1_234_567), "-1,234,567");
+        assert_eq!(add_commas_i64(i64::MIN), "-9,223,372,036,854,775,808");
+        assert_eq!(add_commas_f64(1234567.891, 2), "1,234,567.89");
+        assert_eq!(add_commas_f64(-1234567.891, 0), "-1,234,568");
+        assert_eq!(add_commas_f64(999.9999, 3), "1,000.000");
+        assert_eq!(add_commas_f64(0.5, 1), "0.5");
+        assert_eq!(add_commas_f64(f64::NAN, 2), "NaN");
+    }
+
+    #[test]
+    fn test_add_separators() {
+        use crate::{add_separators, Grouping};
+        let t = Grouping::Thousands;
+        let i = Grouping::Indian;
+        for &sep in [',', '.', ' '].iter() {
+            let f = |n: u128, g: Grouping| add_separators(n, sep, g).replace(sep, "_");
+            assert_eq!(f(0, t), "0");
+            assert_eq!(f(0, i), "0");
+            assert_eq!(f(999, t), "999");
+            assert_eq!(f(999, i), "999");
+            assert_eq!(f(1000, t), "1_000");
+            assert_eq!(f(1000, i), "1_000");
+            assert_eq!(f(1234567, t), "1_234_567");
+            assert_eq!(f(1234567, i), "12_34_567");
+            assert_eq!(f(123456, i), "1_23_456");
+            assert_eq!(
+                f(u128::MAX, t),
+                "340_282_366_920_938_463_463_374_607_431_768_211_455"
+            );
+            assert_eq!(
+                f(u128::MAX, i),
+                "34_02_82_36_69_20_93_84_63_46_33_74_60_74_31_76_82_11_455"
+            );
+        }
+    }
+
+    #[test]
+    fn test_decimal_diffs() {
+        use crate::decimal_diffs;
+        let mut diffs = Vec::new();
+        let mut dd = |s1: &str, s2: &str| {
+            decimal_diffs(s1.as_bytes(), s2.as_bytes(), &mut diffs);
+            diffs.clone()
+        };
+        assert_eq!(
+            dd("woof_1.2x_3", "woof_10.3x_7"),
+            vec![(5, 8, 5, 9, 1.2, 10.3), (10, 11, 11, 12, 3.0, 7.0)]
+        );
+        assert_eq!(dd("x=-1.2", "x=1.2"), vec![(2, 6, 2, 5, -1.2, 1.2)]);
+        assert_eq!(dd("-1", "+2"), vec![(0, 2, 0, 2, -1.0, 2.0)]);
+        assert_eq!(dd("t 1e-3 s", "t 2E-3 s"), vec![(2, 6, 2, 6, 1e-3, 2e-3)]);
+        assert_eq!(dd("1.5e2", "150"), vec![(0, 5, 0, 3, 150.0, 150.0)]);
+        assert_eq!(dd("woof-1", "woof-2"), vec![(5, 6, 5, 6, 1.0, 2.0)]);
+        assert_eq!(dd("2em", "3em"), vec![(0, 1, 0, 1, 2.0, 3.0)]);
+        assert!(dd("woof-1", "woof+1").is_empty());
+        assert!(dd("a.b", "a.c").is_empty());
+        assert!(dd("a 1.2", "a 1.2").is_empty());
+    }
+
+    #[test]
+    fn test_strings_equal_within() {
+        use crate::{check_strings_within, strings_equal_within};
+        assert!(strings_equal_within("x = 1.0001", "x = 1.0002", 1e-3, 0.0));
+        assert!(strings_equal_within("x = 0.0", "x = 0.01", 0.0, 0.01));
+        assert!(strings_equal_within("same", "same", 0.0, 0.0));
+        assert!(!strings_equal_within("x = 1.0", "x = 1.1", 1e-3, 1e-3));
+        assert_eq!(
+            check_strings_within("a=1.0 b=2.0 c=3.0", "a=1.5 b=5.0 c=3.01", 0.01, 0.0),
+            Err((8, 2.0, 5.0))
+        );
+        let e = check_strings_within("n=1 reads", "n=1 read", 1.0, 1.0).unwrap_err();
+        assert_eq!(e.0, 8);
+        assert!(e.1.is_nan() && e.2.is_nan());
+        assert!(!strings_equal_within("x = 1", "y = 1", 1.0, 1.0));
+    }
+
+    #[test]
+    fn test_hcat_n() {
+        use crate::{hcat, hcat_n};
+        let v = |x: &[&str]| x.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        let (a, b, c) = (
+            v(&["ab", "c", "defg"]),
+            v(&["x", "yz"]),
+            v(&["1", "2", "3", "4"]),
+        );
+        assert_eq!(hcat(&a, &b, 2), hcat_n(&[&a, &b], 2));
+        assert_eq!(hcat(&b, &a, 0), hcat_n(&[&b, &a], 0));
+        assert_eq!(
+            hcat_n(&[&a, &b, &[], &c], 1),
+            v(&["ab   x  1", "c    yz 2", "defg    3", "        4"])
+        );
+        assert_eq!(hcat_n(&[&[], &b], 3), b);
+        assert!(hcat_n(&[], 1).is_empty());
+    }
+
+    #[test]
+    fn test_hcat_escapes() {
+        use crate::{hcat, visible_width};
+        let bold = |s: &str| format!("\x1b[01m{}\x1b[0m", s);
+        let a = vec![bold("ab"), bold("c")];
+        let b = vec!["x".to_string(), "y".to_string()];
+        assert_eq!(visible_width(&a[0]), 2);
+        let cat = hcat(&a, &b, 1);
+        assert_eq!(cat, vec![format!("{} x", a[0]), format!("{}  y", a[1])]);
+        assert!(cat.iter().all(|s| visible_width(s) == 4));
+    }
+
+    #[test]
+    fn test_nth() {
+        use crate::TextUtils;
+        let s = "/a/bc/d/e";
+        assert_eq!(s.count_occurrences("/"), 4);
+        assert_eq!(s.count_occurrences("x"), 0);
+        assert_eq!("aaaa".count_occurrences("aa"), 2);
+        assert_eq!("aaaaa".count_occurrences("aa"), 2);
+        assert_eq!(s.after_nth("/", 1), "a/bc/d/e");
+        assert_eq!(s.after_nth("/", 3), "d/e");
+        assert_eq!(s.before_nth("/", 1), "");
+        assert_eq!(s.before_nth("/", 3), "/a/bc");
+        assert_eq!(s.between_nth("/", 2, "/"), "bc");
+        assert_eq!(s.try_after_nth("/", 4), Some("e"));
+        assert_eq!(s.try_after_nth("/", 5), None);
+        assert_eq!(s.try_after_nth("/", 0), None);
+        assert_eq!(s.try_before_nth("/", 5), None);
+        assert_eq!(s.try_between_nth("/", 4, "/"), None);
+        assert_eq!("aaaa".try_after_nth("aa", 2), Some(""));
+        assert_eq!("aaaa".try_before_nth("aa", 2), Some("aa"));
+        assert_eq!("aaaa".try_after_nth("aa", 3), None);
+        let r = std::panic::catch_unwind(|| "a/b".after_nth("/", 2).to_string());
+        let e = r.unwrap_err();
+        assert_eq!(
+            e.downcast_ref::<String>().unwrap(),
+            "after_nth failed to find instance 2 of \"/\" in \"a/b\", which has 1"
+        );
+    }
+
+    #[test]
+    fn test_try_parse() {
+        use crate::{ParseForceError, TextUtils};
+        assert_eq!("12".try_usize(), Ok(12));
+        assert_eq!(" 12\r\n".try_usize(), Ok(12));
+        assert_eq!("\t-7 ".try_i32(), Ok(-7));
+        assert_eq!("-7".try_i64(), Ok(-7));
+        assert_eq!("65535\r".try_u16(), Ok(65535));
+        assert_eq!("1e3 ".try_f64(), Ok(1000.0));
+        assert_eq!(" 5 ".force_u64(), 5);
+        let e = "12x\r".try_usize().unwrap_err();
+        assert_eq!(
+            e,
+            ParseForceError {
+                value: "12x\r".to_string(),
+                target: "usize"
+            }
+        );
+        assert_eq!(e.to_string(), "could not convert \"12x\r\" to usize");
+        assert_eq!("65536".try_u16().unwrap_err().target, "u16");
+        assert!("-1".try_u64().is_err());
+        assert!("".try_f64().is_err());
+    }
+
+    #[test]
+    fn test_cap1() {
+        use crate::{cap1, uncap1};
+        assert_eq!(cap1("woof Woof"), "Woof Woof");
+        assert_eq!(cap1("7a"), "7a");
+        assert_eq!(cap1(""), "");
+        assert_eq!(cap1("épsilon"), "Épsilon");
+        assert_eq!(cap1("αβ"), "Αβ");
+        assert_eq!(cap1("ßa"), "SSa");
+        assert_eq!(uncap1("Woof Woof"), "woof Woof");
+        assert_eq!(uncap1(""), "");
+        assert_eq!(uncap1("Épsilon"), "épsilon");
+        assert_eq!(uncap1("ΑΒ"), "αΒ");
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        use crate::{edit_distance, edit_distance_bounded};
+        let cases = [
+            ("", "", 0),
+            ("", "abc", 3),
+            ("kitten", "sitting", 3),
+            ("flaw", "lawn", 2),
+            ("CDR3", "CDR3", 0),
+            ("IGHV3-23", "IGHV3-30", 2),
+            ("édouard", "edouard", 1),
+            ("αβγ", "αγ", 1),
+            ("intention", "execution", 5),
+        ];
+        for &(a, b, d) in cases.iter() {
+            assert_eq!(edit_distance(a, b), d);
+            assert_eq!(edit_distance(b, a), d);
+            for max in 0..7 {
+                let want = if d <= max { Some(d) } else { None };
+                assert_eq!(edit_distance_bounded(a, b, max), want);
+                assert_eq!(edit_distance_bounded(b, a, max), want);
+            }
+        }
+
+        // Compare the bounded and unbounded versions on random strings.
+
+        let mut x: u64 = 1;
+        let mut next = || {
+            x = x
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (x >> 33) as usize
+        };
+        for _ in 0..1000 {
+            let mut s = Vec::<String>::new();
+            for _ in 0..2 {
+                let n = next() % 10;
+                s.push((0..n).map(|_| ['a', 'b', 'c'][next() % 3]).collect());
+            }
+            let d = edit_distance(&s[0], &s[1]);
+            for max in 0..10 {
+                let want = if d <= max { Some(d) } else { None };
+                assert_eq!(edit_distance_bounded(&s[0], &s[1], max), want);
+            }
+        }
+    }
+
+    #[test]
+    fn test_common_prefix() {
+        use crate::{common_prefix, common_suffix, strip_common_prefix};
+        assert_eq!(common_prefix(&[]), "");
+        assert_eq!(common_suffix(&[]), "");
+        assert_eq!(common_prefix(&["abc"]), "abc");
+        assert_eq!(common_suffix(&["abc"]), "abc");
+        assert_eq!(common_prefix(&["abc", "abc"]), "abc");
+        assert_eq!(
+            common_prefix(&["/data/x.fa", "/data/yy.fa", "/data/"]),
+            "/data/"
+        );
+        assert_eq!(common_suffix(&["/data/x.fa", "/data/yy.fa"]), ".fa");
+        assert_eq!(common_prefix(&["abc", "xyz"]), "");
+        assert_eq!(common_suffix(&["abc", "xyz"]), "");
+        assert_eq!(common_prefix(&["abc", ""]), "");
+        // é and è share their first byte.
+        assert_eq!(common_prefix(&["aé", "aè"]), "a");
+        assert_eq!(common_suffix(&["éa", "èa"]), "a");
+        assert_eq!(common_prefix(&["αβγ", "αβδ", "αβ"]), "αβ");
+        assert_eq!(common_suffix(&["xγβ", "γβ"]), "γβ");
+        let mut v = vec!["lane1".to_string(), "lane2".to_string(), "lab".to_string()];
+        assert_eq!(strip_common_prefix(&mut v), "la");
+        assert_eq!(v, vec!["ne1", "ne2", "b"]);
+    }
+
+    #[test]
+    fn test_natural_cmp() {
+        use crate::{natural_cmp, sort_natural};
+        use std::cmp::Ordering::*;
+        assert_eq!(natural_cmp("a2", "a10"), Less);
+        assert_eq!(natural_cmp("a10", "a2"), Greater);
+        assert_eq!(natural_cmp("a2", "a02"), Less);
+        assert_eq!(natural_cmp("a02", "a2"), Greater);
+        assert_eq!(natural_cmp("a02", "a3"), Less);
+        assert_eq!(natural_cmp("a2", "a2"), Equal);
+        assert_eq!(natural_cmp("x1y10", "x1y9"), Greater);
+        assert_eq!(natural_cmp("x01y9", "x1y10"), Less);
+        assert_eq!(natural_cmp("x01y3", "x1y03"), Greater);
+        assert_eq!(natural_cmp("10", "9"), Greater);
+        assert_eq!(natural_cmp("a", "a1"), Less);
+        assert_eq!(natural_cmp("a1", "ab"), Less);
+        assert_eq!(
+            natural_cmp("n123456789012345678901234567890", "n99999999999999999999"),
+            Greater
+        );
+        assert_eq!(
+            natural_cmp(
+                "n123456789012345678901234567890",
+                "n123456789012345678901234567891"
+            ),
+            Less
+        );
+        let mut v = ["lane10", "lane2", "lane1", "Lane3", "lane02"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<String>>();
+        sort_natural(&mut v);
+        assert_eq!(v, vec!["Lane3", "lane1", "lane2", "lane02", "lane10"]);
+    }
+
+    #[test]
+    fn test_parse_grouped() {
+        use crate::{add_commas, parse_grouped_f64, parse_grouped_i64, parse_grouped_usize};
+        assert_eq!(parse_grouped_usize("1,234,567"), Some(1234567));
+        assert_eq!(parse_grouped_usize("1_234_567"), Some(1234567));
+        assert_eq!(parse_grouped_usize("1234567"), Some(1234567));
+        assert_eq!(parse_grouped_usize("0"), Some(0));
+        assert_eq!(parse_grouped_usize("999,999"), Some(999999));
+        for bad in [
+            "12,34",
+            "1234,567",
+            ",123",
+            "123,",
+            "1,,234",
+            "1,234_567",
+            "",
+            "1a",
+            "+1",
+        ] {
+            assert_eq!(parse_grouped_usize(bad), None);
+        }
+        for n in [0, 7, 1000, 98765, 123456789] {
+            assert_eq!(parse_grouped_usize(&add_commas(n)), Some(n));
+        }
+        assert_eq!(parse_grouped_i64("-1,234,567"), Some(-1234567));
+        assert_eq!(parse_grouped_i64("+12"), Some(12));
+        assert_eq!(parse_grouped_i64("-12,34"), None);
+        assert_eq!(parse_grouped_i64("--1"), None);
+        assert_eq!(parse_grouped_f64("1,234,567.891"), Some(1234567.891));
+        assert_eq!(parse_grouped_f64("-1_000.5"), Some(-1000.5));
+        assert_eq!(parse_grouped_f64("12.5"), Some(12.5));
+        assert_eq!(parse_grouped_f64(".5"), Some(0.5));
+        assert_eq!(parse_grouped_f64("1,000.000,1"), None);
+        assert_eq!(parse_grouped_f64("12,34.5"), None);
+        assert_eq!(parse_grouped_f64("."), None);
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        use crate::{format_bytes, format_bytes_binary, parse_bytes};
+        assert_eq!(format_bytes(0), "0 B");
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(999), "999 B");
+        assert_eq!(format_bytes(1000), "1.0 KB");
+        assert_eq!(format_bytes(1023), "1.0 KB");
+        assert_eq!(format_bytes(9_949), "9.9 KB");
+        assert_eq!(format_bytes(9_950), "10 KB");
+        assert_eq!(format_bytes(27_300), "27 KB");
+        assert_eq!(format_bytes(999_499), "999 KB");
+        assert_eq!(format_bytes(999_500), "1.0 MB");
+        assert_eq!(format_bytes(312_000_000), "312 MB");
+        assert_eq!(format_bytes(1_400_000_000), "1.4 GB");
+        assert_eq!(format_bytes(u64::MAX), "18 EB");
+        assert_eq!(format_bytes_binary(1023), "1023 B");
+        assert_eq!(format_bytes_binary(1024), "1.0 KiB");
+        assert_eq!(format_bytes_binary(10 * 1024), "10 KiB");
+        assert_eq!(format_bytes_binary(3 << 30), "3.0 GiB");
+        assert_eq!(parse_bytes("1.4GB"), Some(1_400_000_000));
+        assert_eq!(parse_bytes("1.4 gb"), Some(1_400_000_000));
+        assert_eq!(parse_bytes("2G"), Some(2_000_000_000));
+        assert_eq!(parse_bytes("1KiB"), Some(1024));
+        assert_eq!(parse_bytes("1.5 MiB"), Some(1_572_864));
+        assert_eq!(parse_bytes("512"), Some(512));
+        assert_eq!(parse_bytes("512 B"), Some(512));
+        assert_eq!(parse_bytes("1.4 XB"), None);
+        assert_eq!(parse_bytes("1 iB"), None);
+        assert_eq!(parse_bytes("GB"), None);
+        assert_eq!(parse_bytes("-1 GB"), None);
+    }
+
+    #[test]
+    fn test_format_duration() {
+        use crate::{format_duration, format_std_duration};
+        assert_eq!(format_duration(0.0), "0.00s");
+        assert_eq!(format_duration(0.43), "0.43s");
+        assert_eq!(format_duration(0.996), "1.0s");
+        assert_eq!(format_duration(12.34), "12.3s");
+        assert_eq!(format_duration(59.94), "59.9s");
+        assert_eq!(format_duration(59.97), "1m 00s");
+        assert_eq!(format_duration(247.0), "4m 07s");
+        assert_eq!(format_duration(3599.6), "1h 00m");
+        assert_eq!(format_duration(4980.0), "1h 23m");
+        assert_eq!(format_duration(86_399.0), "1d 0h");
+        assert_eq!(
+            format_duration(2.0 * 86_400.0 + 3.0 * 3600.0 + 100.0),
+            "2d 3h"
+        );
+        assert_eq!(format_duration(-12.34), "-12.3s");
+        assert_eq!(
+            format_std_duration(std::time::Duration::from_millis(247_000)),
+            "4m 07s"
+        );
+    }
+
+    #[test]
+    fn test_abbrev_count() {
+        use crate::abbrev_count;
+        assert_eq!(abbrev_count(0), "0");
+        assert_eq!(abbrev_count(999), "999");
+        assert_eq!(abbrev_count(1000), "1.0K");
+        assert_eq!(abbrev_count(1234), "1.2K");
+        assert_eq!(abbrev_count(999_949), "999.9K");
+        assert_eq!(abbrev_count(999_950), "999.9K");
+        assert_eq!(abbrev_count(999_999), "999.9K");
+        assert_eq!(abbrev_count(1_000_000), "1.0M");
+        assert_eq!(abbrev_count(345_678_901), "345.6M");
+        assert_eq!(abbrev_count(5_600_000_000), "5.6B");
+        assert_eq!(abbrev_count(7_890_000_000_000), "7.8T");
+        assert_eq!(abbrev_count(12_345_000_000_000_000), "12345.0T");
+    }
+
+    #[test]
+    fn test_quote_vec() {
+        use crate::{quote_join, quote_vec, quote_vec_with, QuoteStyle};
+        assert_eq!(
+            quote_vec(&["a", "", "b c"]),
+            vec!["\"a\"", "\"\"", "\"b c\""]
+        );
+        assert_eq!(quote_vec(&["say \"hi\""]), vec!["\"say \\\"hi\\\"\""]);
+        assert_eq!(quote_vec(&["a\\b"]), vec!["\"a\\b\""]);
+        assert_eq!(
+            quote_vec_with(&["say \"hi\"", "a\\b"], QuoteStyle::Doubled),
+            vec!["\"say \"\"hi\"\"\"", "\"a\\b\""]
+        );
+        assert_eq!(quote_join(&["a", "b\"c"], ", "), "\"a\", \"b\\\"c\"");
+        assert_eq!(quote_join(&[], ","), "");
+    }
+
+    #[test]
+    fn test_wrap_text() {
+        use crate::wrap_text;
+        assert_eq!(
+            wrap_text("the quick brown fox jumps", 10),
+            vec!["the quick", "brown fox", "jumps"]
+        );
+        assert_eq!(
+            wrap_text("abcdefghijkl xy", 5),
+            vec!["abcde", "fghij", "kl xy"]
+        );
+        assert_eq!(wrap_text("ab cdefghij", 4), vec!["ab", "cdef", "ghij"]);
+        assert_eq!(wrap_text("a b   \t", 3), vec!["a b"]);
+        assert_eq!(
+            wrap_text("one\n\ntwo  three", 20),
+            vec!["one", "", "two three"]
+        );
+        assert_eq!(wrap_text("", 5), vec![""]);
+        let (b, r) = ("\x1b[01m", "\x1b[0m");
+        assert_eq!(
+            wrap_text(&format!("{}bold text{} plain", b, r), 5),
+            vec![
+                format!("{}bold{}", b, r),
+                format!("{}text{}", b, r),
+                "plain".to_string()
+            ]
+        );
+        assert_eq!(
+            wrap_text(&format!("{}abcdef{} g", b, r), 4),
+            vec![format!("{}abcd{}", b, r), format!("{}ef{} g", b, r)]
+        );
+
+        // Combining marks take no width, so do not cause an early break, and stay with the
+        // character they follow.
+
+        let (a, e) = ("a\u{301}", "e\u{301}");
+        assert_eq!(
+            wrap_text(&format!("{}b{}cd{}", a, e, a), 4),
+            vec![format!("{}b{}c", a, e), format!("d{}", a)]
+        );
+        assert_eq!(
+            wrap_text(&format!("{}{}{}{}{}", a, e, a, e, a), 2),
+            vec![format!("{}{}", a, e), format!("{}{}", a, e), a.to_string()]
+        );
+    }
+
+    #[test]
+    fn test_dedent() {
+        use crate::{dedent, indent};
+        let s = "
+            usage: woof [options]
+
+              -a  the first
+            \t
+            done
+        ";
+        assert_eq!(
+            dedent(s),
+            "\nusage: woof [options]\n\n  -a  the first\n\ndone\n"
+        );
+        assert_eq!(dedent("\t\tx\n\ty\n"), "\tx\ny\n");
+        assert_eq!(dedent(" \tx\n\t  y"), "x\n y");
+        assert_eq!(dedent("x\n  y"), "x\n  y");
+        assert_eq!(dedent("   \n  "), "\n");
+        assert_eq!(dedent(""), "");
+        assert_eq!(indent("a\n\n b\n", "> "), "> a\n\n>  b\n");
+        assert_eq!(dedent(&indent("a\n  b", "    ")), "a\n  b");
+    }
+
+    #[test]
+    fn test_split_quoted() {
+        use crate::{split_quoted, SplitError};
+        let split = |s: &str| split_quoted(s).unwrap();
+        assert_eq!(split("  a  bc\td \n"), vec!["a", "bc", "d"]);
+        assert!(split("").is_empty());
+        assert_eq!(
+            split("cp '/my files/x' \"y z\""),
+            vec!["cp", "/my files/x", "y z"]
+        );
+        assert_eq!(split("\"a\"'b'c"), vec!["abc"]);
+        assert_eq!(split("'say \"hi\"' \"it's\""), vec!["say \"hi\"", "it's"]);
+        assert_eq!(split("\"a \\\"b\\\" \\\\ \\n\""), vec!["a \"b\" \\ \\n"]);
+        assert_eq!(split("'a\\b' a\\ b \\'"), vec!["a\\b", "a b", "'"]);
+        assert_eq!(split("'' \"\" x"), vec!["", "", "x"]);
+        assert_eq!(
+            split_quoted("a 'b c"),
+            Err(SplitError::UnterminatedQuote {
+                quote: '\'',
+                pos: 2
+            })
+        );
+        assert_eq!(
+            split_quoted("a \"b\\\"").unwrap_err().to_string(),
+            "unterminated \" quote starting at position 2"
+        );
+        assert_eq!(split_quoted("a\\"), Err(SplitError::TrailingBackslash));
+    }
+
+    #[test]
+    fn test_parse_kv() {
+        use crate::{parse_kv, parse_kv_map};
+        let kv = |x: &[(&str, &str)]| {
+            x.iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect::<Vec<_>>()
+        };
+        let gtf1 = "gene_id \"ENSG00000211592\"; gene_version \"8\"; transcript_id \
+            \"ENST00000390237\"; exon_number \"1\"; gene_name \"IGKC\"; \
+            tag \"basic\"; tag \"CCDS\";";
+        let gtf2 = "gene_id \"ENSG00000211592\"; exon_number \"1\"; gene_name \"IGKC\"; \
+            gene_version \"8\"; transcript_id \"ENST00000390237\"; tag \"basic\"; \
+            tag \"CCDS\";";
+        let x = parse_kv(gtf1, ';', ' ');
+        assert_eq!(
+            x,
+            kv(&[
+                ("gene_id", "ENSG00000211592"),
+                ("gene_version", "8"),
+                ("transcript_id", "ENST00000390237"),
+                ("exon_number", "1"),
+                ("gene_name", "IGKC"),
+                ("tag", "basic"),
+                ("tag", "CCDS"),
+            ])
+        );
+        let (m1, m2) = (parse_kv_map(gtf1, ';', ' '), parse_kv_map(gtf2, ';', ' '));
+        assert_eq!(m1, m2);
+        assert_eq!(m2["gene_name"], vec!["IGKC"]);
+        assert_eq!(m2["tag"], vec!["basic", "CCDS"]);
+        assert_eq!(
+            parse_kv(" a = 1, b=\"x, \"\"y\"\"\", c,d= ", ',', '='),
+            kv(&[("a", "1"), ("b", "x, \"y\""), ("c", ""), ("d", "")])
+        );
+        assert!(parse_kv("", ';', '=').is_empty());
+    }
+
+    #[test]
+    fn test_pad() {
+        use crate::{pad_center, pad_left, pad_right};
+        assert_eq!(pad_right("ab", 5), "ab   ");
+        assert_eq!(pad_left("ab", 5), "   ab");
+        assert_eq!(pad_center("ab", 5), " ab  ");
+        assert_eq!(pad_center("ab", 6), "  ab  ");
+        assert_eq!(pad_right("abcdef", 3), "abcdef");
+        assert_eq!(pad_left("abc", 3), "abc");
+        assert_eq!(pad_center("", 2), "  ");
+        let red = "\x1b[31mab\x1b[0m";
+        assert_eq!(pad_right(red, 4), format!("{}  ", red));
+        assert_eq!(pad_left(red, 3), format!(" {}", red));
+        assert_eq!(pad_right("é✅", 5), "é✅  ");
+        assert_eq!(pad_center("✅", 5), " ✅  ");
+        assert_eq!(pad_right("漢字", 6), "漢字  ");
+        assert_eq!(pad_left("한글a", 6), " 한글a");
+        assert_eq!(pad_center("ｆｕｌｌ", 10), " ｆｕｌｌ ");
+        assert_eq!(pad_center("\x1b[31m漢\x1b[0m", 5), " \x1b[31m漢\x1b[0m  ");
+        assert_eq!(pad_left("漢字", 3), "漢字");
+    }
+
+    #[test]
+    fn test_format_percent() {
+        use crate::{format_fraction, format_percent, format_percent_or};
+        assert_eq!(format_percent(3, 8, 1), "37.5%");
+        assert_eq!(format_percent(0, 0, 1), "—");
+        assert_eq!(format_percent_or(0, 0, 1, "0.0%"), "0.0%");
+        assert_eq!(format_percent(5, 5, 1), "100.0%");
+        assert_eq!(format_percent(5, 5, 0), "100%");
+        assert_eq!(format_percent(1, 400, 1), "0.3%");
+        assert_eq!(format_percent(1, 2000, 1), "0.1%");
+        assert_eq!(format_percent(3, 400, 1), "0.8%");
+        assert_eq!(format_percent(1, 3, 2), "33.33%");
+        assert_eq!(format_percent(2, 3, 0), "67%");
+        assert_eq!(format_percent(1, 10000, 3), "0.010%");
+        assert_eq!(format_fraction(123, 456, 1), "123/456 (27.0%)");
+        assert_eq!(format_fraction(0, 0, 1), "0/0 (—)");
+    }
+
+    #[test]
+    fn test_format_sig() {
+        use crate::format_sig;
+        assert_eq!(format_sig(0.000123456, 3), "0.000123");
+        assert_eq!(format_sig(0.0000123456, 3), "1.23e-5");
+        assert_eq!(format_sig(123456.789, 3), "123000");
+        assert_eq!(format_sig(123456.789, 9), "123456.789");
+        assert_eq!(format_sig(12345678.9, 3), "1.23e7");
+        assert_eq!(format_sig(0.0, 3), "0");
+        assert_eq!(format_sig(-5.0, 3), "-5");
+        assert_eq!(format_sig(2.5, 3), "2.5");
+        assert_eq!(format_sig(9.999, 2), "10");
+        assert_eq!(format_sig(-0.5, 1), "-0.5");
+        assert_eq!(format_sig(1e20, 2), "1e20");
+        assert_eq!(format_sig(f64::NAN, 3), "NaN");
+        assert_eq!(format_sig(f64::INFINITY, 3), "inf");
+        assert_eq!(format_sig(f64::NEG_INFINITY, 3), "-inf");
+    }
+
+    #[test]
+    fn test_strme_lossy() {
+        use crate::{stringme_lossy, strme_lossy, try_strme};
+        let x = b"ab\xffcd";
+        assert_eq!(strme_lossy(x), "ab\u{FFFD}cd");
+        assert_eq!(stringme_lossy(x), "ab\u{FFFD}cd");
+        assert!(try_strme(x).is_err());
+        assert_eq!(try_strme(b"abcd").unwrap(), "abcd");
+        assert_eq!(strme_lossy(b"ok"), "ok");
+    }
+
+    #[test]
+    fn test_truncate_visible() {
+        use crate::{truncate_visible, visible_width};
+        assert_eq!(truncate_visible("abc", 3, true), "abc");
+        assert_eq!(truncate_visible("abcdef", 4, true), "abc…");
+        assert_eq!(truncate_visible("abcdef", 4, false), "abcd");
+        assert_eq!(truncate_visible("abcdef", 0, true), "");
+        assert_eq!(truncate_visible("abcdef", 1, true), "…");
+
+        // Cut in the middle of a colored region, and after it.
+
+        let s = "ab\x1b[31mcdef\x1b[0mgh";
+        assert_eq!(truncate_visible(s, 5, true), "ab\x1b[31mcd…\x1b[0m");
+        assert_eq!(truncate_visible(s, 5, false), "ab\x1b[31mcde\x1b[0m");
+        assert_eq!(truncate_visible(s, 7, true), "ab\x1b[31mcdef\x1b[0m…");
+
+        // Cut in the middle of the two columns of a CJK character, and at a combining mark.
+
+        let s = "a漢字b";
+        assert_eq!(visible_width(s), 6);
+        assert_eq!(truncate_visible(s, 4, false), "a漢");
+        assert_eq!(truncate_visible(s, 4, true), "a漢…");
+        assert_eq!(truncate_visible(s, 3, true), "a…");
+        assert_eq!(truncate_visible(s, 2, false), "a");
+        assert_eq!(
+            truncate_visible("\x1b[32m한글\x1b[0m", 3, false),
+            "\x1b[32m한\x1b[0m"
+        );
+        let s = "ae\u{301}e\u{301}z";
+        assert_eq!(visible_width(s), 4);
+        assert_eq!(truncate_visible(s, 3, false), "ae\u{301}e\u{301}");
+        assert_eq!(truncate_visible(s, 3, true), "ae\u{301}…");
+    }
+
+    #[test]
+    fn test_abbrev_list_with_ranges() {
+        use crate::abbrev_list_with_ranges;
+        let x: [usize; 0] = [];
+        assert_eq!(abbrev_list_with_ranges(&x, false), "");
+        let x: [usize; 8] = [1, 2, 3, 5, 7, 8, 8, 10];
+        assert_eq!(abbrev_list_with_ranges(&x, false), "1-3, 5, 7-8, 8, 10");
+        let x: [i64; 6] = [-5, -4, -3, -1, 0, 1];
+        assert_eq!(abbrev_list_with_ranges(&x, false), "-5--3, -1-1");
+        assert_eq!(abbrev_list_with_ranges(&x, true), "-0x5--0x3, -0x1-0x1");
+        let x: [u32; 5] = [1, 2, 10, 11, 255];
+        assert_eq!(abbrev_list_with_ranges(&x, true), "0x1-0x2, 0xa-0xb, 0xff");
+        let x: [u8; 3] = [254, 255, 255];
+        assert_eq!(abbrev_list_with_ranges(&x, false), "254-255, 255");
+    }
+
+    #[test]
+    fn test_split_keep_delims() {
+        use crate::split_keep_delims;
+        fn piece(s: &str, d: Option<char>) -> (String, Option<char>) {
+            (s.to_string(), d)
+        }
+        let delims = ['-', '*'];
+        let x = split_keep_delims("IGHV1-69-2*01", &delims);
+        assert_eq!(
+            x,
+            vec![
+                piece("IGHV1", Some('-')),
+                piece("69", Some('-')),
+                piece("2", Some('*')),
+                piece("01", None)
+            ]
+        );
+        assert_eq!(split_keep_delims("", &delims), vec![piece("", None)]);
+        assert_eq!(
+            split_keep_delims("a-*b*", &delims),
+            vec![
+                piece("a", Some('-')),
+                piece("", Some('*')),
+                piece("b", Some('*')),
+                piece("", None)
+            ]
+        );
+        for s in ["IGHV1-69-2*01", "a-*b*", "--", "*x", "abc", "é-✅"] {
+            let mut r = String::new();
+            for (p, d) in split_keep_delims(s, &delims) {
+                r += &p;
+                r.extend(d);
+            }
+            assert_eq!(r, s);
+        }
+    }
+
+    #[test]
+    fn test_title_case() {
+        use crate::{title_case, title_case_with};
+        let s = "number_of_reads_in_cell".replace('_', " ");
+        assert_eq!(title_case(&s), "Number of Reads in Cell");
+        assert_eq!(title_case_with(&s, false), "Number Of Reads In Cell");
+        assert_eq!(title_case("the end of THE line"), "The End of the Line");
+        assert_eq!(title_case("  and  so\tforth "), "  And  So\tForth ");
+        assert_eq!(title_case("straße élan"), "Straße Élan");
+        assert_eq!(title_case("ßa"), "SSa");
+        assert_eq!(title_case(""), "");
+    }
+
+    #[test]
+    fn test_ordinal() {
+        use crate::ordinal;
+        let x = (1..=25).chain(100..=113).map(ordinal).collect::<Vec<_>>();
+        let y = [
+            "1st", "2nd", "3rd", "4th", "5th", "6th", "7th", "8th", "9th", "10th", "11th", "12th",
+            "13th", "14th", "15th", "16th", "17th", "18th", "19th", "20th", "21st", "22nd", "23rd",
+            "24th", "25th", "100th", "101st", "102nd", "103rd", "104th", "105th", "106th", "107th",
+            "108th", "109th", "110th", "111th", "112th", "113th",
+        ];
+        assert_eq!(x, y);
+        assert_eq!(ordinal(0), "0th");
+        assert_eq!(ordinal(1011), "1011th");
+        assert_eq!(ordinal(1021), "1021st");
+    }
+
+    #[test]
+    fn test_format_aligned() {
+        use crate::{format_aligned_f64, format_aligned_usize};
+        let x = format_aligned_f64(&[0.001, 2.5, 100000.0, -3.25], 3);
+        assert_eq!(x, ["     0.001", "     2.500", "100000.000", "    -3.250"]);
+        for s in x.iter() {
+            assert_eq!(s.len(), 10);
+            assert_eq!(s.find('.'), Some(6));
+        }
+        assert_eq!(format_aligned_f64(&[1.26, 10.0], 1), [" 1.3", "10.0"]);
+        assert_eq!(
+            format_aligned_usize(&[5, 1234567], true),
+            ["        5", "1,234,567"]
+        );
+        assert_eq!(
+            format_aligned_usize(&[5, 1234567], false),
+            ["      5", "1234567"]
+        );
+        assert!(format_aligned_usize(&[], true).is_empty());
+    }
+
+    #[test]
+    fn test_normalize_newlines() {
+        use crate::{detect_line_ending, normalize_newlines, LineEnding};
+        use std::borrow::Cow;
+        let s = "a,1\nb,2\n";
+        assert!(matches!(normalize_newlines(s), Cow::Borrowed(_)));
+        assert_eq!(normalize_newlines(s), s);
+        assert_eq!(normalize_newlines("a\r\nb\rc\nd\r\r\n"), "a\nb\nc\nd\n\n");
+        assert_eq!(normalize_newlines("a,17\r"), "a,17\n");
+        assert_eq!(detect_line_ending(""), LineEnding::None);
+        assert_eq!(detect_line_ending("abc"), LineEnding::None);
+        assert_eq!(detect_line_ending(s), LineEnding::Lf);
+        assert_eq!(detect_line_ending("a\r\nb\r\n"), LineEnding::CrLf);
+        assert_eq!(detect_line_ending("a\rb"), LineEnding::Cr);
+        assert_eq!(detect_line_ending("a,17\r"), LineEnding::Cr);
+        assert_eq!(detect_line_ending("a\r\nb\n"), LineEnding::Mixed);
+        assert_eq!(detect_line_ending("a\r\nb\r"), LineEnding::Mixed);
+    }
+
+    #[test]
+    fn test_find_ignore_case() {
+        use crate::{contains_ignore_case, eq_ignore_case_trimmed, find_ignore_case};
+        assert_eq!(find_ignore_case("this is Chr1", "CHR1"), Some(8));
+        assert_eq!(find_ignore_case("chr1", "chr2"), None);
+        assert_eq!(find_ignore_case("abc", ""), Some(0));
+        assert_eq!(find_ignore_case("", "a"), None);
+        assert_eq!(find_ignore_case("ab", "abc"), None);
+        assert_eq!(find_ignore_case("éÉa", "éa"), Some(2));
+        assert!(contains_ignore_case("IGHV1-69", "ighv"));
+        assert!(!contains_ignore_case("IGHV1-69", "ighj"));
+
+        // Turkish pitfalls: İ lowercases to two characters, and ı has no ASCII counterpart.
+
+        assert_eq!(find_ignore_case("İstanbul", "i"), None);
+        assert_eq!(find_ignore_case("İstanbul", "i\u{307}s"), Some(0));
+        assert!(!contains_ignore_case("kırk", "KIRK"));
+        assert!(eq_ignore_case_trimmed("  Chr1 ", "CHR1"));
+        assert!(!eq_ignore_case_trimmed("chr1", "chr10"));
+
+        // A long haystack.
+
+        let mut h = "aB".repeat(100_000);
+        h += "GeNe_X";
+        assert_eq!(find_ignore_case(&h, "gene_x"), Some(200_000));
+        assert_eq!(find_ignore_case(&h, "ABABG"), Some(199_996));
     }
-    cat
 }
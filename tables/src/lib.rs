@@ -2,7 +2,7 @@
 
 // Functions print_tabular and print_tabular_vbox for making pretty tables.  And related utilities.
 
-use io_utils::{eprintme, fail};
+use ansi_escape::emit_end_escape;
 use itertools::Itertools;
 use std::cmp::{max, min};
 use string_utils::*;
@@ -104,28 +104,147 @@ pub fn print_tabular(
 
 // ▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓
 
-// Compute the visible length of a string, counting unicode characters as width one and
-// ignoring some ASCII escape sequences.
+// Compute the visible length of a string, as for string_utils::visible_width, except that
+// the table markers \ext and \hline have width zero.
 
 pub fn visible_width(s: &str) -> usize {
     if s == "\\ext" || s == "\\hline" {
         return 0;
     }
-    let mut n = 0;
-    let mut escaped = false;
-    for c in s.chars() {
-        if escaped && c != 'm' {
-        } else if c == '' {
-            escaped = true;
-        } else if escaped && c == 'm' {
-            escaped = false;
-        } else if c == '✅' {
-            n += 2;
+    string_utils::visible_width(s)
+}
+
+pub use string_utils::register_wide_char;
+
+// ▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓
+
+// Errors found by validate_table.  Each carries the justify string so that the problem can be
+// displayed in context.
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TableError {
+    // justify starts with |
+    LeadingBar {
+        justify: String,
+    },
+    // there is a | at position pos in justify, after the last column
+    TrailingBar {
+        justify: String,
+        pos: usize,
+        ncols: usize,
+    },
+    // the number of l or r symbols in justify does not match the rows; pos is the position in
+    // justify of the first extra symbol, or the length of justify if there are too few symbols;
+    // deviant_rows lists (row index, row length) for each row whose length is not nsymbols
+    ColumnMismatch {
+        justify: String,
+        pos: usize,
+        ncols: usize,
+        nsymbols: usize,
+        deviant_rows: Vec<(usize, usize)>,
+    },
+    // two rendered tables to be concatenated have different widths
+    WidthMismatch {
+        top: usize,
+        bottom: usize,
+    },
+}
+
+impl std::fmt::Display for TableError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let caret = |f: &mut std::fmt::Formatter, justify: &str, pos: usize| {
+            writeln!(f, "justify = {justify}")?;
+            writeln!(f, "          {}^", " ".repeat(pos))
+        };
+        match self {
+            TableError::LeadingBar { justify } => {
+                writeln!(f, "justify may not start with |")?;
+                caret(f, justify, 0)
+            }
+            TableError::TrailingBar {
+                justify,
+                pos,
+                ncols,
+            } => {
+                writeln!(
+                    f,
+                    "the | at position {pos} in justify comes after the last of the {ncols} columns"
+                )?;
+                caret(f, justify, *pos)
+            }
+            TableError::ColumnMismatch {
+                justify,
+                pos,
+                ncols,
+                nsymbols,
+                deviant_rows,
+            } => {
+                writeln!(
+                    f,
+                    "your table has {ncols} columns but the number of l or r symbols in justify \
+                     is {nsymbols}"
+                )?;
+                caret(f, justify, *pos)?;
+                for (i, n) in deviant_rows.iter() {
+                    writeln!(f, "row {i} has length {n} rather than {nsymbols}")?;
+                }
+                Ok(())
+            }
+            TableError::WidthMismatch { top, bottom } => {
+                writeln!(f, "can't concatenate tables of widths {top} and {bottom}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for TableError {}
+
+// Check that the rows and justify arguments to print_tabular_vbox are consistent.  An empty table
+// is always valid.
+
+pub fn validate_table(rows: &[Vec<String>], justify: &[u8]) -> Result<(), TableError> {
+    let ncols = rows.iter().map(|r| r.len()).max().unwrap_or(0);
+    if ncols == 0 {
+        return Ok(());
+    }
+    let js = stringme_lossy(justify);
+    let mut nsymbols = 0;
+    let mut extra = None;
+    for (pos, &c) in justify.iter().enumerate() {
+        if c == b'|' {
+            if nsymbols == 0 {
+                return Err(TableError::LeadingBar { justify: js });
+            }
+            if nsymbols >= ncols {
+                return Err(TableError::TrailingBar {
+                    justify: js,
+                    pos,
+                    ncols,
+                });
+            }
         } else {
-            n += 1;
+            if nsymbols == ncols && extra.is_none() {
+                extra = Some(pos);
+            }
+            nsymbols += 1;
         }
     }
-    n
+    let deviant_rows = rows
+        .iter()
+        .enumerate()
+        .filter(|(_, r)| r.len() != nsymbols)
+        .map(|(i, r)| (i, r.len()))
+        .collect::<Vec<_>>();
+    if nsymbols != ncols || !deviant_rows.is_empty() {
+        return Err(TableError::ColumnMismatch {
+            justify: js,
+            pos: extra.unwrap_or(justify.len()),
+            ncols,
+            nsymbols,
+            deviant_rows,
+        });
+    }
+    Ok(())
 }
 
 // ▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓
@@ -163,67 +282,190 @@ pub fn print_tabular_vbox(
     debug_print: bool,
     bold_box: bool,
 ) {
+    let opt = VboxOptions {
+        debug_print,
+        bold_box,
+        ..Default::default()
+    };
+    print_tabular_vbox_opt(log, rows, sep, justify, &opt);
+}
+
+// Options for print_tabular_vbox_opt.  The default values reproduce the behavior of
+// print_tabular_vbox with debug_print and bold_box both false.
+
+#[derive(Clone, Debug, Default)]
+pub struct VboxOptions {
+    // print debugging information
+    pub debug_print: bool,
+    // use bold box characters
+    pub bold_box: bool,
+    // vertical alignment for each column, used when an entry in a row contains newlines;
+    // columns past the end of the vector are aligned to the top
+    pub valign: Vec<VAlign>,
+    // number of spaces to add on the left and right inside each entry
+    pub pad: (usize, usize),
+    // minimum width of each column, not including padding; columns past the end of the vector
+    // are unconstrained
+    pub min_widths: Vec<usize>,
+}
+
+// Vertical alignment of the entries in a row, relative to its tallest entry.
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum VAlign {
+    #[default]
+    Top,
+    Middle,
+    Bottom,
+}
+
+// Split an entry into its lines.  If an escape sequence is in effect at the end of a line, the
+// line is ended with a reset sequence, and the escape sequences in effect are repeated at the
+// start of the next line, so that each line is styled as it would have been unsplit.
+
+fn split_entry_lines(x: &str) -> Vec<String> {
+    let mut lines = Vec::<String>::new();
+    let mut active = Vec::<String>::new();
+    for part in x.split('\n') {
+        let mut line = active.concat();
+        let mut chars = part.chars();
+        while let Some(c) = chars.next() {
+            line.push(c);
+            if c == '\x1b' {
+                let mut e = c.to_string();
+                for d in chars.by_ref() {
+                    e.push(d);
+                    line.push(d);
+                    if d == 'm' {
+                        break;
+                    }
+                }
+                if e == "\x1b[0m" || e == "\x1b[m" {
+                    active.clear();
+                } else {
+                    active.push(e);
+                }
+            }
+        }
+        if !active.is_empty() {
+            line.push_str("\x1b[0m");
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+// Split each row that has an entry containing newlines into multiple rows, one per line, placing
+// shorter entries according to valign, and padding with empty entries.  \ext entries are
+// repeated on each line.  Escape sequences are carried across lines, as for split_entry_lines.
+
+fn split_multiline_rows(rows: &[Vec<String>], valign: &[VAlign]) -> Vec<Vec<String>> {
+    let mut out = Vec::<Vec<String>>::new();
+    for row in rows.iter() {
+        let lines = row.iter().map(|x| split_entry_lines(x)).collect::<Vec<_>>();
+        let height = lines.iter().map(|x| x.len()).max().unwrap_or(1);
+        if height == 1 {
+            out.push(row.clone());
+            continue;
+        }
+        for k in 0..height {
+            let mut r = Vec::<String>::new();
+            for (j, x) in lines.iter().enumerate() {
+                if row[j] == "\\ext" {
+                    r.push(row[j].clone());
+                    continue;
+                }
+                let offset = match valign.get(j).cloned().unwrap_or_default() {
+                    VAlign::Top => 0,
+                    VAlign::Middle => (height - x.len()) / 2,
+                    VAlign::Bottom => height - x.len(),
+                };
+                if k >= offset && k - offset < x.len() {
+                    r.push(x[k - offset].clone());
+                } else {
+                    r.push(String::new());
+                }
+            }
+            out.push(r);
+        }
+    }
+    out
+}
+
+// Same as print_tabular_vbox, but with options passed as a struct.  Return the width of each
+// column, not including padding, which may be passed back as min_widths for a later table.
+
+pub fn print_tabular_vbox_opt(
+    log: &mut String,
+    rows: &[Vec<String>],
+    sep: usize,
+    justify: &[u8],
+    opt: &VboxOptions,
+) -> Vec<usize> {
     // If you've added a test that fails and are trying to get it work, temporarily change
     // the next to the last entry in the print_tabular_vbox line for the test to true.
 
+    let debug_print = opt.debug_print;
+    let bold_box = opt.bold_box;
+
     // Define box characters.
 
-    let dash = if !bold_box { '─' } else { '━' };
-    let verty = if !bold_box { '│' } else { '┃' };
-    let topleft = if !bold_box { '┌' } else { '┏' };
-    let topright = if !bold_box { '┐' } else { '┓' };
-    let botleft = if !bold_box { '└' } else { '┗' };
-    let botright = if !bold_box { '┘' } else { '┛' };
-    let tee = if !bold_box { '┬' } else { '┳' };
-    let uptee = if !bold_box { '┴' } else { '┻' };
-    let cross = if !bold_box { '┼' } else { '╋' };
-    let lefty = if !bold_box { '├' } else { '┣' };
-    let righty = if !bold_box { '┤' } else { '┫' };
+    let bc = if !bold_box {
+        BoxChars::plain()
+    } else {
+        BoxChars::bold()
+    };
+    let (dash, verty, tee, uptee, cross) = (bc.dash, bc.verty, bc.tee, bc.uptee, bc.cross);
+    let (topleft, topright, botleft, botright) = (bc.topleft, bc.topright, bc.botleft, bc.botright);
+    let (lefty, righty) = (bc.lefty, bc.righty);
 
     // Proceed.
 
-    let mut rrr = rows.to_owned();
+    let mut rrr = split_multiline_rows(rows, &opt.valign);
+
+    // Add padding inside entries.  Doing this before anything else means that the padding is
+    // accounted for in the column widths, the handling of \ext entries, and the borders.  Because
+    // padding consists of spaces, adding it before justification yields the same result as adding
+    // it after.
+
+    let (lpad, rpad) = opt.pad;
+    if lpad > 0 || rpad > 0 {
+        for row in rrr.iter_mut() {
+            for x in row.iter_mut() {
+                if x != "\\ext" && x != "\\hline" {
+                    *x = format!("{}{x}{}", " ".repeat(lpad), " ".repeat(rpad));
+                }
+            }
+        }
+    }
     let nrows = rrr.len();
     let mut ncols = 0;
     for i in 0..nrows {
         ncols = max(ncols, rrr[i].len());
     }
+
+    // An empty table is rendered as an empty box.
+
+    if ncols == 0 {
+        log.push(topleft);
+        log.push(topright);
+        log.push('\n');
+        log.push(botleft);
+        log.push(botright);
+        log.push('\n');
+        return Vec::new();
+    }
+    if let Err(e) = validate_table(rows, justify) {
+        panic!("\nprint_tabular_vbox: {}", e);
+    }
     let mut vert = vec![false; ncols];
     let mut just = Vec::<u8>::new();
-    let mut count = 0_isize;
     for i in 0..justify.len() {
         if justify[i] == b'|' {
-            if count == 0 {
-                fail!("print_tabular_vbox: justify may not start with |");
-            }
-            if count >= ncols as isize {
-                eprintln!("\nposition of | in justify string is illegal");
-                eprintme!(count, ncols);
-            }
-            assert!(count < ncols as isize);
-            vert[(count - 1) as usize] = true;
+            vert[just.len() - 1] = true;
         } else {
             just.push(justify[i]);
-            count += 1;
-        }
-    }
-    if just.len() != ncols {
-        eprintln!(
-            "\nError.  Your table has {} columns but the number of \
-             l or r symbols in justify is {}.\nThese numbers should be equal.",
-            ncols,
-            just.len()
-        );
-        eprintln!("justify = {}", strme(justify));
-        for i in 0..rows.len() {
-            eprintln!(
-                "row {} = {} = {}",
-                i + 1,
-                rows[i].len(),
-                rows[i].iter().format(",")
-            );
         }
-        assert_eq!(just.len(), ncols);
     }
     let mut maxcol = vec![0; ncols];
     for i in 0..rrr.len() {
@@ -306,6 +548,20 @@ pub fn print_tabular_vbox(
         }
     }
 
+    // Require that every column have width at least one, so that a column of empty entries does
+    // not cause its vertical bars to collapse together.  Also impose the caller's minimum widths.
+
+    for j in 0..ncols {
+        let mut con = vec![false; ncols];
+        con[j] = true;
+        lhs.push(con);
+        let min_width = match opt.min_widths.get(j) {
+            Some(&w) => w + lpad + rpad,
+            None => 0,
+        };
+        rhs.push(max(min_width, 1));
+    }
+
     // Now, in a truly moronic fashion, find a solution to the linear programming problem of
     // minimizing x1 + ... + xn, subject to these constraints.
     // The solution here progressively increments the variables until all the constraints are
@@ -641,7 +897,17 @@ pub fn print_tabular_vbox(
             all.push(z);
         }
         for i in 0..all.len() {
-            mat.push(package_characters_with_escapes_char(&all[i]));
+            // Follow each wide character by an empty super-character, so that positions in the
+            // matrix correspond to visible columns.
+            let mut row = Vec::<Vec<char>>::new();
+            for x in package_characters_with_escapes_char(&all[i]) {
+                let wide = x.last().is_some_and(|&c| is_wide_char(c));
+                row.push(x);
+                if wide {
+                    row.push(Vec::new());
+                }
+            }
+            mat.push(row);
         }
     }
 
@@ -663,19 +929,27 @@ pub fn print_tabular_vbox(
     // "Smooth" edges of hlines.
 
     let verbose = debug_print;
+    let role = |x: &[char]| {
+        if x.len() == 1 {
+            classify(x[0])
+        } else {
+            BoxCharRole::Other
+        }
+    };
+    let last_role = |x: &[char]| x.last().map_or(BoxCharRole::Other, |&c| classify(c));
     for i in 0..mat.len() {
         for j in 0..mat[i].len() {
             if j > 0
-                && mat[i][j - 1] == vec![dash]
-                && mat[i][j] == vec![verty]
+                && role(&mat[i][j - 1]) == BoxCharRole::Dash
+                && role(&mat[i][j]) == BoxCharRole::Verty
                 && j + 1 < mat[i].len()
-                && mat[i][j + 1] == vec![dash]
+                && role(&mat[i][j + 1]) == BoxCharRole::Dash
                 && i + 1 < mat.len()
                 && j < mat[i + 1].len()
-                && mat[i + 1][j].ends_with(&[verty])
+                && last_role(&mat[i + 1][j]) == BoxCharRole::Verty
                 && i > 0
-                && (j >= mat[i - 1].len() || !mat[i - 1][j].ends_with(&[verty]))
-                && (j >= mat[i - 1].len() || mat[i - 1][j] != vec![tee])
+                && (j >= mat[i - 1].len() || last_role(&mat[i - 1][j]) != BoxCharRole::Verty)
+                && (j >= mat[i - 1].len() || role(&mat[i - 1][j]) != BoxCharRole::Tee)
             {
                 if verbose {
                     println!(
@@ -685,13 +959,13 @@ pub fn print_tabular_vbox(
                 }
                 mat[i][j] = vec![tee];
             } else if j > 0
-                && mat[i][j - 1] == vec![dash]
-                && mat[i][j] == vec![verty]
+                && role(&mat[i][j - 1]) == BoxCharRole::Dash
+                && role(&mat[i][j]) == BoxCharRole::Verty
                 && j + 1 < mat[i].len()
-                && mat[i][j + 1] == vec![dash]
+                && role(&mat[i][j + 1]) == BoxCharRole::Dash
                 && i + 1 < mat.len()
                 && j < mat[i + 1].len()
-                && !mat[i + 1][j].ends_with(&[verty])
+                && last_role(&mat[i + 1][j]) != BoxCharRole::Verty
             {
                 if verbose {
                     println!(
@@ -699,18 +973,19 @@ pub fn print_tabular_vbox(
                         mat[i][j][0]
                     );
                 }
-                if i == 0 || !mat[i - 1][j].ends_with(&[verty]) {
+                if i == 0 || last_role(&mat[i - 1][j]) != BoxCharRole::Verty {
                     mat[i][j] = vec![dash];
                 } else {
                     mat[i][j] = vec![uptee];
                 }
             } else if j > 0
-                && mat[i][j - 1] == vec![dash]
-                && mat[i][j] == vec![verty]
+                && role(&mat[i][j - 1]) == BoxCharRole::Dash
+                && role(&mat[i][j]) == BoxCharRole::Verty
                 && j + 1 < mat[i].len()
-                && mat[i][j + 1] == vec![dash]
+                && role(&mat[i][j + 1]) == BoxCharRole::Dash
                 && i > 0
-                && (mat[i - 1][j].ends_with(&[verty]) || mat[i - 1][j] == vec![tee])
+                && (last_role(&mat[i - 1][j]) == BoxCharRole::Verty
+                    || role(&mat[i - 1][j]) == BoxCharRole::Tee)
             {
                 if verbose {
                     println!(
@@ -719,10 +994,10 @@ pub fn print_tabular_vbox(
                     );
                 }
                 mat[i][j] = vec![cross];
-            } else if mat[i][j] == vec![verty]
+            } else if role(&mat[i][j]) == BoxCharRole::Verty
                 && j + 1 < mat[i].len()
-                && mat[i][j + 1] == vec![dash]
-                && (j == 0 || !mat[i][j - 1].ends_with(&[dash]))
+                && role(&mat[i][j + 1]) == BoxCharRole::Dash
+                && (j == 0 || last_role(&mat[i][j - 1]) != BoxCharRole::Dash)
             {
                 if verbose {
                     println!(
@@ -732,9 +1007,9 @@ pub fn print_tabular_vbox(
                 }
                 mat[i][j] = vec![lefty];
             } else if j > 0
-                && mat[i][j - 1] == vec![dash]
-                && mat[i][j] == vec![verty]
-                && (j + 1 == mat[i].len() || mat[i][j + 1] != vec![dash])
+                && role(&mat[i][j - 1]) == BoxCharRole::Dash
+                && role(&mat[i][j]) == BoxCharRole::Verty
+                && (j + 1 == mat[i].len() || role(&mat[i][j + 1]) != BoxCharRole::Dash)
             {
                 if verbose {
                     println!(
@@ -745,10 +1020,10 @@ pub fn print_tabular_vbox(
                 mat[i][j] = vec![righty];
             } else if j > 0
                 && i + 1 < mat.len()
-                && mat[i][j] == vec![tee]
+                && role(&mat[i][j]) == BoxCharRole::Tee
                 && (i + 1 >= mat.len()
                     || j >= mat[i + 1].len()
-                    || !mat[i + 1][j].ends_with(&[verty]))
+                    || last_role(&mat[i + 1][j]) != BoxCharRole::Verty)
             {
                 if verbose {
                     println!("i = {i}, j = {j}, from {} to {dash}", mat[i][j][0]);
@@ -775,6 +1050,591 @@ pub fn print_tabular_vbox(
     if debug_print {
         println!();
     }
+    maxcol
+        .iter()
+        .map(|w| w.saturating_sub(lpad + rpad))
+        .collect()
+}
+
+// ▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓
+
+// Find the width of the terminal.  The environment variable COLUMNS takes precedence.
+// Otherwise we ask the terminal attached to stdout.  If there is no terminal, return usize::MAX.
+
+pub fn terminal_width() -> usize {
+    if let Ok(c) = std::env::var("COLUMNS") {
+        if let Ok(n) = c.trim().parse::<usize>() {
+            if n > 0 {
+                return n;
+            }
+        }
+    }
+    #[cfg(unix)]
+    {
+        let mut ws: libc::winsize = unsafe { std::mem::zeroed() };
+        let r = unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut ws) };
+        if r == 0 && ws.ws_col > 0 {
+            return ws.ws_col as usize;
+        }
+    }
+    usize::MAX
+}
+
+// The rendering chosen by render_table_auto.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TableMode {
+    Vbox,
+    Plain,
+}
+
+// Render a table using print_tabular_vbox_opt, unless the result is wider than the terminal, in
+// which case render it using print_tabular.  In the latter case, \hline rows are dropped,
+// \ext entries are blanked, and vertical bars in justify are ignored.  Return the rendered table,
+// and which mode was used.
+
+pub fn render_table_auto(
+    rows: &[Vec<String>],
+    sep: usize,
+    justify: &[u8],
+    opt: &VboxOptions,
+) -> (String, TableMode) {
+    render_table_auto_with_width(rows, sep, justify, opt, terminal_width())
+}
+
+// As render_table_auto, but for the given terminal width.
+
+fn render_table_auto_with_width(
+    rows: &[Vec<String>],
+    sep: usize,
+    justify: &[u8],
+    opt: &VboxOptions,
+    terminal_width: usize,
+) -> (String, TableMode) {
+    let mut log = String::new();
+    print_tabular_vbox_opt(&mut log, rows, sep, justify, opt);
+    let width = log.lines().map(visible_width).max().unwrap_or(0);
+    if width <= terminal_width {
+        return (log, TableMode::Vbox);
+    }
+    let mut plain = Vec::<Vec<String>>::new();
+    for row in rows.iter() {
+        if is_hline_row(row) {
+            continue;
+        }
+        let mut r = row.clone();
+        for x in r.iter_mut() {
+            if x == "\\ext" || x == "\\hline" {
+                x.clear();
+            }
+        }
+        plain.push(r);
+    }
+    let just = justify
+        .iter()
+        .filter(|&&c| c != b'|')
+        .cloned()
+        .collect::<Vec<u8>>();
+    let mut log = Vec::<u8>::new();
+    print_tabular(&mut log, &plain, sep, Some(just));
+    (stringme(&log), TableMode::Plain)
+}
+
+// ▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓
+
+// Determine if a row consists entirely of \hline entries.
+
+pub fn is_hline_row(row: &[String]) -> bool {
+    !row.is_empty() && row.iter().all(|x| x == "\\hline")
+}
+
+// Prepend an index column to the rows of a table.  The first header_rows rows are treated as
+// headers and get an empty entry.  Each subsequent row gets its number, starting at start, except
+// that \hline rows get an \hline entry and are not counted.  Return the justify prefix that the
+// caller should prepend to their justify argument.
+
+pub fn number_rows(rows: &mut [Vec<String>], start: usize, header_rows: usize) -> Vec<u8> {
+    let mut n = start;
+    for (i, row) in rows.iter_mut().enumerate() {
+        let x = if is_hline_row(row) {
+            "\\hline".to_string()
+        } else if i < header_rows {
+            String::new()
+        } else {
+            n += 1;
+            (n - 1).to_string()
+        };
+        row.insert(0, x);
+    }
+    b"r|".to_vec()
+}
+
+// Highlight rows of a table.  For each row that is not an \hline row, call pred on the row index
+// and the row.  If it returns an escape sequence, wrap each entry in the row with that escape and
+// an end escape.  Entries that are \hline or \ext are left alone (so a multicolumn entry is wrapped
+// only once), as are entries that already start with an escape.
+
+pub fn highlight_rows(rows: &mut [Vec<String>], pred: impl Fn(usize, &[String]) -> Option<String>) {
+    let mut end = Vec::<u8>::new();
+    emit_end_escape(&mut end);
+    let end = stringme(&end);
+    for (i, row) in rows.iter_mut().enumerate() {
+        if is_hline_row(row) {
+            continue;
+        }
+        if let Some(escape) = pred(i, row) {
+            for x in row.iter_mut() {
+                if x == "\\hline" || x == "\\ext" || x.starts_with('') {
+                    continue;
+                }
+                *x = format!("{escape}{x}{end}");
+            }
+        }
+    }
+}
+
+// ▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓
+
+// Formats for numeric columns, for use by format_numeric_columns.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NumFormat {
+    // add commas to the integer part, e.g. 1234567.5 --> 1,234,567.5
+    Commas,
+    // round to n digits after the decimal point
+    FixedDecimals(usize),
+    // multiply by 100, round to n digits after the decimal point, and add %
+    Percent(usize),
+    // round to n significant figures, as for string_utils::format_sig
+    SigFigs(usize),
+}
+
+// Reformat numeric entries in given columns of a table.  Each spec is a column index and a format.
+// Entries that do not parse as numbers (after removing commas) are left alone, which means that
+// header rows are normally left alone.  \hline and \ext entries are skipped.
+
+pub fn format_numeric_columns(rows: &mut [Vec<String>], specs: &[(usize, NumFormat)]) {
+    for row in rows.iter_mut() {
+        for &(j, format) in specs.iter() {
+            if j >= row.len() || row[j] == "\\hline" || row[j] == "\\ext" {
+                continue;
+            }
+            let commas = row[j].contains(',');
+            let t = row[j].trim().replace(',', "");
+            let x = match t.parse::<f64>() {
+                Ok(x) if x.is_finite() && !t.contains(|c: char| c.is_ascii_alphabetic()) => x,
+                _ => continue,
+            };
+            row[j] = match format {
+                NumFormat::Commas => match t.split_once('.') {
+                    Some((_, frac)) => add_commas_f64(x, frac.len()),
+                    None => match t.parse::<i64>() {
+                        Ok(n) => add_commas_i64(n),
+                        Err(_) => add_commas_f64(x, 0),
+                    },
+                },
+                NumFormat::FixedDecimals(n) if commas => add_commas_f64(x, n),
+                NumFormat::FixedDecimals(n) => format!("{x:.n$}"),
+                NumFormat::Percent(n) => format!("{:.*}%", n, 100.0 * x),
+                NumFormat::SigFigs(n) => format_sig(x, n),
+            };
+        }
+    }
+}
+
+// ▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓
+
+// Glyphs used by format_bool_column.  If wide is set, the characters in the glyphs are
+// registered as having width two.
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BoolGlyphs {
+    pub yes: String,
+    pub no: String,
+    pub wide: bool,
+}
+
+impl BoolGlyphs {
+    pub fn check_cross() -> BoolGlyphs {
+        BoolGlyphs {
+            yes: "✅".to_string(),
+            no: "❌".to_string(),
+            wide: true,
+        }
+    }
+
+    pub fn ascii() -> BoolGlyphs {
+        BoolGlyphs {
+            yes: "Y".to_string(),
+            no: "N".to_string(),
+            wide: false,
+        }
+    }
+}
+
+impl Default for BoolGlyphs {
+    fn default() -> Self {
+        BoolGlyphs::check_cross()
+    }
+}
+
+// Replace the entries in a column that are true/false, 1/0 or yes/no (in any case) by the given
+// glyphs.  Other entries, including headers, \hline and \ext, are left alone.
+
+pub fn format_bool_column(rows: &mut [Vec<String>], col: usize, glyphs: &BoolGlyphs) {
+    if glyphs.wide {
+        for c in glyphs.yes.chars().chain(glyphs.no.chars()) {
+            if !c.is_ascii() {
+                register_wide_char(c);
+            }
+        }
+    }
+    for row in rows.iter_mut() {
+        if col >= row.len() {
+            continue;
+        }
+        let x = row[col].trim().to_ascii_lowercase();
+        if x == "true" || x == "1" || x == "yes" {
+            row[col] = glyphs.yes.clone();
+        } else if x == "false" || x == "0" || x == "no" {
+            row[col] = glyphs.no.clone();
+        }
+    }
+}
+
+// ▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓
+
+// Box drawing characters, as used by print_tabular_vbox and StreamingTable.  These are exposed so
+// that rendered tables can be post-processed or extended consistently.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BoxChars {
+    pub dash: char,
+    pub verty: char,
+    pub topleft: char,
+    pub topright: char,
+    pub botleft: char,
+    pub botright: char,
+    pub tee: char,
+    pub uptee: char,
+    pub cross: char,
+    pub lefty: char,
+    pub righty: char,
+}
+
+impl BoxChars {
+    pub fn plain() -> BoxChars {
+        BoxChars {
+            dash: '─',
+            verty: '│',
+            topleft: '┌',
+            topright: '┐',
+            botleft: '└',
+            botright: '┘',
+            tee: '┬',
+            uptee: '┴',
+            cross: '┼',
+            lefty: '├',
+            righty: '┤',
+        }
+    }
+
+    pub fn bold() -> BoxChars {
+        BoxChars {
+            dash: '━',
+            verty: '┃',
+            topleft: '┏',
+            topright: '┓',
+            botleft: '┗',
+            botright: '┛',
+            tee: '┳',
+            uptee: '┻',
+            cross: '╋',
+            lefty: '┣',
+            righty: '┫',
+        }
+    }
+
+    pub fn double() -> BoxChars {
+        BoxChars {
+            dash: '═',
+            verty: '║',
+            topleft: '╔',
+            topright: '╗',
+            botleft: '╚',
+            botright: '╝',
+            tee: '╦',
+            uptee: '╩',
+            cross: '╬',
+            lefty: '╠',
+            righty: '╣',
+        }
+    }
+
+    // Return the role of a character in this set, or Other if it is not in this set.
+
+    pub fn role(&self, c: char) -> BoxCharRole {
+        let roles = [
+            (self.dash, BoxCharRole::Dash),
+            (self.verty, BoxCharRole::Verty),
+            (self.topleft, BoxCharRole::TopLeft),
+            (self.topright, BoxCharRole::TopRight),
+            (self.botleft, BoxCharRole::BotLeft),
+            (self.botright, BoxCharRole::BotRight),
+            (self.tee, BoxCharRole::Tee),
+            (self.uptee, BoxCharRole::UpTee),
+            (self.cross, BoxCharRole::Cross),
+            (self.lefty, BoxCharRole::Lefty),
+            (self.righty, BoxCharRole::Righty),
+        ];
+        for (x, r) in roles.iter() {
+            if c == *x {
+                return *r;
+            }
+        }
+        BoxCharRole::Other
+    }
+
+    // Return the character having a given role, or a space for Other.
+
+    pub fn get(&self, role: BoxCharRole) -> char {
+        match role {
+            BoxCharRole::Dash => self.dash,
+            BoxCharRole::Verty => self.verty,
+            BoxCharRole::TopLeft => self.topleft,
+            BoxCharRole::TopRight => self.topright,
+            BoxCharRole::BotLeft => self.botleft,
+            BoxCharRole::BotRight => self.botright,
+            BoxCharRole::Tee => self.tee,
+            BoxCharRole::UpTee => self.uptee,
+            BoxCharRole::Cross => self.cross,
+            BoxCharRole::Lefty => self.lefty,
+            BoxCharRole::Righty => self.righty,
+            BoxCharRole::Other => ' ',
+        }
+    }
+}
+
+// The role of a box drawing character.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BoxCharRole {
+    Dash,
+    Verty,
+    TopLeft,
+    TopRight,
+    BotLeft,
+    BotRight,
+    Tee,
+    UpTee,
+    Cross,
+    Lefty,
+    Righty,
+    Other,
+}
+
+// Determine the role of a character, if it is in one of the box character sets.
+
+pub fn classify(c: char) -> BoxCharRole {
+    for bc in [BoxChars::plain(), BoxChars::bold(), BoxChars::double()].iter() {
+        let r = bc.role(c);
+        if r != BoxCharRole::Other {
+            return r;
+        }
+    }
+    BoxCharRole::Other
+}
+
+// Stack two tables rendered by print_tabular_vbox, which must have the same width.  The bottom
+// border of the top table and the top border of the bottom table are merged into a single line,
+// with junctions determined by which vertical lines meet it from above and below.
+
+pub fn vcat_vbox(top: &str, bottom: &str) -> Result<String, TableError> {
+    let top = top.lines().collect::<Vec<&str>>();
+    let bottom = bottom.lines().collect::<Vec<&str>>();
+    if top.is_empty() || bottom.is_empty() {
+        let mut x = String::new();
+        for line in top.iter().chain(bottom.iter()) {
+            x += &format!("{line}\n");
+        }
+        return Ok(x);
+    }
+    let a = top[top.len() - 1].chars().collect::<Vec<char>>();
+    let b = bottom[0].chars().collect::<Vec<char>>();
+    if a.len() != b.len() {
+        return Err(TableError::WidthMismatch {
+            top: a.len(),
+            bottom: b.len(),
+        });
+    }
+    let bc = [BoxChars::plain(), BoxChars::bold(), BoxChars::double()]
+        .iter()
+        .find(|bc| bc.role(a[0]) != BoxCharRole::Other)
+        .cloned()
+        .unwrap_or_else(BoxChars::plain);
+    let mut x = String::new();
+    for line in top[..top.len() - 1].iter() {
+        x += &format!("{line}\n");
+    }
+    for k in 0..a.len() {
+        let up = matches!(
+            classify(a[k]),
+            BoxCharRole::UpTee | BoxCharRole::BotLeft | BoxCharRole::BotRight
+        );
+        let down = matches!(
+            classify(b[k]),
+            BoxCharRole::Tee | BoxCharRole::TopLeft | BoxCharRole::TopRight
+        );
+        let role = if k == 0 {
+            BoxCharRole::Lefty
+        } else if k == a.len() - 1 {
+            BoxCharRole::Righty
+        } else if up && down {
+            BoxCharRole::Cross
+        } else if up {
+            BoxCharRole::UpTee
+        } else if down {
+            BoxCharRole::Tee
+        } else {
+            BoxCharRole::Dash
+        };
+        x.push(bc.get(role));
+    }
+    x.push('\n');
+    for line in bottom[1..].iter() {
+        x += &format!("{line}\n");
+    }
+    Ok(x)
+}
+
+// A table that is printed one row at a time, for use when rows become available slowly.  The
+// column widths are fixed in advance (for example, by a previous call to print_tabular_vbox_opt),
+// and the layout is the same as for print_tabular_vbox.  Entries wider than their column are
+// truncated.  \ext entries are not supported, but a row that consists entirely of \hline
+// entries is printed as a horizontal line.
+
+pub struct StreamingTable<W: std::io::Write> {
+    out: W,
+    widths: Vec<usize>,
+    just: Vec<u8>,
+    vert: Vec<bool>,
+    sep: usize,
+    chars: BoxChars,
+}
+
+impl<W: std::io::Write> StreamingTable<W> {
+    // Create a table and print its top border, followed by the header row and a horizontal line,
+    // unless the header is empty.  The justify argument is as for print_tabular_vbox.
+
+    pub fn new(
+        out: W,
+        widths: &[usize],
+        sep: usize,
+        justify: &[u8],
+        header: &[String],
+        bold_box: bool,
+    ) -> std::io::Result<StreamingTable<W>> {
+        if let Err(e) = validate_table(&[vec![String::new(); widths.len()]], justify) {
+            panic!("\nStreamingTable: {}", e);
+        }
+        let mut vert = vec![false; widths.len()];
+        let mut just = Vec::<u8>::new();
+        for &c in justify.iter() {
+            if c == b'|' {
+                vert[just.len() - 1] = true;
+            } else {
+                just.push(c);
+            }
+        }
+        let chars = if bold_box {
+            BoxChars::bold()
+        } else {
+            BoxChars::plain()
+        };
+        let mut t = StreamingTable {
+            out,
+            widths: widths.to_vec(),
+            just,
+            vert,
+            sep,
+            chars,
+        };
+        t.print_rule(chars.topleft, chars.tee, chars.topright)?;
+        if !header.is_empty() {
+            t.append_row(header)?;
+            t.print_rule(chars.lefty, chars.cross, chars.righty)?;
+        }
+        Ok(t)
+    }
+
+    fn print_rule(&mut self, left: char, mid: char, right: char) -> std::io::Result<()> {
+        let n = self.widths.len();
+        let mut x = String::new();
+        x.push(left);
+        for j in 0..n {
+            let mut w = self.widths[j];
+            if j < n - 1 {
+                w += self.sep;
+            }
+            for _ in 0..w {
+                x.push(self.chars.dash);
+            }
+            if self.vert[j] {
+                x.push(mid);
+                for _ in 0..self.sep {
+                    x.push(self.chars.dash);
+                }
+            }
+        }
+        x.push(right);
+        writeln!(self.out, "{x}")
+    }
+
+    // Print a row.  Missing entries at the end of the row are treated as empty.
+
+    pub fn append_row(&mut self, row: &[String]) -> std::io::Result<()> {
+        assert!(row.len() <= self.widths.len());
+        if is_hline_row(row) {
+            let c = self.chars;
+            return self.print_rule(c.lefty, c.cross, c.righty);
+        }
+        let n = self.widths.len();
+        let mut x = String::new();
+        x.push(self.chars.verty);
+        for j in 0..n {
+            let r = match row.get(j) {
+                Some(r) => truncate_visible(r, self.widths[j], true),
+                None => String::new(),
+            };
+            let fill = " ".repeat(self.widths[j] - visible_width(&r));
+            if self.just[j] == b'r' {
+                x += &fill;
+                x += &r;
+            } else {
+                x += &r;
+                x += &fill;
+            }
+            if j < n - 1 {
+                x += &" ".repeat(self.sep);
+            }
+            if self.vert[j] {
+                x.push(self.chars.verty);
+                x += &" ".repeat(self.sep);
+            }
+        }
+        x.push(self.chars.verty);
+        writeln!(self.out, "{x}")?;
+        self.out.flush()
+    }
+
+    // Print the bottom border and return the writer.
+
+    pub fn finish(mut self) -> std::io::Result<W> {
+        let c = self.chars;
+        self.print_rule(c.botleft, c.uptee, c.botright)?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
 }
 
 // ▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓
@@ -785,9 +1645,8 @@ mod tests {
     // run this test using:
     // cargo test -p tables test_print_tabular_vbox -- --nocapture
 
-    use crate::print_tabular_vbox;
-    use ansi_escape::{emit_bold_escape, emit_end_escape};
-    use string_utils::stringme;
+    use super::*;
+    use ansi_escape::emit_bold_escape;
 
     #[test]
     fn test_print_tabular_vbox() {
@@ -1200,4 +2059,428 @@ mod tests {
             panic!();
         }
     }
+
+    #[test]
+    fn test_number_rows() {
+        let mut rows = vec![
+            vec!["name".to_string(), "count".to_string()],
+            vec!["\\hline".to_string(); 2],
+            vec!["apple".to_string(), "3".to_string()],
+            vec!["\\hline".to_string(); 2],
+            vec!["pear".to_string(), "17".to_string()],
+        ];
+        let mut justify = number_rows(&mut rows, 1, 1);
+        justify.append(&mut b"l|r".to_vec());
+        let mut log = String::new();
+        print_tabular_vbox(&mut log, &rows, 0, &justify, false, false);
+        let answer = "┌─┬─────┬─────┐\n\
+                      │ │name │count│\n\
+                      ├─┼─────┼─────┤\n\
+                      │1│apple│    3│\n\
+                      ├─┼─────┼─────┤\n\
+                      │2│pear │   17│\n\
+                      └─┴─────┴─────┘\n";
+        assert_eq!(log, answer);
+    }
+
+    #[test]
+    fn test_highlight_rows() {
+        let mut rows = vec![
+            vec!["sample".to_string(), "status".to_string()],
+            vec!["\\hline".to_string(); 2],
+            vec!["s1".to_string(), "PASS".to_string()],
+            vec!["s2".to_string(), "FAIL".to_string()],
+            vec!["s3 and s4".to_string(), "\\ext".to_string()],
+        ];
+        highlight_rows(&mut rows, |i, row| {
+            if i > 0 && row[1] != "PASS" {
+                Some("[31m".to_string())
+            } else {
+                None
+            }
+        });
+        // Highlighting again should not double-wrap.
+        highlight_rows(&mut rows, |_, _| Some("[31m".to_string()));
+        let mut log = String::new();
+        print_tabular_vbox(&mut log, &rows, 1, b"l|l", false, false);
+        let answer = "┌───────┬───────┐\n\
+                      │[31msample[0m │ [31mstatus[0m│\n\
+                      ├───────┼───────┤\n\
+                      │[31ms1[0m     │ [31mPASS[0m  │\n\
+                      │[31ms2[0m     │ [31mFAIL[0m  │\n\
+                      │[31ms3 and s4[0m      │\n\
+                      └───────────────┘\n";
+        assert_eq!(log, answer);
+    }
+
+    #[test]
+    fn test_render_table_auto() {
+        let rows = vec![
+            vec!["name".to_string(), "count".to_string()],
+            vec!["\\hline".to_string(); 2],
+            vec!["apple".to_string(), "3".to_string()],
+        ];
+        let opt = VboxOptions::default();
+        let (log, mode) = render_table_auto_with_width(&rows, 1, b"l|r", &opt, 80);
+        assert_eq!(mode, TableMode::Vbox);
+        assert_eq!(
+            log,
+            "┌──────┬──────┐\n│name  │ count│\n├──────┼──────┤\n│apple │     3│\n└──────┴──────┘\n"
+        );
+        let (log, mode) = render_table_auto_with_width(&rows, 1, b"l|r", &opt, 12);
+        assert_eq!(mode, TableMode::Plain);
+        assert_eq!(log, "name  count\napple     3\n");
+    }
+
+    #[test]
+    fn test_degenerate_tables() {
+        // empty table
+
+        let mut log = String::new();
+        print_tabular_vbox(&mut log, &[], 0, b"", false, false);
+        assert_eq!(log, "┌┐\n└┘\n");
+
+        // column of empty entries
+
+        let rows = vec![
+            vec!["a".to_string(), "".to_string(), "b".to_string()],
+            vec!["c".to_string(), "".to_string(), "d".to_string()],
+        ];
+        let mut log = String::new();
+        print_tabular_vbox(&mut log, &rows, 0, b"l|l|l", false, false);
+        let answer = "┌─┬─┬─┐\n\
+                      │a│ │b│\n\
+                      │c│ │d│\n\
+                      └─┴─┴─┘\n";
+        assert_eq!(log, answer);
+
+        // single row and single column
+
+        let rows = vec![vec!["a".to_string()]];
+        let mut log = String::new();
+        print_tabular_vbox(&mut log, &rows, 2, b"r", false, false);
+        assert_eq!(log, "┌─┐\n│a│\n└─┘\n");
+        let rows = vec![vec!["".to_string()]];
+        let mut log = String::new();
+        print_tabular_vbox(&mut log, &rows, 0, b"l", false, false);
+        assert_eq!(log, "┌─┐\n│ │\n└─┘\n");
+    }
+
+    #[test]
+    fn test_validate_table() {
+        let rows = vec![
+            vec!["a".to_string(), "b".to_string()],
+            vec!["c".to_string()],
+        ];
+        assert_eq!(validate_table(&rows[0..1], b"l|r"), Ok(()));
+        assert_eq!(validate_table(&[], b"lll"), Ok(()));
+        assert_eq!(
+            validate_table(&rows[0..1], b"|lr"),
+            Err(TableError::LeadingBar {
+                justify: "|lr".to_string()
+            })
+        );
+        assert_eq!(
+            validate_table(&rows[0..1], b"lr|"),
+            Err(TableError::TrailingBar {
+                justify: "lr|".to_string(),
+                pos: 2,
+                ncols: 2
+            })
+        );
+        let e = validate_table(&rows, b"l|r").unwrap_err();
+        assert_eq!(
+            e,
+            TableError::ColumnMismatch {
+                justify: "l|r".to_string(),
+                pos: 3,
+                ncols: 2,
+                nsymbols: 2,
+                deviant_rows: vec![(1, 1)]
+            }
+        );
+        let e = validate_table(&rows[0..1], b"lrl").unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "your table has 2 columns but the number of l or r symbols in justify is 3\n\
+             justify = lrl\n\
+             \x20           ^\n\
+             row 0 has length 2 rather than 3\n"
+        );
+    }
+
+    #[test]
+    fn test_multiline_entries() {
+        let rows = vec![
+            vec!["name".to_string(), "pileup".to_string(), "n".to_string()],
+            vec!["\\hline".to_string(); 3],
+            vec![
+                "read1".to_string(),
+                "ACGT\n.C..\n..T.".to_string(),
+                "3".to_string(),
+            ],
+        ];
+        let answers = [
+            (
+                VAlign::Top,
+                "┌─────┬──────┬─┐\n\
+                 │name │pileup│n│\n\
+                 ├─────┼──────┼─┤\n\
+                 │read1│ACGT  │3│\n\
+                 │     │.C..  │ │\n\
+                 │     │..T.  │ │\n\
+                 └─────┴──────┴─┘\n",
+            ),
+            (
+                VAlign::Middle,
+                "┌─────┬──────┬─┐\n\
+                 │name │pileup│n│\n\
+                 ├─────┼──────┼─┤\n\
+                 │     │ACGT  │ │\n\
+                 │read1│.C..  │3│\n\
+                 │     │..T.  │ │\n\
+                 └─────┴──────┴─┘\n",
+            ),
+            (
+                VAlign::Bottom,
+                "┌─────┬──────┬─┐\n\
+                 │name │pileup│n│\n\
+                 ├─────┼──────┼─┤\n\
+                 │     │ACGT  │ │\n\
+                 │     │.C..  │ │\n\
+                 │read1│..T.  │3│\n\
+                 └─────┴──────┴─┘\n",
+            ),
+        ];
+        for (valign, answer) in answers.iter() {
+            let opt = VboxOptions {
+                valign: vec![*valign; 3],
+                ..Default::default()
+            };
+            let mut log = String::new();
+            print_tabular_vbox_opt(&mut log, &rows, 0, b"l|l|l", &opt);
+            assert_eq!(log, *answer);
+        }
+
+        // A coloured entry on two lines, in which the colour is ended on the first line and
+        // restarted on the second.
+
+        assert_eq!(
+            split_entry_lines("\x1b[01m\x1b[31mAC\nGT\x1b[0m\nTT"),
+            vec![
+                "\x1b[01m\x1b[31mAC\x1b[0m",
+                "\x1b[01m\x1b[31mGT\x1b[0m",
+                "TT"
+            ]
+        );
+        let rows = vec![vec![
+            "read1".to_string(),
+            "\x1b[31mACGT\n.C\x1b[0m.".to_string(),
+        ]];
+        let mut log = String::new();
+        print_tabular_vbox_opt(&mut log, &rows, 0, b"l|l", &VboxOptions::default());
+        assert_eq!(
+            log,
+            "┌─────┬────┐\n\
+             │read1│\x1b[31mACGT\x1b[0m│\n\
+             │     │\x1b[31m.C\x1b[0m. │\n\
+             └─────┴────┘\n"
+        );
+    }
+
+    #[test]
+    fn test_pad() {
+        let rows = vec![
+            vec!["piglet".to_string(), "\\ext".to_string(), "p".to_string()],
+            vec!["\\hline".to_string(); 3],
+            vec!["x".to_string(), "y".to_string(), "10".to_string()],
+        ];
+        let opt = VboxOptions {
+            pad: (1, 1),
+            ..Default::default()
+        };
+        let mut log = String::new();
+        print_tabular_vbox_opt(&mut log, &rows, 0, b"l|l|r", &opt);
+        let answer = "┌────────┬────┐\n\
+                      │ piglet │  p │\n\
+                      ├────┬───┼────┤\n\
+                      │ x  │ y │ 10 │\n\
+                      └────┴───┴────┘\n";
+        assert_eq!(log, answer);
+    }
+
+    #[test]
+    fn test_format_numeric_columns() {
+        let mut rows = vec![
+            vec![
+                "count".to_string(),
+                "mean".to_string(),
+                "frac".to_string(),
+                "size".to_string(),
+            ],
+            vec!["\\hline".to_string(); 4],
+            vec![
+                "1234567".to_string(),
+                "1,234.5678".to_string(),
+                "0.12345".to_string(),
+                "0.0012345".to_string(),
+            ],
+            vec![
+                "-1234".to_string(),
+                "-2.25".to_string(),
+                "1".to_string(),
+                "987654".to_string(),
+            ],
+            vec![
+                "12".to_string(),
+                "3".to_string(),
+                "n/a".to_string(),
+                "9.996".to_string(),
+            ],
+        ];
+        let specs = [
+            (0, NumFormat::Commas),
+            (1, NumFormat::FixedDecimals(2)),
+            (2, NumFormat::Percent(1)),
+            (3, NumFormat::SigFigs(3)),
+        ];
+        format_numeric_columns(&mut rows, &specs);
+        assert_eq!(rows[0], vec!["count", "mean", "frac", "size"]);
+        assert_eq!(rows[1], vec!["\\hline"; 4]);
+        assert_eq!(rows[2], vec!["1,234,567", "1,234.57", "12.3%", "0.00123"]);
+        assert_eq!(rows[3], vec!["-1,234", "-2.25", "100.0%", "988000"]);
+        assert_eq!(rows[4], vec!["12", "3.00", "n/a", "10"]);
+        let mut rows = vec![vec!["-1234567.25".to_string()], vec!["1,000".to_string()]];
+        format_numeric_columns(&mut rows, &[(0, NumFormat::Commas)]);
+        assert_eq!(rows[0][0], "-1,234,567.25");
+        assert_eq!(rows[1][0], "1,000");
+    }
+
+    #[test]
+    fn test_format_bool_column() {
+        let mut rows = vec![
+            vec!["sample".to_string(), "ok".to_string(), "note".to_string()],
+            vec!["\\hline".to_string(); 3],
+            vec!["s1".to_string(), "true".to_string(), "fine".to_string()],
+            vec!["s2".to_string(), "No".to_string(), "low".to_string()],
+            vec!["s3".to_string(), "1".to_string(), "".to_string()],
+        ];
+        let mut ascii = rows.clone();
+        format_bool_column(&mut rows, 1, &BoolGlyphs::check_cross());
+        let mut log = String::new();
+        print_tabular_vbox(&mut log, &rows, 0, b"l|l|l", false, false);
+        let answer = "┌──────┬──┬────┐\n\
+                      │sample│ok│note│\n\
+                      ├──────┼──┼────┤\n\
+                      │s1    │✅│fine│\n\
+                      │s2    │❌│low │\n\
+                      │s3    │✅│    │\n\
+                      └──────┴──┴────┘\n";
+        assert_eq!(log, answer);
+        format_bool_column(&mut ascii, 1, &BoolGlyphs::ascii());
+        assert_eq!(ascii[3], vec!["s2", "N", "low"]);
+    }
+
+    #[test]
+    fn test_min_widths() {
+        let rows = vec![
+            vec![
+                "frame".to_string(),
+                "reads".to_string(),
+                "\\ext".to_string(),
+            ],
+            vec!["\\hline".to_string(); 3],
+            vec!["1".to_string(), "10".to_string(), "7".to_string()],
+        ];
+        let mut opt = VboxOptions {
+            pad: (1, 0),
+            ..Default::default()
+        };
+        let mut log = String::new();
+        let widths = print_tabular_vbox_opt(&mut log, &rows, 0, b"l|r|r", &opt);
+        assert_eq!(widths, vec![5, 2, 1]);
+        opt.min_widths = vec![0, 4, 3];
+        let mut log = String::new();
+        let widths = print_tabular_vbox_opt(&mut log, &rows, 0, b"l|r|r", &opt);
+        let answer = "┌──────┬──────────┐\n\
+                      │ frame│ reads    │\n\
+                      ├──────┼─────┬────┤\n\
+                      │ 1    │   10│   7│\n\
+                      └──────┴─────┴────┘\n";
+        assert_eq!(log, answer);
+        assert_eq!(widths, vec![5, 4, 3]);
+    }
+
+    #[test]
+    fn test_streaming_table() {
+        let header = vec!["sample".to_string(), "reads".to_string()];
+        let mut t =
+            StreamingTable::new(Vec::<u8>::new(), &[6, 5], 1, b"l|r", &header, false).unwrap();
+        t.append_row(&["s1".to_string(), "1234".to_string()])
+            .unwrap();
+        t.append_row(&["a_long_name".to_string(), "5".to_string()])
+            .unwrap();
+        let log = stringme(&t.finish().unwrap());
+        let answer = "┌───────┬──────┐\n\
+                      │sample │ reads│\n\
+                      ├───────┼──────┤\n\
+                      │s1     │  1234│\n\
+                      │a_lon… │     5│\n\
+                      └───────┴──────┘\n";
+        assert_eq!(log, answer);
+
+        // The layout should agree with print_tabular_vbox.
+
+        let rows = vec![
+            header,
+            vec!["\\hline".to_string(); 2],
+            vec!["s1".to_string(), "1234".to_string()],
+            vec!["a_lon…".to_string(), "5".to_string()],
+        ];
+        let mut log2 = String::new();
+        print_tabular_vbox(&mut log2, &rows, 1, b"l|r", false, false);
+        assert_eq!(log, log2);
+    }
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(classify('┼'), BoxCharRole::Cross);
+        assert_eq!(classify('┻'), BoxCharRole::UpTee);
+        assert_eq!(classify('╠'), BoxCharRole::Lefty);
+        assert_eq!(classify('x'), BoxCharRole::Other);
+        let bc = BoxChars::double();
+        assert_eq!(bc.get(bc.role('╗')), '╗');
+        assert_eq!(BoxChars::plain().role('━'), BoxCharRole::Other);
+    }
+
+    #[test]
+    fn test_vcat_vbox() {
+        let rows = vec![
+            vec!["sample".to_string(), "reads".to_string()],
+            vec!["s1".to_string(), "1234".to_string()],
+        ];
+        let mut top = String::new();
+        print_tabular_vbox(&mut top, &rows, 0, b"l|r", false, false);
+        let rows = vec![vec!["a".to_string(), "b".to_string()]];
+        let mut bottom = String::new();
+        print_tabular_vbox(&mut bottom, &rows, 0, b"l|l", false, false);
+        assert_eq!(
+            vcat_vbox(&top, &bottom),
+            Err(TableError::WidthMismatch { top: 14, bottom: 5 })
+        );
+        let answer = "┌──────┬─────┐\n\
+                      │sample│reads│\n\
+                      │s1    │ 1234│\n\
+                      ├──┬───┼─────┤\n\
+                      │s1│123│12345│\n\
+                      └──┴───┴─────┘\n";
+        let rows = vec![vec![
+            "s1".to_string(),
+            "123".to_string(),
+            "12345".to_string(),
+        ]];
+        let mut bottom = String::new();
+        print_tabular_vbox(&mut bottom, &rows, 0, b"l|r|r", false, false);
+        assert_eq!(vcat_vbox(&top, &bottom).unwrap(), answer);
+    }
 }
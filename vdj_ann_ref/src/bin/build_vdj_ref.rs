@@ -83,7 +83,9 @@ use debruijn::{
     dna_string::{DnaString, DnaStringSlice},
     Mer,
 };
-use fasta_tools::load_genbank_accession;
+use fasta_tools::genbank::load_genbank_accessions;
+use fasta_tools::header::{split_fasta_header, VdjHeaderFields};
+use fasta_tools::write_fasta_record;
 use flate2::read::MultiGzDecoder;
 use perf_stats::elapsed;
 use pretty_trace::PrettyTrace;
@@ -92,7 +94,7 @@ use sha2::{Digest, Sha256};
 use std::io::copy;
 use std::io::Write;
 use std::{
-    assert, char,
+    assert,
     collections::HashMap,
     env, eprintln, format, fs,
     fs::File,
@@ -102,7 +104,7 @@ use std::{
     time::Instant,
     usize, vec, write, writeln,
 };
-use string_utils::{cap1, TextUtils};
+use string_utils::{cap1, parse_kv_map, TextUtils};
 use vector_utils::{bin_member, bin_position1_2, erase_if, next_diff12_8, unique_sort};
 
 use io_utils::{fwrite, fwriteln, open_for_read, open_for_write_new};
@@ -114,58 +116,23 @@ fn header_from_gene(
     record: &mut usize,
     source: &str,
 ) -> String {
-    let mut gene = gene.to_string();
-    if gene.ends_with(' ') {
-        gene = gene.rev_before(" ").to_string();
-    }
-    let genev = gene.as_bytes();
-    let mut xx = "None";
-    if gene == "IGHD"
-        || gene == "IGHE"
-        || gene == "IGHM"
-        || gene.starts_with("IGHG")
-        || gene.starts_with("IGHA")
-    {
-        xx = gene.after("IGH");
-    }
-    let header_tail = format!(
-        "{}{}|{}{}{}|{}|00",
-        genev[0] as char,
-        genev[1] as char,
-        genev[0] as char,
-        genev[1] as char,
-        genev[2] as char,
-        xx
-    );
     *record += 1;
-    let region_type: String;
-    if is_5utr {
-        region_type = "5'UTR".to_string();
-    } else if is_3utr {
-        region_type = "3'UTR".to_string();
-    } else if gene == "IGHD"
-        || gene == "IGHE"
-        || gene == "IGHM"
-        || gene.starts_with("IGHG")
-        || gene.starts_with("IGHA")
-    {
-        region_type = "C-REGION".to_string();
-    } else if genev[3] == b'V' {
-        region_type = "L-REGION+V-REGION".to_string();
-    } else {
-        region_type = format!("{}-REGION", genev[3] as char);
-    }
-    format!(
-        "{}|{} {}|{}|{}|{}",
-        record, gene, source, gene, region_type, header_tail
-    )
+    VdjHeaderFields::from_gene(gene, is_5utr, is_3utr, *record, source).to_string()
 }
 
+// Print a record.  A region of length zero, which write_fasta_record refuses, is reported,
+// and written as a header followed by an empty line, so that the record numbering is kept.
+
 fn print_fasta<R: Write>(out: &mut R, header: &str, seq: &DnaStringSlice, none: bool) {
     if none {
         return;
     }
-    fwriteln!(out, ">{}\n{}", header, seq.to_string());
+    if seq.len() == 0 {
+        eprintln!("warning: writing empty region {}", header);
+        fwriteln!(out, ">{}\n", header);
+        return;
+    }
+    write_fasta_record(out, header, seq.to_string().as_bytes(), 0).unwrap();
 }
 
 fn print_oriented_fasta<R: Write>(
@@ -260,20 +227,16 @@ fn parse_gtf_file(gtf: &str, demangle: &HashMap<String, String>, exons: &mut Vec
         if fields.len() < 9 {
             continue;
         }
-        let fields8: Vec<&str> = fields[8].split_terminator(';').collect();
-        if fields8.len() < 6 {
+        if fields[8].split_terminator(';').count() < 6 {
             continue;
         }
+        let attrs = parse_kv_map(fields[8], ';', ' ');
+        let attr = |key: &str| attrs.get(key).map_or(String::new(), |v| v[0].clone());
 
         // Get type of entry.  If it's called a pseudogene and the type is exon,
         // change it to CDS.
 
-        let mut biotype = String::new();
-        for i in 0..fields8.len() {
-            if fields8[i].starts_with(" gene_biotype") {
-                biotype = fields8[i].between("\"", "\"").to_string();
-            }
-        }
+        let biotype = attr("gene_biotype");
         let mut cat = fields[2];
         if biotype.contains("pseudogene") && cat == "exon" {
             cat = "CDS";
@@ -299,13 +262,7 @@ fn parse_gtf_file(gtf: &str, demangle: &HashMap<String, String>, exons: &mut Vec
 
         // Get gene name and demangle.
 
-        let mut gene = String::new();
-        for i in 0..fields8.len() {
-            if fields8[i].starts_with(" gene_name") {
-                gene = fields8[i].between("\"", "\"").to_string();
-            }
-        }
-        gene = gene.to_uppercase();
+        let mut gene = attr("gene_name").to_uppercase();
         if gene.starts_with("TCRG-C") {
             gene = format!("TRGC{}", gene.after("TCRG-C"));
         }
@@ -347,21 +304,11 @@ fn parse_gtf_file(gtf: &str, demangle: &HashMap<String, String>, exons: &mut Vec
 
         // Get transcript name.
 
-        let mut tr = String::new();
-        for i in 0..fields8.len() {
-            if fields8[i].starts_with(" transcript_name") {
-                tr = fields8[i].between("\"", "\"").to_string();
-            }
-        }
+        let tr = attr("transcript_name");
 
         // Get transcript id.
 
-        let mut trid = String::new();
-        for i in 0..fields8.len() {
-            if fields8[i].starts_with(" transcript_id") {
-                trid = fields8[i].between("\"", "\"").to_string();
-            }
-        }
+        let trid = attr("transcript_id");
 
         // Save in exons.
 
@@ -1938,20 +1885,10 @@ fn main() {
         if fields[2] != "gene" && fields[2] != "pseudogene" {
             continue;
         }
-        let fields8: Vec<&str> = fields[8].split_terminator(';').collect();
-        let (mut gene, mut gene2) = (String::new(), String::new());
-        let mut biotype = String::new();
-        for i in 0..fields8.len() {
-            if fields8[i].starts_with("Name=") {
-                gene = fields8[i].after("Name=").to_string();
-            }
-            if fields8[i].starts_with("description=") {
-                gene2 = fields8[i].after("description=").to_string();
-            }
-            if fields8[i].starts_with("biotype=") {
-                biotype = fields8[i].after("biotype=").to_string();
-            }
-        }
+        let attrs = parse_kv_map(fields[8], ';', '=');
+        let attr = |key: &str| attrs.get(key).map_or(String::new(), |v| v[0].clone());
+        let (mut gene, mut gene2) = (attr("Name"), attr("description"));
+        let biotype = attr("biotype");
 
         // Test for appropriate gene type.
         // Note that we allow V and J pseudogenes, but only by explicit inclusion.
@@ -2099,10 +2036,7 @@ fn main() {
             if rheaders.len() == all_chrs.len() {
                 break;
             }
-            let mut h = s.get(1..).unwrap().to_string();
-            if h.contains(' ') {
-                h = h.before(" ").to_string();
-            }
+            let h = split_fasta_header(s.get(1..).unwrap()).0.to_string();
             if bin_member(&all_chrs, &h) {
                 rheaders.push(h.clone());
                 using = true;
@@ -2503,6 +2437,11 @@ fn main() {
             &source,
         );
     }
+    let accs = added_genes2_source
+        .iter()
+        .map(|x| x.6.as_str())
+        .collect::<Vec<_>>();
+    let fetched = load_genbank_accessions(&accs).unwrap_or_else(|e| panic!("{}", e));
     for i in 0..added_genes2_source.len() {
         let gene = &added_genes2_source[i].0;
         let start1 = added_genes2_source[i].1;
@@ -2511,8 +2450,7 @@ fn main() {
         let stop2 = added_genes2_source[i].4;
         let fw = added_genes2_source[i].5;
         let source = &added_genes2_source[i].6;
-        let mut seq = DnaString::new();
-        load_genbank_accession(source, &mut seq);
+        let seq = &fetched[i].1;
         let seq1 = seq.slice(start1 - 1, stop1);
         let seq2 = seq.slice(start2 - 1, stop2);
         let mut seq = seq1.to_owned();
@@ -12,6 +12,7 @@
 // build_vdj_ref_exons MOUSE > filename
 
 use debruijn::dna_string::DnaString;
+use fasta_tools::header::split_fasta_header;
 use flate2::read::MultiGzDecoder;
 use pretty_trace::PrettyTrace;
 use process::Command;
@@ -26,7 +27,7 @@ use std::{
     io::{BufRead, BufReader},
     print, println, process, str, u8, usize, vec, write, writeln,
 };
-use string_utils::{cap1, strme, TextUtils};
+use string_utils::{cap1, parse_kv_map, strme, TextUtils};
 use vector_utils::{bin_member, erase_if, unique_sort};
 
 use io_utils::{fwrite, fwriteln, open_for_read, open_for_write_new};
@@ -58,20 +59,16 @@ fn parse_gtf_file(gtf: &str, demangle: &HashMap<String, String>, exons: &mut Vec
         if fields.len() < 9 {
             continue;
         }
-        let fields8: Vec<&str> = fields[8].split_terminator(';').collect();
-        if fields8.len() < 6 {
+        if fields[8].split_terminator(';').count() < 6 {
             continue;
         }
+        let attrs = parse_kv_map(fields[8], ';', ' ');
+        let attr = |key: &str| attrs.get(key).map_or(String::new(), |v| v[0].clone());
 
         // Get type of entry.  If it's called a pseudogene and the type is exon,
         // change it to CDS. [NOT]
 
-        let mut biotype = String::new();
-        for i in 0..fields8.len() {
-            if fields8[i].starts_with(" gene_biotype") {
-                biotype = fields8[i].between("\"", "\"").to_string();
-            }
-        }
+        let biotype = attr("gene_biotype");
         let cat = fields[2];
         if !biotype.starts_with("IG_") {
             continue;
@@ -88,13 +85,7 @@ fn parse_gtf_file(gtf: &str, demangle: &HashMap<String, String>, exons: &mut Vec
 
         // Get gene name and demangle.
 
-        let mut gene = "";
-        for i in 0..fields8.len() {
-            if fields8[i].starts_with(" gene_name") {
-                gene = fields8[i].between("\"", "\"");
-            }
-        }
-        let gene = gene.to_uppercase();
+        let gene = attr("gene_name").to_uppercase();
         let gene2 = demangle.get(&gene);
         if gene2.is_none() {
             continue;
@@ -138,21 +129,11 @@ fn parse_gtf_file(gtf: &str, demangle: &HashMap<String, String>, exons: &mut Vec
 
         // Get transcript name.
 
-        let mut tr = "";
-        for i in 0..fields8.len() {
-            if fields8[i].starts_with(" transcript_name") {
-                tr = fields8[i].between("\"", "\"");
-            }
-        }
+        let tr = attr("transcript_name");
 
         // Get transcript id.
 
-        let mut trid = "";
-        for i in 0..fields8.len() {
-            if fields8[i].starts_with(" transcript_id") {
-                trid = fields8[i].between("\"", "\"");
-            }
-        }
+        let trid = attr("transcript_id");
 
         // Save in exons.
 
@@ -162,13 +143,13 @@ fn parse_gtf_file(gtf: &str, demangle: &HashMap<String, String>, exons: &mut Vec
         let fw = fields[6] == "+";
         exons.push((
             gene2,
-            tr.to_string(),
+            tr,
             chr.to_string(),
             start,
             stop,
             cat.to_string(),
             fw,
-            trid.to_string(),
+            trid,
         ));
     }
     exons.sort();
@@ -707,20 +688,10 @@ fn main() {
         if fields[2] != "gene" && fields[2] != "pseudogene" {
             continue;
         }
-        let fields8: Vec<&str> = fields[8].split_terminator(';').collect();
-        let (mut gene, mut gene2) = (String::new(), String::new());
-        let mut biotype = String::new();
-        for i in 0..fields8.len() {
-            if fields8[i].starts_with("Name=") {
-                gene = fields8[i].after("Name=").to_string();
-            }
-            if fields8[i].starts_with("description=") {
-                gene2 = fields8[i].after("description=").to_string();
-            }
-            if fields8[i].starts_with("biotype=") {
-                biotype = fields8[i].after("biotype=").to_string();
-            }
-        }
+        let attrs = parse_kv_map(fields[8], ';', '=');
+        let attr = |key: &str| attrs.get(key).map_or(String::new(), |v| v[0].clone());
+        let (mut gene, mut gene2) = (attr("Name"), attr("description"));
+        let biotype = attr("biotype");
 
         // Test for appropriate gene type.
         // Note that we allow V and J pseudogenes, but only by explicit inclusion.
@@ -867,10 +838,7 @@ fn main() {
             if rheaders.len() == all_chrs.len() {
                 break;
             }
-            let mut h = s.get(1..).unwrap().to_string();
-            if h.contains(' ') {
-                h = h.before(" ").to_string();
-            }
+            let h = split_fasta_header(s.get(1..).unwrap()).0.to_string();
             if bin_member(&all_chrs, &h) {
                 rheaders.push(h.clone());
                 using = true;